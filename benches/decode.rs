@@ -0,0 +1,102 @@
+//! Compares decoding hours with fresh buffers and with a reused [HourDecoder]
+//!
+//! Run with `cargo bench --bench decode`. Besides the time per hour, it reports the number
+//! of allocations and allocated bytes per hour, counted by a wrapper around the system allocator.
+//! Most of the remaining allocations are the probability tables `lzma-rs` sets up for each file.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use dukascopy_rs::bi5::{self, HourDecoder};
+use time::macros::datetime;
+use time::{Duration, PrimitiveDateTime};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const ROUNDS: usize = 200;
+
+fn hours() -> Vec<(PrimitiveDateTime, Vec<u8>)> {
+    [6, 7, 8]
+        .into_iter()
+        .map(|hour| {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join(format!("test-resources/{hour:02}h_ticks.bi5"));
+            let start = datetime!(2020-03-12 00:00) + Duration::hours(hour);
+
+            (start, std::fs::read(path).unwrap())
+        })
+        .collect()
+}
+
+fn measure(
+    name: &str,
+    hours: &[(PrimitiveDateTime, Vec<u8>)],
+    mut decode: impl FnMut(PrimitiveDateTime, &[u8]) -> usize,
+) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let started = Instant::now();
+
+    let mut ticks = 0;
+    for _ in 0..ROUNDS {
+        for (start, bytes) in hours {
+            ticks += decode(*start, bytes);
+        }
+    }
+
+    let decoded = (ROUNDS * hours.len()) as u32;
+    println!(
+        "{name:<16} {:>10.2?} {:>8.1} allocations {:>10} KiB allocated per hour ({ticks} ticks)",
+        started.elapsed() / decoded,
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / decoded as f64,
+        (ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes) / decoded as usize / 1024,
+    );
+}
+
+fn main() {
+    let hours = hours();
+
+    // decompresses into a fresh buffer, as parse_hour used to
+    measure("fresh buffers", &hours, |start, bytes| {
+        bi5::parse_hour_raw(bytes, start).unwrap().len()
+    });
+
+    measure("parse_hour", &hours, |start, bytes| {
+        bi5::parse_hour(bytes, start, 100_000.0).unwrap().len()
+    });
+
+    let mut decoder = HourDecoder::new();
+    let mut ticks = Vec::new();
+    measure("HourDecoder", &hours, |start, bytes| {
+        decoder
+            .parse_hour_into(bytes, start, 100_000.0, &mut ticks)
+            .unwrap();
+        ticks.len()
+    });
+}
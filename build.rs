@@ -0,0 +1,31 @@
+fn main() {
+    #[cfg(feature = "liblzma")]
+    liblzma::build();
+}
+
+#[cfg(feature = "liblzma")]
+mod liblzma {
+    /// Oldest liblzma with a stable `lzma_stream` layout and the `.lzma` decoder
+    const MIN_VERSION: &str = "5.0";
+
+    /// Finds the system liblzma and compiles a probe exporting the layout of its `lzma_stream`,
+    /// which `bi5::liblzma` checks its own declaration against
+    pub fn build() {
+        println!("cargo:rerun-if-changed=csrc/lzma_layout.c");
+
+        let library = pkg_config::Config::new()
+            .atleast_version(MIN_VERSION)
+            .probe("liblzma")
+            .unwrap_or_else(|e| {
+                panic!(
+                    "the liblzma feature requires liblzma {MIN_VERSION} or newer with its headers \
+                     and pkg-config, e.g. the liblzma-dev or xz-devel package: {e}"
+                )
+            });
+
+        cc::Build::new()
+            .file("csrc/lzma_layout.c")
+            .includes(&library.include_paths)
+            .compile("dukascopy_lzma_layout");
+    }
+}
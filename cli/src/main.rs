@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use dukascopy_rs::export::{AtomicFile, CsvSink, ParquetSink};
+use dukascopy_rs::{
+    compare_sources, ArchiveManifest, DukascopyService, ExponentialBackoff, FileStatus,
+    FileSystemDataSupplier, Kind, Progress,
+};
+use time::macros::format_description;
+use time::{Date, Duration};
+
+/// Downloads historical data from Dukascopy
+#[derive(Parser)]
+#[command(name = "dukascopy", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Downloads ticks of an instrument, writing a file for each day
+    Ticks(TicksArgs),
+
+    /// Compares the ticks of a local mirror with the datafeed, exiting with an error when they differ
+    Compare(CompareArgs),
+
+    /// Verifies the files of an archive against its manifest, exiting with an error when any is missing or damaged
+    Verify(VerifyArgs),
+}
+
+#[derive(clap::Args)]
+struct TicksArgs {
+    /// Instrument to download, e.g. EURUSD or EUR/USD
+    instrument: String,
+
+    /// First day to download (inclusive), e.g. 2020-01-01
+    #[arg(long, value_parser = parse_date)]
+    from: Date,
+
+    /// Last day to download (exclusive), e.g. 2020-02-01
+    #[arg(long, value_parser = parse_date)]
+    to: Date,
+
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    /// Directory files are written to
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+
+    /// How many hours are downloaded at the same time
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+
+    /// Proxy requests are sent through, e.g. http://proxy.example.com:8080 or socks5://127.0.0.1:1080,
+    /// by default the HTTP_PROXY, HTTPS_PROXY and ALL_PROXY environment variables are used
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Downloads days again even if they were already downloaded, by default days recorded
+    /// in the manifest are skipped so an interrupted download can be resumed
+    #[arg(long)]
+    overwrite: bool,
+
+    /// File recording the downloaded days, including the ones without any ticks,
+    /// by default `manifest.txt` in the output directory
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct CompareArgs {
+    /// Instrument to compare, e.g. EURUSD or EUR/USD
+    instrument: String,
+
+    /// First day to compare (inclusive), e.g. 2020-01-01
+    #[arg(long, value_parser = parse_date)]
+    from: Date,
+
+    /// Last day to compare (exclusive), e.g. 2020-02-01
+    #[arg(long, value_parser = parse_date)]
+    to: Date,
+
+    /// Directory of the mirror, using the datafeed's layout, e.g. one written by a disk cache
+    #[arg(long)]
+    mirror: PathBuf,
+
+    /// Proxy requests are sent through, see the ticks command
+    #[arg(long)]
+    proxy: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct VerifyArgs {
+    /// Directory of the archive
+    dir: PathBuf,
+
+    /// Manifest the archive was recorded with, by default `manifest.tsv` in the archive's directory
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Format {
+    Csv,
+    Parquet,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Ticks(args) => download_ticks(args).await,
+        Command::Compare(args) => compare(args).await,
+        Command::Verify(args) => verify(args).await,
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn download_ticks(args: TicksArgs) -> Result<(), String> {
+    if args.from >= args.to {
+        return Err(String::from("--to has to be after --from"));
+    }
+    if args.concurrency == 0 {
+        return Err(String::from("--concurrency has to be at least 1"));
+    }
+
+    let mut builder = DukascopyService::builder()
+        .with_concurrency(args.concurrency)
+        .with_retry(ExponentialBackoff::default())
+        .with_progress(report_progress);
+    if let Some(proxy) = &args.proxy {
+        builder = builder.with_proxy(proxy);
+    }
+    let service = builder.build().map_err(|e| e.to_string())?;
+    let instrument = service
+        .resolve_instrument(&args.instrument)
+        .map_err(|e| e.to_string())?
+        .name;
+
+    let manifest = args
+        .manifest
+        .clone()
+        .unwrap_or_else(|| args.out.join("manifest.txt"));
+    let downloaded = read_manifest(&manifest)?;
+
+    let (mut completed, mut skipped, mut failed) = (0, 0, Vec::new());
+    let days = (args.to - args.from).whole_days();
+    for day in (0..days).map(|e| args.from + Duration::days(e)) {
+        let entry = manifest_entry(args.format, &instrument, day);
+        if downloaded.contains(&entry) && !args.overwrite {
+            skipped += 1;
+            continue;
+        }
+
+        let ticks = service.download_ticks(
+            &instrument,
+            day.midnight(),
+            (day + Duration::days(1)).midnight(),
+        );
+        let written = match args.format {
+            Format::Csv => write_csv(&csv_path(&args, &instrument, day), ticks).await,
+            Format::Parquet => ParquetSink::new(&args.out, &instrument)
+                .write_all(ticks)
+                .await
+                .map_err(|e| e.to_string()),
+        };
+
+        match written {
+            Ok(_) => {
+                record_day(&manifest, &entry)?;
+                completed += 1;
+            }
+            Err(e) => failed.push(format!("{instrument} {day}: {e}")),
+        }
+    }
+
+    eprintln!(
+        "\r\x1b[K{instrument}: {completed} days downloaded, {skipped} skipped, {} failed",
+        failed.len()
+    );
+    for error in &failed {
+        eprintln!("  {error}");
+    }
+
+    match failed.len() {
+        0 => Ok(()),
+        count => Err(format!(
+            "{count} days failed, run the command again to retry them"
+        )),
+    }
+}
+
+async fn compare(args: CompareArgs) -> Result<(), String> {
+    if args.from >= args.to {
+        return Err(String::from("--to has to be after --from"));
+    }
+
+    let mut builder = DukascopyService::builder().with_retry(ExponentialBackoff::default());
+    if let Some(proxy) = &args.proxy {
+        builder = builder.with_proxy(proxy);
+    }
+    let live = builder.build().map_err(|e| e.to_string())?;
+    let mirror = DukascopyService::builder()
+        .with_data_supplier(FileSystemDataSupplier::new(&args.mirror))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let comparison = compare_sources(
+        &mirror,
+        &live,
+        &args.instrument,
+        args.from.midnight(),
+        args.to.midnight(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for difference in &comparison.differences {
+        if difference.is_failed() {
+            let failure = |error: Option<Kind>| error.map_or(String::from("ok"), |e| e.to_string());
+            println!(
+                "{}: failed, mirror: {}, datafeed: {}",
+                difference.hour,
+                failure(difference.left_error),
+                failure(difference.right_error)
+            );
+            continue;
+        }
+        println!(
+            "{}: {} ticks in the mirror, {} in the datafeed, {} missing in the mirror, {} missing in the datafeed, {} mismatched",
+            difference.hour,
+            difference.left_ticks,
+            difference.right_ticks,
+            difference.missing_in_left,
+            difference.missing_in_right,
+            difference.mismatched_ticks
+        );
+    }
+
+    match comparison.differences.len() {
+        0 => {
+            eprintln!(
+                "{} hours compared, no differences",
+                comparison.hours_compared
+            );
+            Ok(())
+        }
+        count => {
+            let failed = comparison
+                .differences
+                .iter()
+                .filter(|e| e.is_failed())
+                .count();
+            Err(format!(
+                "{} of {} compared hours differ, {failed} hours failed",
+                count - failed,
+                comparison.hours_compared
+            ))
+        }
+    }
+}
+
+async fn verify(args: VerifyArgs) -> Result<(), String> {
+    let manifest = ArchiveManifest::new(
+        args.manifest
+            .unwrap_or_else(|| args.dir.join("manifest.tsv")),
+    );
+    if !manifest.file().exists() {
+        return Err(format!("{} doesn't exist", manifest.file().display()));
+    }
+
+    let statuses = manifest
+        .verify(&args.dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut findings = 0;
+    for (entry, status) in &statuses {
+        let finding = match status {
+            FileStatus::Valid => continue,
+            FileStatus::Missing => String::from("missing"),
+            FileStatus::Truncated { size } => {
+                format!("truncated, {size} of {} bytes", entry.size)
+            }
+            FileStatus::Altered => String::from("altered, checksum doesn't match"),
+        };
+        println!("{}: {finding}", entry.path);
+        findings += 1;
+    }
+
+    match findings {
+        0 => {
+            eprintln!("{} files verified, no findings", statuses.len());
+            Ok(())
+        }
+        count => Err(format!("{count} of {} files are damaged", statuses.len())),
+    }
+}
+
+fn report_progress(progress: &Progress) {
+    eprint!(
+        "\r\x1b[K{}: {}/{} hours, {} ticks",
+        progress.hour.date(),
+        progress.hours_completed,
+        progress.hours_total,
+        progress.ticks
+    );
+}
+
+/// Returns the line recording a downloaded day in the manifest, e.g. `EURUSD csv 2020-03-12`
+fn manifest_entry(format: Format, instrument: &str, day: Date) -> String {
+    let format = match format {
+        Format::Csv => "csv",
+        Format::Parquet => "parquet",
+    };
+    format!("{instrument} {format} {day}")
+}
+
+fn read_manifest(path: &Path) -> Result<HashSet<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content.lines().map(String::from).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashSet::new()),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+fn record_day(manifest: &Path, entry: &str) -> Result<(), String> {
+    if let Some(parent) = manifest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(manifest)
+        .map_err(|e| format!("{}: {e}", manifest.display()))?;
+    writeln!(file, "{entry}").map_err(|e| format!("{}: {e}", manifest.display()))
+}
+
+fn csv_path(args: &TicksArgs, instrument: &str, day: Date) -> PathBuf {
+    args.out.join(instrument).join(format!("{day}.csv"))
+}
+
+async fn write_csv(
+    path: &Path,
+    ticks: impl futures::Stream<Item = Result<dukascopy_rs::Tick, dukascopy_rs::Error>>,
+) -> Result<u64, String> {
+    let mut file = AtomicFile::create(path).map_err(|e| e.to_string())?;
+    let mut sink = CsvSink::new(BufWriter::new(&mut file));
+    let written = sink.write_all(ticks).await.map_err(|e| e.to_string())?;
+    sink.into_inner().map_err(|e| e.to_string())?;
+
+    file.commit().map_err(|e| e.to_string())?;
+    Ok(written)
+}
+
+fn parse_date(value: &str) -> Result<Date, String> {
+    Date::parse(value, format_description!("[year]-[month]-[day]")).map_err(|e| e.to_string())
+}
@@ -0,0 +1,19 @@
+/* Layout of lzma_stream as declared by the system's liblzma headers, compared
+ * against the Rust declaration in src/bi5/liblzma.rs by its tests */
+#include <stddef.h>
+#include <lzma.h>
+
+const size_t dukascopy_lzma_stream_layout[] = {
+    sizeof(lzma_stream),
+    offsetof(lzma_stream, next_in),
+    offsetof(lzma_stream, avail_in),
+    offsetof(lzma_stream, total_in),
+    offsetof(lzma_stream, next_out),
+    offsetof(lzma_stream, avail_out),
+    offsetof(lzma_stream, total_out),
+    offsetof(lzma_stream, allocator),
+    offsetof(lzma_stream, internal),
+    LZMA_OK,
+    LZMA_STREAM_END,
+    LZMA_FINISH,
+};
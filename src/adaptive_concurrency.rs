@@ -0,0 +1,204 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::error::Kind;
+
+/// How much longer than usual a request may take before it's considered a latency spike,
+/// so the jitter of fast requests isn't mistaken for congestion
+const LATENCY_TOLERANCE: Duration = Duration::from_secs(1);
+
+/// Adjusts how many requests are in flight to what the network and the datafeed handle,
+/// instead of a fixed [concurrency](crate::DukascopyService::with_concurrency)
+///
+/// The limit starts at `min` and follows AIMD (additive increase, multiplicative decrease):
+/// it grows by one after as many successful requests as the current limit, and is halved when
+/// a request is rate limited, fails with a server error, times out or stalls, or takes more
+/// than twice as long as the average of recent requests and at least a second longer.
+///
+/// Cloning a controller is cheap and every clone shares the same limit.
+#[derive(Clone)]
+pub struct AdaptiveConcurrency {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    permits: Semaphore,
+    min: usize,
+    max: usize,
+    state: Mutex<State>,
+}
+
+struct State {
+    limit: usize,
+    successes: usize,
+
+    /// Permits to drop once they're released, after the limit was decreased
+    to_forget: usize,
+
+    /// Exponentially weighted average of successful request durations
+    latency: Option<Duration>,
+}
+
+impl AdaptiveConcurrency {
+    /// Creates a controller keeping between `min` and `max` requests in flight,
+    /// `min` is at least 1 and `max` at least `min`
+    pub fn new(min: usize, max: usize) -> AdaptiveConcurrency {
+        let min = min.max(1);
+
+        AdaptiveConcurrency {
+            inner: Arc::new(Inner {
+                permits: Semaphore::new(min),
+                min,
+                max: max.max(min),
+                state: Mutex::new(State {
+                    limit: min,
+                    successes: 0,
+                    to_forget: 0,
+                    latency: None,
+                }),
+            }),
+        }
+    }
+
+    /// Returns how many requests are currently allowed in flight
+    pub fn limit(&self) -> usize {
+        self.inner.state.lock().unwrap().limit
+    }
+
+    /// Returns the highest limit, the service downloads that many hours ahead
+    pub(crate) fn max(&self) -> usize {
+        self.inner.max
+    }
+
+    /// Waits until a request is allowed to start, the returned permit has to be
+    /// passed to [record](AdaptiveConcurrency::record) once the request completes
+    pub(crate) async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.inner.permits.acquire().await.unwrap()
+    }
+
+    /// Adjusts the limit according to the outcome of a completed request
+    pub(crate) fn record<T>(
+        &self,
+        permit: SemaphorePermit<'_>,
+        result: &Result<T, crate::error::Error>,
+        elapsed: Duration,
+    ) {
+        let mut state = self.inner.state.lock().unwrap();
+
+        let congested = match result {
+            Ok(_) => state
+                .latency
+                .is_some_and(|e| elapsed > (e * 2).max(e + LATENCY_TOLERANCE)),
+            Err(e) => matches!(
+                e.kind,
+                Kind::RateLimited | Kind::ServerError | Kind::Timeout | Kind::Stalled
+            ),
+        };
+
+        if congested {
+            let limit = (state.limit / 2).max(self.inner.min);
+            state.to_forget += state.limit - limit;
+            state.limit = limit;
+            state.successes = 0;
+        } else if result.is_ok() {
+            state.successes += 1;
+            if state.successes >= state.limit && state.limit < self.inner.max {
+                state.limit += 1;
+                state.successes = 0;
+                match state.to_forget {
+                    0 => self.inner.permits.add_permits(1),
+                    _ => state.to_forget -= 1,
+                }
+            }
+        }
+
+        if result.is_ok() {
+            state.latency = Some(state.latency.map_or(elapsed, |e| (e * 4 + elapsed) / 5));
+        }
+
+        if state.to_forget > 0 {
+            state.to_forget -= 1;
+            permit.forget();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use super::AdaptiveConcurrency;
+    use crate::data_supplier::tests::TestResourceDataSupplier;
+    use crate::error::{Error, Kind};
+    use crate::DukascopyService;
+
+    async fn complete(controller: &AdaptiveConcurrency, result: Result<(), Error>) {
+        let permit = controller.acquire().await;
+        controller.record(permit, &result, Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn increases_additively_and_decreases_multiplicatively() {
+        let controller = AdaptiveConcurrency::new(1, 4);
+
+        for _ in 0..(1 + 2 + 3) {
+            complete(&controller, Ok(())).await;
+        }
+        assert_eq!(controller.limit(), 4);
+
+        for _ in 0..10 {
+            complete(&controller, Ok(())).await;
+        }
+        assert_eq!(controller.limit(), 4);
+
+        complete(&controller, Err(Error::new(Kind::RateLimited, "429"))).await;
+        assert_eq!(controller.limit(), 2);
+
+        // the other permit over the limit is dropped once it's released
+        complete(&controller, Err(Error::new(Kind::Decode, "corrupt"))).await;
+        assert_eq!(controller.limit(), 2);
+        assert_eq!(controller.inner.permits.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn backs_off_on_latency_spikes() {
+        let controller = AdaptiveConcurrency::new(2, 8);
+        for _ in 0..2 {
+            complete(&controller, Ok(())).await;
+        }
+        assert_eq!(controller.limit(), 3);
+
+        let permit = controller.acquire().await;
+        controller.record(permit, &Ok::<_, Error>(()), Duration::from_millis(500));
+        assert_eq!(controller.limit(), 3);
+
+        let permit = controller.acquire().await;
+        controller.record(permit, &Ok::<_, Error>(()), Duration::from_secs(2));
+        assert_eq!(controller.limit(), 2);
+    }
+
+    #[tokio::test]
+    async fn limits_requests_of_a_service() {
+        let controller = AdaptiveConcurrency::new(1, 4);
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}))
+                .with_adaptive_concurrency(controller.clone());
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(ticks.iter().all(|e| e.is_ok()));
+        assert_eq!(controller.limit(), 3);
+    }
+}
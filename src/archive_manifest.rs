@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::io::AsyncWriteExt;
+
+use crate::bi5::TICK_SIZE;
+use crate::error::io_error;
+
+/// Manifest of the files in an archive written by a [RecordingDataSupplier](crate::RecordingDataSupplier),
+/// so the archive can be audited and verified later without fetching anything again
+///
+/// The manifest is a text file with a tab-separated line for each recorded file: its path
+/// relative to the archive, source URL, fetch time, size, CRC-32 and the number of ticks
+/// (`-` for files other than hours of ticks), e.g.
+/// `EURUSD/2020/02/12/10h_ticks.bi5  https://…/10h_ticks.bi5  2023-01-02T10:00:00Z  24571  3f2a11c0  3520`.
+#[derive(Debug, Clone)]
+pub struct ArchiveManifest {
+    file: PathBuf,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// A single file recorded in an [ArchiveManifest](ArchiveManifest)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path of the file relative to the archive's directory
+    pub path: String,
+
+    /// URL the file was fetched from
+    pub url: String,
+
+    pub fetched_at: OffsetDateTime,
+
+    /// Size of the (compressed) file in bytes
+    pub size: u64,
+
+    /// CRC-32 (IEEE) of the file
+    pub crc32: u32,
+
+    /// Number of ticks in the file, None if it's not an hour of ticks or couldn't be decompressed
+    pub ticks: Option<u64>,
+}
+
+/// Status of a recorded file, as found by [ArchiveManifest::verify](ArchiveManifest::verify)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Size and checksum match the manifest
+    Valid,
+
+    /// The file doesn't exist
+    Missing,
+
+    /// The file is smaller than recorded, e.g. a write was cut short
+    Truncated {
+        /// Actual size of the file in bytes
+        size: u64,
+    },
+
+    /// The checksum (or size, when the file grew) doesn't match the manifest
+    Altered,
+}
+
+impl ManifestEntry {
+    pub(crate) fn new(path: &str, url: &str, bytes: &[u8]) -> ManifestEntry {
+        let ticks = match path.ends_with("_ticks.bi5") {
+            true => crate::bi5::decompress(bytes)
+                .ok()
+                .map(|e| (e.len() / TICK_SIZE) as u64),
+            false => None,
+        };
+
+        ManifestEntry {
+            path: path.to_string(),
+            url: url.to_string(),
+            fetched_at: OffsetDateTime::now_utc(),
+            size: bytes.len() as u64,
+            crc32: crc32(bytes),
+            ticks,
+        }
+    }
+
+    fn to_line(&self) -> Result<String, crate::error::Error> {
+        let fetched_at = self
+            .fetched_at
+            .format(&Rfc3339)
+            .map_err(|e| crate::error::Error::new(crate::Kind::InvalidArgument, e))?;
+        let ticks = self.ticks.map_or(String::from("-"), |e| e.to_string());
+
+        Ok(format!(
+            "{}\t{}\t{}\t{}\t{:08x}\t{}\n",
+            self.path, self.url, fetched_at, self.size, self.crc32, ticks
+        ))
+    }
+
+    fn parse(line: &str) -> Option<ManifestEntry> {
+        let mut fields = line.split('\t');
+        let mut next = || fields.next();
+
+        Some(ManifestEntry {
+            path: next()?.to_string(),
+            url: next()?.to_string(),
+            fetched_at: OffsetDateTime::parse(next()?, &Rfc3339).ok()?,
+            size: next()?.parse().ok()?,
+            crc32: u32::from_str_radix(next()?, 16).ok()?,
+            ticks: match next()? {
+                "-" => None,
+                ticks => Some(ticks.parse().ok()?),
+            },
+        })
+    }
+}
+
+impl ArchiveManifest {
+    /// Creates a manifest stored in a given file, which is created when needed
+    pub fn new(file: impl Into<PathBuf>) -> ArchiveManifest {
+        ArchiveManifest {
+            file: file.into(),
+            lock: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Returns the file the manifest is stored in
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Returns the recorded files, the most recent entry of each path, in the order
+    /// the paths were first recorded
+    pub async fn entries(&self) -> Result<Vec<ManifestEntry>, crate::error::Error> {
+        let content = match tokio::fs::read_to_string(&self.file).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        // lines that can't be parsed, e.g. one cut short by a crash, are ignored
+        let mut entries = Vec::<ManifestEntry>::new();
+        let mut positions = HashMap::<String, usize>::new();
+        for entry in content.lines().filter_map(ManifestEntry::parse) {
+            match positions.get(&entry.path) {
+                Some(&position) => entries[position] = entry,
+                None => {
+                    positions.insert(entry.path.clone(), entries.len());
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Checks the files of an archive stored in a given directory against the manifest
+    ///
+    /// # Returns
+    ///
+    /// * Ok - every recorded file along with its status, in the order of the manifest
+    /// * Err - when the manifest or any of the existing files can't be read
+    pub async fn verify(
+        &self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<(ManifestEntry, FileStatus)>, crate::error::Error> {
+        let mut statuses = Vec::new();
+
+        for entry in self.entries().await? {
+            let status = match tokio::fs::read(dir.as_ref().join(&entry.path)).await {
+                Ok(bytes) if (bytes.len() as u64) < entry.size => FileStatus::Truncated {
+                    size: bytes.len() as u64,
+                },
+                Ok(bytes) if bytes.len() as u64 != entry.size || crc32(&bytes) != entry.crc32 => {
+                    FileStatus::Altered
+                }
+                Ok(_) => FileStatus::Valid,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => FileStatus::Missing,
+                Err(e) => return Err(io_error(e)),
+            };
+
+            statuses.push((entry, status));
+        }
+
+        Ok(statuses)
+    }
+
+    pub(crate) async fn record(&self, entry: &ManifestEntry) -> Result<(), crate::error::Error> {
+        let line = entry.to_line()?;
+        let _guard = self.lock.lock().await;
+
+        if let Some(parent) = self.file.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file)
+            .await
+            .map_err(io_error)?;
+        file.write_all(line.as_bytes()).await.map_err(io_error)?;
+        file.flush().await.map_err(io_error)
+    }
+}
+
+/// CRC-32 as used by zip and gzip, so checksums can be compared with common tools
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, ArchiveManifest, FileStatus, ManifestEntry};
+
+    #[test]
+    fn computes_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[tokio::test]
+    async fn verifies_recorded_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = ArchiveManifest::new(dir.path().join("manifest.tsv"));
+
+        let files = [
+            ("a.bi5", b"first".as_slice()),
+            ("b.bi5", b"second"),
+            ("c.bi5", b"third"),
+            ("d.bi5", b"fourth"),
+        ];
+        for (path, bytes) in files {
+            std::fs::write(dir.path().join(path), bytes).unwrap();
+            manifest
+                .record(&ManifestEntry::new(path, "https://example.com", bytes))
+                .await
+                .unwrap();
+        }
+        assert_eq!(manifest.entries().await.unwrap().len(), 4);
+
+        // recording a path again replaces its entry
+        manifest
+            .record(&ManifestEntry::new(
+                "b.bi5",
+                "https://example.com/b",
+                b"second",
+            ))
+            .await
+            .unwrap();
+        let entries = manifest.entries().await.unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[1].url, "https://example.com/b");
+        let statuses = manifest.verify(dir.path()).await.unwrap();
+        assert!(statuses.iter().all(|(_, e)| *e == FileStatus::Valid));
+
+        std::fs::remove_file(dir.path().join("a.bi5")).unwrap();
+        std::fs::write(dir.path().join("b.bi5"), b"sec").unwrap();
+        std::fs::write(dir.path().join("c.bi5"), b"thirD").unwrap();
+
+        let statuses = manifest.verify(dir.path()).await.unwrap();
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|(entry, status)| (entry.path.as_str(), *status))
+                .collect::<Vec<_>>(),
+            [
+                ("a.bi5", FileStatus::Missing),
+                ("b.bi5", FileStatus::Truncated { size: 3 }),
+                ("c.bi5", FileStatus::Altered),
+                ("d.bi5", FileStatus::Valid),
+            ]
+        );
+    }
+}
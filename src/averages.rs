@@ -0,0 +1,187 @@
+use futures::{stream, Stream, StreamExt};
+
+use crate::{Tick, Timeframe};
+
+/// Volume and time weighted average prices over a period of time
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceAverages {
+    /// Start of the period, in the same unit as [Tick::time](Tick::time)
+    pub time: i64,
+
+    /// Average of ask and bid prices weighted by their volumes, equal to
+    /// [twap_mid](PriceAverages::twap_mid) when the period had no volume
+    pub vwap: f64,
+
+    /// Average of mid prices weighted by how long each of them was in effect
+    pub twap_mid: f64,
+
+    /// Ask and bid volumes summed up
+    pub volume: f64,
+
+    /// Number of ticks in the period
+    pub ticks: u64,
+}
+
+/// Running sums of the period being aggregated
+struct Accumulator {
+    period: i64,
+    price_volume: f64,
+    volume: f64,
+    mid_duration: f64,
+    covered_from: i64,
+    last_time: i64,
+    last_mid: f64,
+    ticks: u64,
+}
+
+impl Accumulator {
+    /// Starts a period with its first tick, the mid price preceding it (if any)
+    /// is considered to be in effect since the start of the period
+    fn open(period: i64, tick: &Tick, carried_mid: Option<f64>) -> Accumulator {
+        let (mid_duration, covered_from) = match carried_mid {
+            Some(mid) => (mid * (tick.time - period) as f64, period),
+            None => (0.0, tick.time),
+        };
+
+        let mut accumulator = Accumulator {
+            period,
+            price_volume: 0.0,
+            volume: 0.0,
+            mid_duration,
+            covered_from,
+            last_time: tick.time,
+            last_mid: tick.mid(),
+            ticks: 0,
+        };
+        accumulator.add_volume(tick);
+        accumulator
+    }
+
+    fn update(&mut self, tick: &Tick) {
+        self.mid_duration += self.last_mid * (tick.time - self.last_time) as f64;
+        self.last_time = tick.time;
+        self.last_mid = tick.mid();
+        self.add_volume(tick);
+    }
+
+    fn add_volume(&mut self, tick: &Tick) {
+        self.price_volume += tick.ask * tick.ask_volume + tick.bid * tick.bid_volume;
+        self.volume += tick.ask_volume + tick.bid_volume;
+        self.ticks += 1;
+    }
+
+    /// Closes the period, whose last mid price is considered to be in effect until its end
+    fn finish(&self, end: i64) -> PriceAverages {
+        let mid_duration = self.mid_duration + self.last_mid * (end - self.last_time) as f64;
+        let twap_mid = mid_duration / (end - self.covered_from) as f64;
+
+        PriceAverages {
+            time: self.period,
+            vwap: if self.volume > 0.0 {
+                self.price_volume / self.volume
+            } else {
+                twap_mid
+            },
+            twap_mid,
+            volume: self.volume,
+            ticks: self.ticks,
+        }
+    }
+}
+
+/// Aggregates a stream of ticks into [volume and time weighted averages](PriceAverages)
+/// over periods of a given timeframe, e.g. to evaluate the execution of orders
+///
+/// Ticks are expected to be in chronological order. Periods without any ticks
+/// produce no averages and errors are passed through as they come.
+pub fn ticks_to_averages<S>(
+    ticks: S,
+    timeframe: Timeframe,
+) -> impl Stream<Item = Result<PriceAverages, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    let ticks = Box::pin(ticks.fuse());
+    let period_end = move |e: &Accumulator| e.period + timeframe.millis();
+
+    stream::unfold(
+        (ticks, None::<Accumulator>),
+        move |(mut ticks, mut current)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let period = timeframe.period_start(tick.time);
+
+                        match current.as_mut() {
+                            Some(accumulator) if accumulator.period == period => {
+                                accumulator.update(&tick)
+                            }
+                            _ => {
+                                let carried_mid = current.as_ref().map(|e| e.last_mid);
+                                let finished = current
+                                    .replace(Accumulator::open(period, &tick, carried_mid))
+                                    .map(|e| e.finish(period_end(&e)));
+                                if let Some(finished) = finished {
+                                    return Some((Ok(finished), (ticks, current)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, current))),
+                    None => {
+                        return current
+                            .take()
+                            .map(|e| (Ok(e.finish(period_end(&e))), (ticks, None)))
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{ticks_to_averages, PriceAverages};
+    use crate::{Tick, Timeframe};
+
+    fn tick(time: i64, mid: f64, volume: f64) -> Tick {
+        Tick {
+            time,
+            ask: mid + 0.5,
+            bid: mid - 0.5,
+            ask_volume: volume,
+            bid_volume: volume,
+        }
+    }
+
+    #[test]
+    fn weights_prices_by_volume_and_time() {
+        let ticks = vec![
+            Ok(tick(0, 10.0, 1.0)),
+            Ok(tick(750, 20.0, 3.0)),
+            Ok(tick(1_500, 30.0, 0.0)),
+        ];
+
+        let averages = block_on(
+            ticks_to_averages(stream::iter(ticks), Timeframe::seconds(1))
+                .map(|e| e.unwrap())
+                .collect::<Vec<PriceAverages>>(),
+        );
+
+        assert_eq!(averages.len(), 2);
+        assert_eq!(averages[0].time, 0);
+        assert_eq!(averages[0].ticks, 2);
+        assert_eq!(averages[0].volume, 8.0);
+        assert_abs_diff_eq!(averages[0].vwap, 17.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(averages[0].twap_mid, 12.5, epsilon = 1e-9);
+
+        // the mid of 20 is in effect for the first half of the second period
+        assert_eq!(averages[1].time, 1_000);
+        assert_abs_diff_eq!(averages[1].twap_mid, 25.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(averages[1].vwap, 25.0, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::VecDeque;
+
+use futures::{stream, Stream, StreamExt};
+
+use crate::{Candle, PriceSource, Tick};
+
+/// Tolerance of comparisons in pips, so that e.g. 1.1010 - 1.1000 counts as 10 pips
+/// despite floating point errors
+const EPSILON_PIPS: f64 = 1e-6;
+
+/// Bricks of the renko chart being built
+struct Renko {
+    top: f64,
+    bottom: f64,
+    time: Option<i64>,
+    volume: f64,
+}
+
+impl Renko {
+    fn new(price: f64) -> Renko {
+        Renko {
+            top: price,
+            bottom: price,
+            time: None,
+            volume: 0.0,
+        }
+    }
+
+    /// Adds a price and queues the bricks it completes
+    fn update(
+        &mut self,
+        time: i64,
+        price: f64,
+        volume: f64,
+        brick_pips: f64,
+        pip_size: f64,
+        bricks: &mut VecDeque<Candle>,
+    ) {
+        self.time.get_or_insert(time);
+        self.volume += volume;
+
+        let brick = brick_pips * pip_size;
+        loop {
+            let (open, close) = if (price - self.top) / pip_size >= brick_pips - EPSILON_PIPS {
+                (self.top, self.top + brick)
+            } else if (self.bottom - price) / pip_size >= brick_pips - EPSILON_PIPS {
+                (self.bottom, self.bottom - brick)
+            } else {
+                return;
+            };
+
+            self.top = open.max(close);
+            self.bottom = open.min(close);
+            bricks.push_back(Candle {
+                time: self.time.take().unwrap_or(time),
+                open,
+                high: self.top,
+                low: self.bottom,
+                close,
+                volume: std::mem::take(&mut self.volume),
+            });
+        }
+    }
+}
+
+/// When [ticks_to_bars](ticks_to_bars) completes a bar
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BarThreshold {
+    /// After a given number of ticks
+    Ticks(u64),
+
+    /// After a given volume is traded
+    Volume(f64),
+
+    /// After a given notional value, i.e. price multiplied by volume, is traded
+    Notional(f64),
+}
+
+impl BarThreshold {
+    fn is_reached(&self, bar: &Candle, ticks: u64, notional: f64) -> bool {
+        match *self {
+            BarThreshold::Ticks(threshold) => ticks >= threshold,
+            BarThreshold::Volume(threshold) => bar.volume >= threshold,
+            BarThreshold::Notional(threshold) => notional >= threshold,
+        }
+    }
+}
+
+/// Aggregates a stream of ticks into bars sampled by activity rather than time,
+/// i.e. tick, volume or notional ("dollar") bars
+///
+/// A bar is completed by the tick with which it reaches the [threshold](BarThreshold),
+/// bars don't carry the excess over to the next one. Its time is the time of its first tick.
+/// Ticks are expected to be in chronological order, an incomplete bar at the end is emitted
+/// as well and errors are passed through as they come.
+pub fn ticks_to_bars<S>(
+    ticks: S,
+    threshold: BarThreshold,
+    source: PriceSource,
+) -> impl Stream<Item = Result<Candle, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    let ticks = Box::pin(ticks.fuse());
+
+    stream::unfold(
+        (ticks, None::<(Candle, u64, f64)>),
+        move |(mut ticks, mut bar)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let (price, volume) = source.price_and_volume(&tick);
+
+                        let (current, count, notional) = match bar.as_mut() {
+                            Some((current, count, notional)) => {
+                                current.update(price, volume);
+                                *count += 1;
+                                *notional += price * volume;
+                                (*current, *count, *notional)
+                            }
+                            None => *bar.insert((
+                                Candle::open(tick.time, price, volume),
+                                1,
+                                price * volume,
+                            )),
+                        };
+
+                        if threshold.is_reached(&current, count, notional) {
+                            return Some((Ok(current), (ticks, None)));
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, bar))),
+                    None => return bar.take().map(|(e, _, _)| (Ok(e), (ticks, None))),
+                }
+            }
+        },
+    )
+}
+
+/// Aggregates a stream of ticks into renko bricks of a given size
+///
+/// A brick is completed when the price moves by the brick size beyond the top or bottom
+/// of the previous brick, the first brick is measured from the first price. A single tick
+/// can complete several bricks, the volume and time of the ticks forming them go to the first one.
+///
+/// Bricks are [candles](Candle) whose time is the time of the first tick forming them
+/// and whose high and low are their top and bottom. Ticks are expected to be in chronological
+/// order, an incomplete brick at the end is dropped and errors are passed through as they come.
+///
+/// # Arguments
+///
+/// * `brick_pips` - size of a brick in pips
+/// * `pip_size` - see [Instrument::pip_size](crate::Instrument::pip_size)
+/// * `source` - side of the quote bricks are built from
+pub fn ticks_to_renko<S>(
+    ticks: S,
+    brick_pips: f64,
+    pip_size: f64,
+    source: PriceSource,
+) -> impl Stream<Item = Result<Candle, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    assert!(brick_pips > 0.0 && pip_size > 0.0);
+
+    let ticks = Box::pin(ticks.fuse());
+
+    stream::unfold(
+        (ticks, None::<Renko>, VecDeque::new()),
+        move |(mut ticks, mut renko, mut bricks)| async move {
+            loop {
+                if let Some(brick) = bricks.pop_front() {
+                    return Some((Ok(brick), (ticks, renko, bricks)));
+                }
+
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let (price, volume) = source.price_and_volume(&tick);
+                        renko.get_or_insert_with(|| Renko::new(price)).update(
+                            tick.time,
+                            price,
+                            volume,
+                            brick_pips,
+                            pip_size,
+                            &mut bricks,
+                        );
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, renko, bricks))),
+                    None => return None,
+                }
+            }
+        },
+    )
+}
+
+/// Aggregates a stream of ticks into bars whose high and low are at most a given range apart
+///
+/// A bar is completed by the first tick whose price would exceed the range, which opens
+/// the next bar. Its time is the time of its first tick. Ticks are expected to be in
+/// chronological order, an incomplete bar at the end is emitted as well and errors
+/// are passed through as they come.
+///
+/// # Arguments
+///
+/// * `range_pips` - range of a bar in pips
+/// * `pip_size` - see [Instrument::pip_size](crate::Instrument::pip_size)
+/// * `source` - side of the quote bars are built from
+pub fn ticks_to_range_bars<S>(
+    ticks: S,
+    range_pips: f64,
+    pip_size: f64,
+    source: PriceSource,
+) -> impl Stream<Item = Result<Candle, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    assert!(range_pips > 0.0 && pip_size > 0.0);
+
+    let ticks = Box::pin(ticks.fuse());
+    let fits = move |bar: &Candle, price: f64| {
+        (bar.high.max(price) - bar.low.min(price)) / pip_size <= range_pips + EPSILON_PIPS
+    };
+
+    stream::unfold(
+        (ticks, None::<Candle>),
+        move |(mut ticks, mut bar)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let (price, volume) = source.price_and_volume(&tick);
+
+                        match bar.as_mut() {
+                            Some(current) if fits(current, price) => current.update(price, volume),
+                            _ => {
+                                let finished = bar.replace(Candle::open(tick.time, price, volume));
+                                if let Some(finished) = finished {
+                                    return Some((Ok(finished), (ticks, bar)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, bar))),
+                    None => return bar.take().map(|e| (Ok(e), (ticks, None))),
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{ticks_to_bars, ticks_to_range_bars, ticks_to_renko, BarThreshold};
+    use crate::{Candle, PriceSource, Tick};
+
+    fn ticks(bids: &[f64]) -> Vec<Result<Tick, crate::error::Error>> {
+        bids.iter()
+            .enumerate()
+            .map(|(time, &bid)| {
+                Ok(Tick {
+                    time: time as i64,
+                    ask: bid + 0.0001,
+                    bid,
+                    ask_volume: 1.0,
+                    bid_volume: 1.0,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn builds_renko_bricks() {
+        let ticks = ticks(&[1.1000, 1.1006, 1.1010, 1.1025, 1.1012, 1.1000, 1.0995]);
+
+        let bricks = block_on(
+            ticks_to_renko(stream::iter(ticks), 10.0, 0.0001, PriceSource::Bid)
+                .map(|e| e.unwrap())
+                .collect::<Vec<Candle>>(),
+        );
+
+        let prices = bricks
+            .iter()
+            .map(|e| (e.time, e.open, e.close, e.volume))
+            .collect::<Vec<_>>();
+        let expected = [
+            (0, 1.1000, 1.1010, 3.0),
+            (3, 1.1010, 1.1020, 1.0),
+            (4, 1.1010, 1.1000, 2.0),
+        ];
+
+        assert_eq!(prices.len(), expected.len());
+        for (brick, expected) in prices.iter().zip(expected) {
+            assert_eq!(brick.0, expected.0);
+            assert_abs_diff_eq!(brick.1, expected.1, epsilon = 1e-9);
+            assert_abs_diff_eq!(brick.2, expected.2, epsilon = 1e-9);
+            assert_eq!(brick.3, expected.3);
+        }
+    }
+
+    #[test]
+    fn builds_range_bars() {
+        let ticks = ticks(&[1.1000, 1.1006, 1.0998, 1.1008, 1.1009, 1.1000]);
+
+        let bars = block_on(
+            ticks_to_range_bars(stream::iter(ticks), 10.0, 0.0001, PriceSource::Bid)
+                .map(|e| e.unwrap())
+                .collect::<Vec<Candle>>(),
+        );
+
+        assert_eq!(bars.iter().map(|e| e.time).collect::<Vec<_>>(), vec![0, 4]);
+        assert_eq!(bars[0].high, 1.1008);
+        assert_eq!(bars[0].low, 1.0998);
+        assert_eq!(bars[0].close, 1.1008);
+        assert_eq!(bars[1].open, 1.1009);
+        assert_eq!(bars[1].close, 1.1000);
+    }
+
+    fn bar_times(threshold: BarThreshold) -> Vec<i64> {
+        let ticks = ticks(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        block_on(
+            ticks_to_bars(stream::iter(ticks), threshold, PriceSource::Bid)
+                .map(|e| e.unwrap().time)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn builds_tick_volume_and_notional_bars() {
+        assert_eq!(bar_times(BarThreshold::Ticks(2)), vec![0, 2, 4]);
+        assert_eq!(bar_times(BarThreshold::Volume(3.0)), vec![0, 3]);
+        assert_eq!(bar_times(BarThreshold::Notional(4.0)), vec![0, 3, 4]);
+    }
+}
@@ -0,0 +1,488 @@
+//! Decoding and encoding of the `.bi5` files served by the datafeed, without any networking
+//!
+//! Use these functions to read files mirrored locally, the [DukascopyService](crate::DukascopyService)
+//! uses the same ones for the files it downloads.
+
+#[cfg(feature = "liblzma")]
+mod liblzma;
+
+use std::cell::RefCell;
+
+use byteorder::{BigEndian, ByteOrder};
+use lzma_rs::lzma_compress;
+use time::{Date, OffsetDateTime, PrimitiveDateTime};
+
+use crate::error::Kind;
+use crate::{Candle, RawTick, Tick};
+
+/// Size of a single tick record in a decompressed hour file
+pub(crate) const TICK_SIZE: usize = 20;
+
+/// Size of a single candle record in a decompressed candle file
+const CANDLE_SIZE: usize = 24;
+
+thread_local! {
+    /// Decoder [parse_hour](parse_hour) reuses, so that decoding an hour doesn't allocate
+    /// a fresh buffer for the decompressed bytes
+    static DECODER: RefCell<HourDecoder> = RefCell::new(HourDecoder::new());
+}
+
+/// Decompresses a `.bi5` file, an empty file is decompressed to no bytes
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, crate::error::Error> {
+    let mut buf = Vec::<u8>::new();
+    decompress_into(bytes, &mut buf)?;
+
+    Ok(buf)
+}
+
+/// Decodes a compressed hour of ticks, e.g. `EURUSD/2020/02/12/10h_ticks.bi5`
+///
+/// # Arguments
+///
+/// * `bytes` - contents of the file
+/// * `hour_start` - UTC hour the file contains ticks of
+/// * `decimal_factor` - factor prices are divided by, see [Instrument::decimal_factor](crate::Instrument::decimal_factor)
+pub fn parse_hour(
+    bytes: &[u8],
+    hour_start: PrimitiveDateTime,
+    decimal_factor: f64,
+) -> Result<Vec<Tick>, crate::error::Error> {
+    let mut ticks = Vec::new();
+    DECODER.with(|e| {
+        e.borrow_mut()
+            .parse_hour_into(bytes, hour_start, decimal_factor, &mut ticks)
+    })?;
+
+    Ok(ticks)
+}
+
+/// Same as [parse_hour](parse_hour), but decompresses the file incrementally and yields
+/// ticks as soon as they're decompressed, instead of decompressing the whole file first
+///
+/// Memory used doesn't depend on the size of the file, which helps with unusually large
+/// hours. After an error, e.g. when the file is truncated, the iterator ends.
+#[cfg(feature = "liblzma")]
+pub fn parse_hour_incremental(
+    bytes: &[u8],
+    hour_start: PrimitiveDateTime,
+    decimal_factor: f64,
+) -> impl Iterator<Item = Result<Tick, crate::error::Error>> + '_ {
+    let millis_since_epoch = hour_start.assume_utc().unix_timestamp() * 1000;
+    let records = liblzma::TickRecords::new(bytes);
+    let (records, error) = match records {
+        Ok(records) => (Some(records), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    error
+        .into_iter()
+        .map(Err)
+        .chain(records.into_iter().flatten())
+        .map(move |e| {
+            e.map(|e| read_tick(&e, millis_since_epoch).to_tick(decimal_factor))
+                .map_err(|e| e.with_hour(hour_start))
+        })
+}
+
+/// Same as [parse_hour](parse_hour), but prices are kept as the integers stored in the file,
+/// see [RawTick](RawTick)
+pub fn parse_hour_raw(
+    bytes: &[u8],
+    hour_start: PrimitiveDateTime,
+) -> Result<Vec<RawTick>, crate::error::Error> {
+    let buf = decompress_records(bytes, TICK_SIZE).map_err(|e| e.with_hour(hour_start))?;
+
+    let millis_since_epoch = hour_start.assume_utc().unix_timestamp() * 1000;
+    Ok(buf
+        .chunks(TICK_SIZE)
+        .map(|e| read_tick(e, millis_since_epoch))
+        .collect())
+}
+
+/// Decodes hours of ticks like [parse_hour](parse_hour), but reuses its buffers between
+/// hours, e.g. to keep long backfills from churning the allocator
+///
+/// The buffers grow to the size of the largest hour decoded and are kept until
+/// the decoder is dropped.
+#[derive(Debug, Default)]
+pub struct HourDecoder {
+    buf: Vec<u8>,
+}
+
+impl HourDecoder {
+    pub fn new() -> HourDecoder {
+        HourDecoder::default()
+    }
+
+    /// Decodes a compressed hour of ticks into `ticks`, replacing its contents
+    /// and reusing its capacity
+    ///
+    /// See [parse_hour](parse_hour) for the arguments. On error, the contents
+    /// of `ticks` are unspecified.
+    pub fn parse_hour_into(
+        &mut self,
+        bytes: &[u8],
+        hour_start: PrimitiveDateTime,
+        decimal_factor: f64,
+        ticks: &mut Vec<Tick>,
+    ) -> Result<(), crate::error::Error> {
+        decompress_into(bytes, &mut self.buf)
+            .and_then(|_| check_record_size(&self.buf, TICK_SIZE))
+            .map_err(|e| e.with_hour(hour_start))?;
+
+        let millis_since_epoch = hour_start.assume_utc().unix_timestamp() * 1000;
+        ticks.clear();
+        ticks.extend(
+            self.buf
+                .chunks(TICK_SIZE)
+                .map(|e| read_tick(e, millis_since_epoch).to_tick(decimal_factor)),
+        );
+
+        Ok(())
+    }
+}
+
+/// Decompresses a `.bi5` file into a buffer, replacing its contents
+fn decompress_into(bytes: &[u8], buf: &mut Vec<u8>) -> Result<(), crate::error::Error> {
+    buf.clear();
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    #[cfg(feature = "liblzma")]
+    return liblzma::decompress_into(bytes, buf);
+
+    #[cfg(not(feature = "liblzma"))]
+    lzma_rs::lzma_decompress(&mut &bytes[..], buf).map_err(|e| decode_error(Box::new(e)))
+}
+
+fn read_tick(record: &[u8], millis_since_epoch: i64) -> RawTick {
+    RawTick {
+        time: millis_since_epoch + BigEndian::read_u32(&record[0..4]) as i64,
+        ask: BigEndian::read_u32(&record[4..8]),
+        bid: BigEndian::read_u32(&record[8..12]),
+        ask_volume: BigEndian::read_f32(&record[12..16]),
+        bid_volume: BigEndian::read_f32(&record[16..20]),
+    }
+}
+
+/// Checks that decoded ticks are plausible, i.e. within the hour they were decoded for,
+/// in chronological order and with positive prices
+///
+/// Since ticks of each hour have to be within it, hours checked one by one are
+/// in chronological order across hours as well.
+///
+/// # Returned value
+///
+/// * Ok - when all ticks are plausible
+/// * Err - of [Decode](crate::Kind::Decode) kind, describing the first implausible tick
+pub fn validate_hour(
+    ticks: &[Tick],
+    hour_start: PrimitiveDateTime,
+) -> Result<(), crate::error::Error> {
+    let millis_since_epoch = hour_start.assume_utc().unix_timestamp() * 1000;
+    let hour = millis_since_epoch..millis_since_epoch + 3_600_000;
+    let mut previous = millis_since_epoch;
+
+    for tick in ticks {
+        let problem = if !hour.contains(&tick.time) {
+            "is outside of the hour"
+        } else if tick.time < previous {
+            "is earlier than the tick preceding it"
+        } else if !(tick.ask > 0.0 && tick.bid > 0.0) {
+            "has a non-positive price"
+        } else {
+            previous = tick.time;
+            continue;
+        };
+
+        let message = format!("tick at {} {problem}", tick.time);
+        return Err(decode_error(message.into()).with_hour(hour_start));
+    }
+
+    Ok(())
+}
+
+/// Encodes an hour of ticks into a compressed file, the inverse of [parse_hour](parse_hour)
+///
+/// Prices are rounded to the nearest multiple of `1 / decimal_factor` and volumes are
+/// stored with single precision, so the round trip is exact only for data that came
+/// from a `.bi5` file in the first place. An empty slice is encoded as an empty file,
+/// the same way the datafeed serves hours without any ticks.
+///
+/// # Arguments
+///
+/// * `ticks` - ticks in chronological order, all within the hour starting at `hour_start`
+/// * `hour_start` - UTC hour the ticks belong to
+/// * `decimal_factor` - factor prices are multiplied by, see [Instrument::decimal_factor](crate::Instrument::decimal_factor)
+pub fn encode_hour(
+    ticks: &[Tick],
+    hour_start: PrimitiveDateTime,
+    decimal_factor: f64,
+) -> Result<Vec<u8>, crate::error::Error> {
+    let mut compressed = Vec::<u8>::new();
+    if ticks.is_empty() {
+        return Ok(compressed);
+    }
+
+    let millis_since_epoch = hour_start.assume_utc().unix_timestamp() * 1000;
+    let mut buf = vec![0u8; ticks.len() * TICK_SIZE];
+    for (tick, e) in ticks.iter().zip(buf.chunks_mut(TICK_SIZE)) {
+        let offset = tick.time - millis_since_epoch;
+        if !(0..3_600_000).contains(&offset) {
+            return Err(crate::error::Error::new(
+                Kind::InvalidArgument,
+                format!(
+                    "tick at {} is outside of the hour starting at {hour_start}",
+                    tick.time
+                ),
+            ));
+        }
+
+        BigEndian::write_u32(&mut e[0..4], offset as u32);
+        BigEndian::write_u32(&mut e[4..8], encode_price(tick.ask, decimal_factor)?);
+        BigEndian::write_u32(&mut e[8..12], encode_price(tick.bid, decimal_factor)?);
+        BigEndian::write_f32(&mut e[12..16], tick.ask_volume as f32);
+        BigEndian::write_f32(&mut e[16..20], tick.bid_volume as f32);
+    }
+
+    lzma_compress(&mut &buf[..], &mut compressed).map_err(crate::error::io_error)?;
+    Ok(compressed)
+}
+
+/// Decodes a compressed day of one minute candles, e.g. `EURUSD/2020/02/12/BID_candles_min_1.bi5`
+///
+/// # Arguments
+///
+/// * `bytes` - contents of the file
+/// * `day` - UTC day the file contains candles of
+/// * `decimal_factor` - factor prices are divided by, see [Instrument::decimal_factor](crate::Instrument::decimal_factor)
+pub fn parse_minute_candles(
+    bytes: &[u8],
+    day: Date,
+    decimal_factor: f64,
+) -> Result<Vec<Candle>, crate::error::Error> {
+    let buf = decompress_records(bytes, CANDLE_SIZE).map_err(|e| e.with_hour(day.midnight()))?;
+
+    let millis_since_epoch = day.midnight().assume_utc().unix_timestamp() * 1000;
+    Ok(buf
+        .chunks(CANDLE_SIZE)
+        .map(|e| Candle {
+            time: millis_since_epoch + BigEndian::read_u32(&e[0..4]) as i64 * 1000,
+            open: BigEndian::read_u32(&e[4..8]) as f64 / decimal_factor,
+            close: BigEndian::read_u32(&e[8..12]) as f64 / decimal_factor,
+            low: BigEndian::read_u32(&e[12..16]) as f64 / decimal_factor,
+            high: BigEndian::read_u32(&e[16..20]) as f64 / decimal_factor,
+            volume: BigEndian::read_f32(&e[20..24]) as f64,
+        })
+        .collect())
+}
+
+/// Decodes an instrument's `metadata/HistoryStart.bi5` file into the earliest UTC time with data
+pub fn parse_history_start(bytes: &[u8]) -> Result<PrimitiveDateTime, crate::error::Error> {
+    if bytes.len() < 8 {
+        return Err(decode_error(
+            "history start has to be at least 8 bytes long".into(),
+        ));
+    }
+
+    let millis_since_epoch = BigEndian::read_i64(&bytes[0..8]);
+    OffsetDateTime::from_unix_timestamp_nanos(millis_since_epoch as i128 * 1_000_000)
+        .map(|e| PrimitiveDateTime::new(e.date(), e.time()))
+        .map_err(|e| decode_error(Box::new(e)))
+}
+
+fn encode_price(price: f64, decimal_factor: f64) -> Result<u32, crate::error::Error> {
+    let scaled = (price * decimal_factor).round();
+    if !(0.0..=u32::MAX as f64).contains(&scaled) {
+        return Err(crate::error::Error::new(
+            Kind::InvalidArgument,
+            format!("price {price} can't be encoded with a decimal factor of {decimal_factor}"),
+        ));
+    }
+
+    Ok(scaled as u32)
+}
+
+/// Decompresses a `.bi5` file whose contents are records of a given size
+fn decompress_records(bytes: &[u8], record_size: usize) -> Result<Vec<u8>, crate::error::Error> {
+    let buf = decompress(bytes)?;
+    check_record_size(&buf, record_size)?;
+
+    Ok(buf)
+}
+
+fn check_record_size(buf: &[u8], record_size: usize) -> Result<(), crate::error::Error> {
+    if !buf.len().is_multiple_of(record_size) {
+        return Err(decode_error(
+            format!(
+                "decompressed size {} is not a multiple of {record_size} bytes",
+                buf.len()
+            )
+            .into(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn decode_error(inner: crate::error::BoxError) -> crate::error::Error {
+    crate::error::Error::new(Kind::Decode, inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lzma_rs::lzma_compress;
+    use time::macros::datetime;
+
+    use super::{encode_hour, parse_hour, parse_hour_raw, validate_hour, HourDecoder};
+    use crate::{Kind, Tick};
+
+    #[test]
+    fn parses_hour_files() {
+        let bytes = std::fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/06h_ticks.bi5"),
+        )
+        .unwrap();
+
+        let ticks = parse_hour(&bytes, datetime!(2020-03-12 06:00), 100_000.0).unwrap();
+
+        assert!(!ticks.is_empty());
+        assert!(ticks.windows(2).all(|e| e[0].time <= e[1].time));
+        assert!(ticks
+            .iter()
+            .all(|e| e.datetime().unwrap().hour() == 6 && e.bid > 0.0 && e.ask >= e.bid));
+        assert!(parse_hour(&[], datetime!(2020-03-12 06:00), 100_000.0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "liblzma")]
+    #[test]
+    fn parses_hour_files_incrementally() {
+        let bytes = std::fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/08h_ticks.bi5"),
+        )
+        .unwrap();
+        let hour_start = datetime!(2020-03-12 08:00);
+
+        let ticks = super::parse_hour_incremental(&bytes, hour_start, 100_000.0)
+            .collect::<Result<Vec<Tick>, _>>()
+            .unwrap();
+
+        assert_eq!(ticks, parse_hour(&bytes, hour_start, 100_000.0).unwrap());
+        let truncated = super::parse_hour_incremental(&bytes[..100], hour_start, 100_000.0)
+            .collect::<Result<Vec<Tick>, _>>();
+        assert_eq!(truncated.unwrap_err().kind, Kind::Decode);
+    }
+
+    #[test]
+    fn keeps_raw_prices_exact() {
+        let bytes = std::fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/06h_ticks.bi5"),
+        )
+        .unwrap();
+
+        let raw = parse_hour_raw(&bytes, datetime!(2020-03-12 06:00)).unwrap();
+        let ticks = parse_hour(&bytes, datetime!(2020-03-12 06:00), 100_000.0).unwrap();
+
+        assert_eq!(raw.len(), ticks.len());
+        assert_eq!(raw[0].to_tick(100_000.0), ticks[0]);
+        assert_eq!(raw[0].ask, (ticks[0].ask * 100_000.0).round() as u32);
+    }
+
+    #[test]
+    fn reuses_buffers_between_hours() {
+        let mut decoder = HourDecoder::new();
+        let mut ticks = Vec::new();
+
+        for hour in [6, 7, 8] {
+            let bytes = std::fs::read(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join(format!("test-resources/{hour:02}h_ticks.bi5")),
+            )
+            .unwrap();
+            let hour_start = datetime!(2020-03-12 00:00) + time::Duration::hours(hour);
+
+            decoder
+                .parse_hour_into(&bytes, hour_start, 100_000.0, &mut ticks)
+                .unwrap();
+
+            assert_eq!(ticks, parse_hour(&bytes, hour_start, 100_000.0).unwrap());
+        }
+    }
+
+    #[test]
+    fn validates_decoded_ticks() {
+        let hour_start = datetime!(2020-03-12 06:00);
+        let millis_since_epoch = hour_start.assume_utc().unix_timestamp() * 1000;
+        let tick = |offset: i64, bid: f64| Tick {
+            time: millis_since_epoch + offset,
+            ask: bid + 0.0001,
+            bid,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+
+        assert!(validate_hour(&[tick(0, 1.1), tick(0, 1.1), tick(5, 1.2)], hour_start).is_ok());
+
+        for ticks in [
+            [tick(5, 1.1), tick(0, 1.1)],
+            [tick(0, 1.1), tick(1, 0.0)],
+            [tick(0, 1.1), tick(3_600_000, 1.1)],
+        ] {
+            let error = validate_hour(&ticks, hour_start).unwrap_err();
+
+            assert!(matches!(error.kind, Kind::Decode));
+            assert_eq!(error.context.hour, Some(hour_start));
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_records() {
+        let mut compressed = Vec::new();
+        lzma_compress(&mut &[0u8; 30][..], &mut compressed).unwrap();
+
+        let error = parse_hour(&compressed, datetime!(2020-03-12 06:00), 100_000.0).unwrap_err();
+
+        assert!(matches!(error.kind, Kind::Decode));
+        assert_eq!(error.context.hour, Some(datetime!(2020-03-12 06:00)));
+        assert_eq!(
+            error.to_string(),
+            "failed to decode data: decompressed size 30 is not a multiple of 20 bytes (at 2020-03-12 6:00:00.0)"
+        );
+    }
+
+    #[test]
+    fn encodes_hours_parsed_back_unchanged() {
+        let bytes = std::fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/07h_ticks.bi5"),
+        )
+        .unwrap();
+        let ticks = parse_hour(&bytes, datetime!(2020-03-12 07:00), 100_000.0).unwrap();
+
+        let encoded = encode_hour(&ticks, datetime!(2020-03-12 07:00), 100_000.0).unwrap();
+
+        assert_eq!(
+            parse_hour(&encoded, datetime!(2020-03-12 07:00), 100_000.0).unwrap(),
+            ticks
+        );
+    }
+
+    #[test]
+    fn rejects_ticks_outside_of_the_hour() {
+        let tick = Tick {
+            time: datetime!(2020-03-12 08:00 UTC).unix_timestamp() * 1000,
+            ask: 1.1,
+            bid: 1.0,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+
+        let error = encode_hour(&[tick], datetime!(2020-03-12 07:00), 100_000.0).unwrap_err();
+
+        assert!(matches!(error.kind, Kind::InvalidArgument));
+    }
+}
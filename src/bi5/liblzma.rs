@@ -0,0 +1,272 @@
+//! Decompression with the native liblzma, enabled by the `liblzma` feature
+//!
+//! Only the few functions needed to decode `.lzma` ("LZMA alone") files are declared,
+//! see `lzma/base.h` and `lzma/container.h` for their documentation. The build script
+//! finds the library with pkg-config and exports the layout of `lzma_stream` from its
+//! headers, which the tests compare with [LzmaStream](LzmaStream).
+
+use std::os::raw::c_void;
+
+use super::{decode_error, TICK_SIZE};
+
+const LZMA_OK: u32 = 0;
+const LZMA_STREAM_END: u32 = 1;
+const LZMA_FINISH: u32 = 3;
+
+/// Initial size of the output buffer relative to the compressed size, tick files
+/// usually decompress to about four times their size
+const EXPECTED_RATIO: usize = 4;
+
+/// Size of the output buffer of [TickRecords](TickRecords), a multiple of [TICK_SIZE](TICK_SIZE)
+const STREAM_BUF_SIZE: usize = 256 * TICK_SIZE;
+
+/// Mirrors `lzma_stream`, a zeroed value is equivalent to `LZMA_STREAM_INIT`
+#[repr(C)]
+struct LzmaStream {
+    next_in: *const u8,
+    avail_in: usize,
+    total_in: u64,
+    next_out: *mut u8,
+    avail_out: usize,
+    total_out: u64,
+    allocator: *const c_void,
+    internal: *mut c_void,
+    reserved_ptr: [*mut c_void; 4],
+    reserved_int: [u64; 2],
+    reserved_size: [usize; 2],
+    reserved_enum: [u32; 2],
+}
+
+// linked by the build script
+extern "C" {
+    fn lzma_alone_decoder(strm: *mut LzmaStream, memlimit: u64) -> u32;
+    fn lzma_code(strm: *mut LzmaStream, action: u32) -> u32;
+    fn lzma_end(strm: *mut LzmaStream);
+}
+
+/// Decompresses a non-empty `.bi5` file into a buffer, appending to its contents
+pub(super) fn decompress_into(bytes: &[u8], buf: &mut Vec<u8>) -> Result<(), crate::error::Error> {
+    // SAFETY: all fields are integers or nullable pointers, for which zero is valid
+    let mut stream: LzmaStream = unsafe { std::mem::zeroed() };
+
+    // SAFETY: the stream is initialized as required by lzma_alone_decoder
+    let ret = unsafe { lzma_alone_decoder(&mut stream, u64::MAX) };
+    if ret != LZMA_OK {
+        return Err(liblzma_error(ret));
+    }
+
+    stream.next_in = bytes.as_ptr();
+    stream.avail_in = bytes.len();
+
+    let ret = loop {
+        if buf.len() == buf.capacity() {
+            buf.reserve((bytes.len() * EXPECTED_RATIO).max(4096));
+        }
+
+        let spare = buf.spare_capacity_mut();
+        stream.next_out = spare.as_mut_ptr().cast();
+        stream.avail_out = spare.len();
+        let available = spare.len();
+
+        // SAFETY: next_in and next_out point to buffers of at least avail_in and avail_out bytes
+        let ret = unsafe { lzma_code(&mut stream, LZMA_FINISH) };
+
+        // SAFETY: liblzma initialized the bytes it wrote to the spare capacity
+        unsafe { buf.set_len(buf.len() + available - stream.avail_out) };
+
+        if ret != LZMA_OK {
+            break ret;
+        }
+    };
+
+    // SAFETY: the stream was initialized by lzma_alone_decoder and isn't used afterwards
+    unsafe { lzma_end(&mut stream) };
+
+    match ret {
+        LZMA_STREAM_END => Ok(()),
+        ret => Err(liblzma_error(ret)),
+    }
+}
+
+/// Tick records of a `.bi5` file, decompressed incrementally into a small buffer, so
+/// the first ones are available before the whole file is decompressed
+pub(super) struct TickRecords<'a> {
+    // boxed so that liblzma's state doesn't move with the iterator
+    stream: Box<LzmaStream>,
+    input: std::marker::PhantomData<&'a [u8]>,
+    buf: Box<[u8; STREAM_BUF_SIZE]>,
+    start: usize,
+    end: usize,
+    finished: bool,
+}
+
+impl<'a> TickRecords<'a> {
+    pub(super) fn new(bytes: &'a [u8]) -> Result<TickRecords<'a>, crate::error::Error> {
+        // SAFETY: all fields are integers or nullable pointers, for which zero is valid
+        let mut stream: Box<LzmaStream> = Box::new(unsafe { std::mem::zeroed() });
+
+        // SAFETY: the stream is initialized as required by lzma_alone_decoder
+        let ret = unsafe { lzma_alone_decoder(&mut *stream, u64::MAX) };
+        if ret != LZMA_OK {
+            return Err(liblzma_error(ret));
+        }
+
+        stream.next_in = bytes.as_ptr();
+        stream.avail_in = bytes.len();
+
+        Ok(TickRecords {
+            stream,
+            input: std::marker::PhantomData,
+            buf: Box::new([0; STREAM_BUF_SIZE]),
+            start: 0,
+            end: 0,
+            finished: bytes.is_empty(),
+        })
+    }
+
+    /// Decompresses more bytes into the buffer, after the ones not read yet
+    fn fill(&mut self) -> Result<(), crate::error::Error> {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+
+        let spare = &mut self.buf[self.end..];
+        self.stream.next_out = spare.as_mut_ptr();
+        self.stream.avail_out = spare.len();
+        let available = spare.len();
+
+        // SAFETY: next_in points to the borrowed input and next_out to the rest of the buffer,
+        // of at least avail_in and avail_out bytes
+        let ret = unsafe { lzma_code(&mut *self.stream, LZMA_FINISH) };
+        self.end += available - self.stream.avail_out;
+
+        match ret {
+            LZMA_OK => Ok(()),
+            LZMA_STREAM_END => {
+                self.finished = true;
+                Ok(())
+            }
+            ret => Err(liblzma_error(ret)),
+        }
+    }
+}
+
+impl Iterator for TickRecords<'_> {
+    type Item = Result<[u8; TICK_SIZE], crate::error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.end - self.start < TICK_SIZE {
+            if self.finished {
+                if self.start == self.end {
+                    return None;
+                }
+                let message = format!("decompressed size is not a multiple of {TICK_SIZE} bytes");
+                self.start = self.end;
+                return Some(Err(decode_error(message.into())));
+            }
+            if let Err(e) = self.fill() {
+                self.finished = true;
+                self.start = self.end;
+                return Some(Err(e));
+            }
+        }
+
+        let mut record = [0; TICK_SIZE];
+        record.copy_from_slice(&self.buf[self.start..self.start + TICK_SIZE]);
+        self.start += TICK_SIZE;
+
+        Some(Ok(record))
+    }
+}
+
+impl Drop for TickRecords<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the stream was initialized by lzma_alone_decoder and isn't used afterwards
+        unsafe { lzma_end(&mut *self.stream) };
+    }
+}
+
+fn liblzma_error(ret: u32) -> crate::error::Error {
+    decode_error(format!("liblzma failed with code {ret}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use std::mem::{offset_of, size_of};
+
+    use lzma_rs::lzma_decompress;
+
+    use super::{decompress_into, LzmaStream, TickRecords, LZMA_FINISH, LZMA_OK, LZMA_STREAM_END};
+
+    // compiled by the build script from csrc/lzma_layout.c
+    extern "C" {
+        static dukascopy_lzma_stream_layout: [usize; 12];
+    }
+
+    #[test]
+    fn declares_lzma_stream_like_the_headers() {
+        let layout = [
+            size_of::<LzmaStream>(),
+            offset_of!(LzmaStream, next_in),
+            offset_of!(LzmaStream, avail_in),
+            offset_of!(LzmaStream, total_in),
+            offset_of!(LzmaStream, next_out),
+            offset_of!(LzmaStream, avail_out),
+            offset_of!(LzmaStream, total_out),
+            offset_of!(LzmaStream, allocator),
+            offset_of!(LzmaStream, internal),
+            LZMA_OK as usize,
+            LZMA_STREAM_END as usize,
+            LZMA_FINISH as usize,
+        ];
+
+        // SAFETY: the array is a constant defined with the same length
+        assert_eq!(layout, unsafe { dukascopy_lzma_stream_layout });
+    }
+
+    #[test]
+    fn decompresses_like_lzma_rs() {
+        for hour in [6, 7, 8] {
+            let bytes = std::fs::read(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join(format!("test-resources/{hour:02}h_ticks.bi5")),
+            )
+            .unwrap();
+
+            let mut expected = Vec::new();
+            lzma_decompress(&mut &bytes[..], &mut expected).unwrap();
+            let mut decompressed = Vec::new();
+            decompress_into(&bytes, &mut decompressed).unwrap();
+
+            assert_eq!(decompressed, expected);
+            assert!(decompress_into(&bytes[..bytes.len() / 2], &mut Vec::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn decompresses_records_incrementally() {
+        for hour in [6, 7, 8] {
+            let bytes = std::fs::read(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join(format!("test-resources/{hour:02}h_ticks.bi5")),
+            )
+            .unwrap();
+
+            let mut expected = Vec::new();
+            decompress_into(&bytes, &mut expected).unwrap();
+            let records = TickRecords::new(&bytes)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(records.concat(), expected);
+            assert!(TickRecords::new(&bytes[..bytes.len() / 2])
+                .unwrap()
+                .any(|e| e.is_err()));
+        }
+
+        assert_eq!(TickRecords::new(&[]).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,103 @@
+//! Blocking counterparts of [DukascopyService's](crate::DukascopyService) methods, for
+//! consumers that don't run an async runtime
+//!
+//! Requests are driven by a runtime shared by all blocking calls, which is started
+//! on first use. Calling blocking methods from within an async runtime panics.
+
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+use futures::{Stream, StreamExt};
+use time::PrimitiveDateTime;
+use tokio::runtime::Runtime;
+
+use crate::{DukascopyService, Tick};
+
+/// Iterator returned by [download_ticks_blocking](crate::DukascopyService::download_ticks_blocking)
+pub struct BlockingTicks<'a> {
+    ticks: Pin<Box<dyn Stream<Item = Result<Tick, crate::error::Error>> + 'a>>,
+}
+
+impl Iterator for BlockingTicks<'_> {
+    type Item = Result<Tick, crate::error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        runtime().block_on(self.ticks.next())
+    }
+}
+
+impl DukascopyService {
+    /// Same as [download_ticks](DukascopyService::download_ticks), but blocks
+    /// the current thread until each tick is available
+    pub fn download_ticks_blocking(
+        &self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> BlockingTicks<'_> {
+        let instrument = instrument.as_ref().to_string();
+
+        BlockingTicks {
+            ticks: Box::pin(self.download_ticks(instrument, start, end)),
+        }
+    }
+
+    /// Same as [current_quote](DukascopyService::current_quote), but blocks
+    /// the current thread until the quote is available
+    pub fn current_quote_blocking(
+        &self,
+        instrument: impl AsRef<str>,
+    ) -> Result<Option<Tick>, crate::error::Error> {
+        runtime().block_on(self.current_quote(instrument))
+    }
+}
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("dukascopy-blocking")
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime for blocking calls")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use crate::{data_supplier::tests::TestResourceDataSupplier, DukascopyService};
+
+    #[test]
+    fn downloads_ticks_without_a_runtime() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let ticks = service
+            .download_ticks_blocking(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let expected = super::runtime().block_on(
+            service
+                .download_ticks(
+                    "EURGBP",
+                    datetime!(2020-03-12 06:00),
+                    datetime!(2020-03-12 09:00),
+                )
+                .map(|e| e.unwrap())
+                .collect::<Vec<_>>(),
+        );
+
+        assert!(!ticks.is_empty());
+        assert_eq!(ticks, expected);
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use futures::{stream, StreamExt};
+use time::macros::format_description;
+use time::PrimitiveDateTime;
+use tokio::io::AsyncWriteExt;
+
+use crate::error::io_error;
+use crate::{DukascopyService, Tick};
+
+/// Downloads long ranges of ticks hour by hour, keeping a manifest of the completed hours
+/// on disk, so an interrupted download can be resumed by running it again
+///
+/// The manifest is a text file with a line for each completed hour, e.g. `EURUSD 2020-03-12T06:00`,
+/// and can be shared by downloads of different instruments.
+#[derive(Debug, Clone)]
+pub struct BulkDownload {
+    manifest: PathBuf,
+}
+
+/// Outcome of a single [run](BulkDownload::run)
+#[derive(Debug, Default)]
+pub struct BulkSummary {
+    /// Number of hours completed by this run
+    pub completed: u64,
+
+    /// Number of hours skipped because they were completed by a previous run
+    pub skipped: u64,
+
+    /// Errors of the hours that failed, they are downloaded again by the next run.
+    /// The [context](crate::Error::context) of each error tells which hour it belongs to.
+    pub failed: Vec<crate::error::Error>,
+}
+
+impl BulkDownload {
+    /// Creates a download keeping its manifest in a given file, which is created when needed
+    pub fn new(manifest: impl Into<PathBuf>) -> BulkDownload {
+        BulkDownload {
+            manifest: manifest.into(),
+        }
+    }
+
+    /// Returns the file the manifest is stored in
+    pub fn manifest(&self) -> &Path {
+        &self.manifest
+    }
+
+    /// Returns the hours of a given instrument recorded as completed in the manifest
+    pub async fn completed_hours(
+        &self,
+        instrument: &str,
+    ) -> Result<BTreeSet<PrimitiveDateTime>, crate::error::Error> {
+        let content = match tokio::fs::read_to_string(&self.manifest).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        // lines that can't be parsed, e.g. one cut short by a crash, are ignored,
+        // so their hours are simply downloaded again
+        Ok(content
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .filter(|(name, _)| *name == instrument)
+            .filter_map(|(_, hour)| {
+                PrimitiveDateTime::parse(
+                    hour,
+                    format_description!("[year]-[month]-[day]T[hour]:[minute]"),
+                )
+                .ok()
+            })
+            .collect())
+    }
+
+    /// Downloads the hours of a given instrument and time interval that aren't completed yet,
+    /// passing the ticks of each hour to `write_hour` in chronological order.
+    ///
+    /// An hour is recorded as completed once `write_hour` returns successfully. Hours that failed
+    /// to download or write don't stop the run, they're reported in the [summary](BulkSummary).
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - service the hours are downloaded with, at its [concurrency](DukascopyService::with_concurrency)
+    /// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    /// * `write_hour` - stores the ticks of an hour, e.g. in a file
+    ///
+    /// # Returns
+    ///
+    /// * Ok - summary of the run
+    /// * Err - when the arguments are invalid or the manifest can't be read or written
+    pub async fn run<F>(
+        &self,
+        service: &DukascopyService,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        mut write_hour: F,
+    ) -> Result<BulkSummary, crate::error::Error>
+    where
+        F: FnMut(PrimitiveDateTime, Vec<Tick>) -> Result<(), crate::error::Error>,
+    {
+        let instrument = service.resolve_download(instrument.as_ref(), start, end)?;
+        let completed = self.completed_hours(&instrument.name).await?;
+        let planned = service.compute_tick_download_times(&instrument, start, end);
+
+        let mut summary = BulkSummary::default();
+        let pending = planned
+            .into_iter()
+            .filter(|e| {
+                let done = completed.contains(e);
+                summary.skipped += done as u64;
+                !done
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(parent) = self.manifest.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        let mut manifest = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.manifest)
+            .await
+            .map_err(io_error)?;
+
+        let instrument = &instrument;
+        let mut hours = stream::iter(pending)
+            .map(|hour| async move { (hour, service.fetch_hour(instrument, hour).await) })
+            .buffered(service.concurrency());
+
+        while let Some((hour, result)) = hours.next().await {
+            let written = result.and_then(|ticks| {
+                write_hour(hour, ticks).map_err(|e| e.with_item(&instrument.name, hour))
+            });
+
+            match written {
+                Ok(()) => {
+                    let line = format!(
+                        "{} {}-{:02}-{:02}T{:02}:00\n",
+                        instrument.name,
+                        hour.year(),
+                        hour.month() as u8,
+                        hour.day(),
+                        hour.hour()
+                    );
+                    manifest
+                        .write_all(line.as_bytes())
+                        .await
+                        .map_err(io_error)?;
+                    manifest.flush().await.map_err(io_error)?;
+                    summary.completed += 1;
+                }
+                Err(e) => summary.failed.push(e),
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::BulkDownload;
+    use crate::{data_supplier::tests::TestResourceDataSupplier, DukascopyService, Kind};
+
+    #[tokio::test]
+    async fn resumes_from_the_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let download = BulkDownload::new(dir.path().join("manifest.txt"));
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let first = download
+            .run(
+                &service,
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+                |hour, _| match hour.hour() {
+                    7 => Err(crate::error::Error::new(Kind::Io, "disk full")),
+                    _ => Ok(()),
+                },
+            )
+            .await
+            .unwrap();
+
+        let mut written = Vec::new();
+        let second = download
+            .run(
+                &service,
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+                |hour, ticks| {
+                    written.push((hour, ticks.len()));
+                    Ok(())
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            (first.completed, first.skipped, first.failed.len()),
+            (2, 0, 1)
+        );
+        assert_eq!(
+            first.failed[0].context.hour,
+            Some(datetime!(2020-03-12 07:00))
+        );
+        assert_eq!(
+            (second.completed, second.skipped, second.failed.len()),
+            (1, 2, 0)
+        );
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].0, datetime!(2020-03-12 07:00));
+        assert!(written[0].1 > 0);
+        assert_eq!(download.completed_hours("EURGBP").await.unwrap().len(), 3);
+    }
+}
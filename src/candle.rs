@@ -0,0 +1,256 @@
+use std::fmt::Display;
+
+use crate::tick::millis_to_date_time;
+use crate::Tick;
+use futures::{stream, Stream, StreamExt};
+
+/// Open, high, low and close prices and the volume over a period of time
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candle {
+    /// Start of the period, in the same unit as [Tick::time](Tick::time)
+    pub time: i64,
+
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl Candle {
+    pub(crate) fn open(time: i64, price: f64, volume: f64) -> Candle {
+        Candle {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        }
+    }
+
+    pub(crate) fn update(&mut self, price: f64, volume: f64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += volume;
+    }
+}
+
+impl Display for Candle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(date_time) = millis_to_date_time(self.time) {
+            write!(
+                f,
+                "{} {}\t\t{:<16} {:<16} {:<16} {:<16} {:<26}",
+                date_time.date(),
+                date_time.time(),
+                self.open,
+                self.high,
+                self.low,
+                self.close,
+                self.volume
+            )
+        } else {
+            Err(std::fmt::Error)
+        }
+    }
+}
+
+/// Length of the period a single [Candle](Candle) covers
+///
+/// Periods are aligned to the unix epoch, so e.g. daily candles start at midnight UTC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Timeframe {
+    seconds: i64,
+}
+
+impl Timeframe {
+    pub const M1: Timeframe = Timeframe::minutes(1);
+    pub const M5: Timeframe = Timeframe::minutes(5);
+    pub const M15: Timeframe = Timeframe::minutes(15);
+    pub const M30: Timeframe = Timeframe::minutes(30);
+    pub const H1: Timeframe = Timeframe::hours(1);
+    pub const H4: Timeframe = Timeframe::hours(4);
+    pub const D1: Timeframe = Timeframe::days(1);
+
+    /// A timeframe of a given number of seconds, at least one
+    pub const fn seconds(seconds: i64) -> Timeframe {
+        let seconds = if seconds > 0 { seconds } else { 1 };
+
+        Timeframe { seconds }
+    }
+
+    pub const fn minutes(minutes: i64) -> Timeframe {
+        Timeframe::seconds(minutes * 60)
+    }
+
+    pub const fn hours(hours: i64) -> Timeframe {
+        Timeframe::minutes(hours * 60)
+    }
+
+    pub const fn days(days: i64) -> Timeframe {
+        Timeframe::hours(days * 24)
+    }
+
+    /// Returns the length of the period in milliseconds
+    pub(crate) fn millis(&self) -> i64 {
+        self.seconds * 1000
+    }
+
+    /// Returns the start of the period a given time (in milliseconds) belongs to
+    pub(crate) fn period_start(&self, time: i64) -> i64 {
+        let millis = self.millis();
+        time.div_euclid(millis) * millis
+    }
+}
+
+/// Which side of the quote candles are built from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PriceSource {
+    /// Bid prices and bid volumes
+    Bid,
+
+    /// Ask prices and ask volumes
+    Ask,
+
+    /// Prices in the middle of bid and ask, volumes of both sides summed up
+    Mid,
+}
+
+impl PriceSource {
+    pub(crate) fn price_and_volume(&self, tick: &Tick) -> (f64, f64) {
+        match self {
+            PriceSource::Bid => (tick.bid, tick.bid_volume),
+            PriceSource::Ask => (tick.ask, tick.ask_volume),
+            PriceSource::Mid => (tick.mid(), tick.ask_volume + tick.bid_volume),
+        }
+    }
+}
+
+/// Side of the quote of the candles served by Dukascopy,
+/// see [download_minute_candles](crate::DukascopyService::download_minute_candles)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OfferSide {
+    Bid,
+    Ask,
+}
+
+/// Aggregates a stream of ticks into candles of a given timeframe
+///
+/// Ticks are expected to be in chronological order. Periods without any ticks
+/// produce no candles and errors are passed through as they come.
+pub fn ticks_to_candles<S>(
+    ticks: S,
+    timeframe: Timeframe,
+    source: PriceSource,
+) -> impl Stream<Item = Result<Candle, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    let ticks = Box::pin(ticks.fuse());
+
+    stream::unfold(
+        (ticks, None::<Candle>),
+        move |(mut ticks, mut candle)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let (price, volume) = source.price_and_volume(&tick);
+                        let period = timeframe.period_start(tick.time);
+
+                        match candle.as_mut() {
+                            Some(current) if current.time == period => {
+                                current.update(price, volume)
+                            }
+                            _ => {
+                                let finished = candle.replace(Candle::open(period, price, volume));
+                                if let Some(finished) = finished {
+                                    return Some((Ok(finished), (ticks, candle)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, candle))),
+                    None => return candle.take().map(|e| (Ok(e), (ticks, None))),
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{ticks_to_candles, Candle, PriceSource, Timeframe};
+    use crate::Tick;
+
+    fn tick(time: i64, bid: f64) -> Tick {
+        Tick {
+            time,
+            ask: bid + 0.0002,
+            bid,
+            ask_volume: 1.0,
+            bid_volume: 2.0,
+        }
+    }
+
+    #[test]
+    fn aggregates_ticks_into_candles() {
+        let ticks = vec![
+            Ok(tick(0, 1.1)),
+            Ok(tick(10_000, 1.3)),
+            Ok(tick(20_000, 1.0)),
+            Ok(tick(59_999, 1.2)),
+            Ok(tick(185_000, 1.5)),
+        ];
+
+        let candles = block_on(
+            ticks_to_candles(stream::iter(ticks), Timeframe::M1, PriceSource::Bid)
+                .map(|e| e.unwrap())
+                .collect::<Vec<Candle>>(),
+        );
+
+        assert_eq!(
+            candles,
+            vec![
+                Candle {
+                    time: 0,
+                    open: 1.1,
+                    high: 1.3,
+                    low: 1.0,
+                    close: 1.2,
+                    volume: 8.0,
+                },
+                Candle {
+                    time: 180_000,
+                    open: 1.5,
+                    high: 1.5,
+                    low: 1.5,
+                    close: 1.5,
+                    volume: 2.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_mid_price_candles() {
+        let ticks = vec![Ok(tick(3_600_000, 1.1)), Ok(tick(7_199_999, 1.2))];
+
+        let candles = block_on(
+            ticks_to_candles(stream::iter(ticks), Timeframe::H1, PriceSource::Mid)
+                .map(|e| e.unwrap())
+                .collect::<Vec<Candle>>(),
+        );
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].time, 3_600_000);
+        assert_abs_diff_eq!(candles[0].open, 1.1001, epsilon = 0.000_001);
+        assert_abs_diff_eq!(candles[0].close, 1.2001, epsilon = 0.000_001);
+        assert_eq!(candles[0].volume, 6.0);
+    }
+}
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::DataSupplier;
+
+/// A [DataSupplier](DataSupplier) trying a number of suppliers in order, e.g. a local
+/// mirror first and the live datafeed second
+///
+/// The first response with data is returned. When a supplier has no data or fails,
+/// the next one is asked instead, and the response of the last one is returned as it is.
+#[derive(Default)]
+pub struct ChainedDataSupplier {
+    suppliers: Vec<Box<dyn DataSupplier + Send + Sync>>,
+}
+
+impl ChainedDataSupplier {
+    /// Creates a chain without any suppliers, which never has any data
+    pub fn new() -> ChainedDataSupplier {
+        ChainedDataSupplier::default()
+    }
+
+    /// Appends a supplier that is asked when all previous ones had no data or failed
+    pub fn with_supplier(
+        mut self,
+        supplier: impl DataSupplier + Send + Sync + 'static,
+    ) -> ChainedDataSupplier {
+        self.suppliers.push(Box::new(supplier));
+        self
+    }
+}
+
+#[async_trait]
+impl DataSupplier for ChainedDataSupplier {
+    async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let (last, fallbacks) = match self.suppliers.split_last() {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+
+        for supplier in fallbacks {
+            if let Ok(Some(bytes)) = supplier.fetch(url).await {
+                return Ok(Some(bytes));
+            }
+        }
+
+        last.fetch(url).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use futures::executor::block_on;
+
+    use super::ChainedDataSupplier;
+    use crate::{DataSupplier, Kind};
+
+    struct StaticDataSupplier(Option<&'static str>);
+
+    #[async_trait]
+    impl DataSupplier for StaticDataSupplier {
+        async fn fetch(&self, _url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            Ok(self.0.map(Bytes::from))
+        }
+    }
+
+    struct FailingDataSupplier;
+
+    #[async_trait]
+    impl DataSupplier for FailingDataSupplier {
+        async fn fetch(&self, _url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            Err(crate::error::Error::new(Kind::Network, "connection reset"))
+        }
+    }
+
+    #[test]
+    fn falls_back_to_following_suppliers() {
+        let supplier = ChainedDataSupplier::new()
+            .with_supplier(FailingDataSupplier)
+            .with_supplier(StaticDataSupplier(None))
+            .with_supplier(StaticDataSupplier(Some("mirror")))
+            .with_supplier(StaticDataSupplier(Some("live")));
+
+        let bytes = block_on(supplier.fetch("https://example.com")).unwrap();
+
+        assert_eq!(bytes, Some(Bytes::from("mirror")));
+    }
+
+    #[test]
+    fn returns_the_response_of_the_last_supplier() {
+        let supplier = ChainedDataSupplier::new()
+            .with_supplier(StaticDataSupplier(None))
+            .with_supplier(FailingDataSupplier);
+
+        let error = block_on(supplier.fetch("https://example.com")).unwrap_err();
+
+        assert!(matches!(error.kind, Kind::Network));
+        assert_eq!(
+            block_on(ChainedDataSupplier::new().fetch("https://example.com")).unwrap(),
+            None
+        );
+    }
+}
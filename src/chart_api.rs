@@ -0,0 +1,120 @@
+use time::PrimitiveDateTime;
+
+use crate::error::Kind;
+use crate::{Candle, OfferSide};
+
+/// URL of the JSON candle API behind Dukascopy's web charts
+pub(crate) const CHART_API_URL: &str = "https://freeserv.dukascopy.com/2.0/index.php";
+
+/// Where the data of a [SourcedCandle](SourcedCandle) comes from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CandleSource {
+    /// Aggregated from the ticks of the datafeed's hourly files
+    Ticks,
+
+    /// Fetched from the JSON candle API behind Dukascopy's web charts, because the hour's
+    /// file was missing or corrupt. These candles are published at minute resolution only
+    /// and their volumes may be rounded differently.
+    ChartApi,
+}
+
+/// A one minute candle flagged with where its data comes from,
+/// see [download_candles_with_fallback](crate::DukascopyService::download_candles_with_fallback)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SourcedCandle {
+    pub candle: Candle,
+    pub source: CandleSource,
+}
+
+/// Returns the URL of the one minute candles of a given hour in the chart API
+pub(crate) fn chart_api_url(
+    base_url: &str,
+    instrument: &str,
+    hour: PrimitiveDateTime,
+    side: OfferSide,
+) -> String {
+    // the chart API names currency pairs with a slash, e.g. EUR/USD
+    let instrument = match instrument.len() == 6 && instrument.chars().all(char::is_alphabetic) {
+        true => format!("{}/{}", &instrument[..3], &instrument[3..]),
+        false => instrument.to_string(),
+    };
+    let side = match side {
+        OfferSide::Bid => "B",
+        OfferSide::Ask => "A",
+    };
+    let time = hour.assume_utc().unix_timestamp() * 1000;
+
+    format!(
+        "{base_url}?path=chart/json3&instrument={instrument}&offer_side={side}&interval=1MIN\
+         &splits=true&stocks=true&time={time}&limit=60&jsonp=_callbacks"
+    )
+}
+
+/// Parses a response of the chart API, a JSONP callback with an array of
+/// `[time, open, high, low, close, volume]` arrays
+pub(crate) fn parse_chart_response(bytes: &[u8]) -> Result<Vec<Candle>, crate::error::Error> {
+    let invalid = |message: String| crate::error::Error::new(Kind::Decode, message);
+
+    let body = std::str::from_utf8(bytes).map_err(|e| invalid(e.to_string()))?;
+    let json = match (body.find('('), body.rfind(')')) {
+        (Some(open), Some(close)) if open < close => &body[open + 1..close],
+        _ => body,
+    };
+
+    let rows: Vec<Vec<f64>> = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    rows.into_iter()
+        .map(|row| match row[..] {
+            [time, open, high, low, close, volume] => Ok(Candle {
+                time: time as i64,
+                open,
+                high,
+                low,
+                close,
+                volume,
+            }),
+            _ => Err(invalid(format!("unexpected candle: {row:?}"))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{chart_api_url, parse_chart_response};
+    use crate::{Candle, OfferSide};
+
+    #[test]
+    fn requests_an_hour_of_minute_candles() {
+        let url = chart_api_url(
+            "https://example.com/chart",
+            "EURUSD",
+            datetime!(2020-03-12 06:00),
+            OfferSide::Ask,
+        );
+
+        assert_eq!(
+            url,
+            "https://example.com/chart?path=chart/json3&instrument=EUR/USD&offer_side=A\
+             &interval=1MIN&splits=true&stocks=true&time=1583992800000&limit=60&jsonp=_callbacks"
+        );
+    }
+
+    #[test]
+    fn parses_jsonp_responses() {
+        let body = b"_callbacks([[1583992800000,1.1,1.2,1.0,1.15,12.5]]);";
+
+        assert_eq!(
+            parse_chart_response(body).unwrap(),
+            [Candle {
+                time: 1_583_992_800_000,
+                open: 1.1,
+                high: 1.2,
+                low: 1.0,
+                close: 1.15,
+                volume: 12.5,
+            }]
+        );
+        assert!(parse_chart_response(b"_callbacks([[1, 2]]);").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+use crate::Tick;
+
+/// Which ticks [dedup_ticks](DedupExt::dedup_ticks) considers duplicates of the tick preceding them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Ticks with the same prices and volumes, regardless of their time
+    SameQuote,
+
+    /// Ticks with the same prices, volumes and time
+    SameQuoteAndTime,
+}
+
+/// Adds [dedup_ticks](DedupExt::dedup_ticks) to tick streams
+pub trait DedupExt: Stream<Item = Result<Tick, crate::error::Error>> + Sized {
+    /// Removes ticks identical to the tick preceding them, which shrinks
+    /// the data of quiet instruments considerably. Errors are passed through as they come.
+    fn dedup_ticks(self, duplicates: Duplicates) -> DedupTicks<Self> {
+        DedupTicks {
+            stream: Box::pin(self),
+            duplicates,
+            previous: None,
+        }
+    }
+}
+
+impl<S> DedupExt for S where S: Stream<Item = Result<Tick, crate::error::Error>> {}
+
+/// Stream returned by [dedup_ticks](DedupExt::dedup_ticks)
+pub struct DedupTicks<S> {
+    stream: Pin<Box<S>>,
+    duplicates: Duplicates,
+    previous: Option<Tick>,
+}
+
+impl<S> DedupTicks<S> {
+    fn is_duplicate(&self, tick: &Tick) -> bool {
+        let previous = match &self.previous {
+            Some(previous) => previous,
+            None => return false,
+        };
+
+        let same_quote = (tick.ask, tick.bid, tick.ask_volume, tick.bid_volume)
+            == (
+                previous.ask,
+                previous.bid,
+                previous.ask_volume,
+                previous.bid_volume,
+            );
+
+        match self.duplicates {
+            Duplicates::SameQuote => same_quote,
+            Duplicates::SameQuoteAndTime => same_quote && tick.time == previous.time,
+        }
+    }
+}
+
+impl<S> Stream for DedupTicks<S>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            return match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(tick))) if this.is_duplicate(&tick) => continue,
+                Poll::Ready(Some(Ok(tick))) => {
+                    this.previous = Some(tick);
+                    Poll::Ready(Some(Ok(tick)))
+                }
+                result => result,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{DedupExt, Duplicates};
+    use crate::Tick;
+
+    fn tick(time: i64, bid: f64) -> Tick {
+        Tick {
+            time,
+            ask: bid + 0.0001,
+            bid,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        }
+    }
+
+    fn dedup(duplicates: Duplicates) -> Vec<i64> {
+        let ticks = [
+            tick(0, 1.1),
+            tick(0, 1.1),
+            tick(5, 1.1),
+            tick(7, 1.2),
+            tick(9, 1.1),
+        ];
+
+        block_on(
+            stream::iter(ticks.map(Ok))
+                .dedup_ticks(duplicates)
+                .map(|e| e.unwrap().time)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn removes_consecutive_duplicates() {
+        assert_eq!(dedup(Duplicates::SameQuote), vec![0, 7, 9]);
+        assert_eq!(dedup(Duplicates::SameQuoteAndTime), vec![0, 5, 7, 9]);
+    }
+}
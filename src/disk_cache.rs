@@ -0,0 +1,190 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::error::{io_error, Kind};
+use crate::export::AtomicFile;
+use crate::{DatafeedLayout, StorageLayout};
+
+/// On-disk cache of the raw (compressed) files downloaded by
+/// [DukascopyService](crate::DukascopyService)
+///
+/// Files are stored using the same layout as the datafeed by default, e.g.
+/// `{dir}/EURUSD/2020/02/12/10h_ticks.bi5`, see [with_layout](DiskCache::with_layout).
+/// Only files containing data are cached, so hours without any ticks (or not yet published)
+/// are always requested again.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    layout: Arc<dyn StorageLayout>,
+}
+
+/// Size of a [DiskCache](DiskCache)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of cached files
+    pub files: u64,
+
+    /// Total size of cached files in bytes
+    pub bytes: u64,
+}
+
+impl DiskCache {
+    /// Creates a cache stored in a given directory, which is created when needed
+    pub fn new(dir: impl Into<PathBuf>) -> DiskCache {
+        DiskCache {
+            dir: dir.into(),
+            layout: Arc::new(DatafeedLayout),
+        }
+    }
+
+    /// Sets the [layout](StorageLayout) files are stored in
+    pub fn with_layout(mut self, layout: impl StorageLayout + 'static) -> DiskCache {
+        self.layout = Arc::new(layout);
+        self
+    }
+
+    /// Returns the directory the cache is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns how many files the cache holds and how much space they take
+    pub async fn stats(&self) -> Result<CacheStats, crate::error::Error> {
+        let mut stats = CacheStats::default();
+        let mut dirs = vec![self.dir.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(io_error(e)),
+            };
+
+            while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+                let metadata = entry.metadata().await.map_err(io_error)?;
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                } else {
+                    stats.files += 1;
+                    stats.bytes += metadata.len();
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Removes all cached files
+    pub async fn clear(&self) -> Result<(), crate::error::Error> {
+        match tokio::fs::remove_dir_all(&self.dir).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(io_error(e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the cached file under a given path relative to the datafeed's base URL
+    pub(crate) async fn get(&self, path: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        match tokio::fs::read(self.dir.join(self.storage_path(path))).await {
+            Ok(bytes) => Ok(Some(Bytes::from(bytes))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Returns the path a file is stored under relative to the cache's directory
+    pub(crate) fn storage_path(&self, path: &str) -> String {
+        self.layout.storage_path(path)
+    }
+
+    /// Stores a file under a given path relative to the datafeed's base URL
+    pub(crate) async fn put(&self, path: &str, bytes: &Bytes) -> Result<(), crate::error::Error> {
+        let target = self.dir.join(self.storage_path(path));
+        let bytes = bytes.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut file = AtomicFile::create(target)?;
+            file.write_all(&bytes).map_err(io_error)?;
+            file.commit()
+        })
+        .await
+        .map_err(|e| crate::error::Error::new(Kind::Io, e))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use crate::{
+        data_supplier::tests::{InMemoryDataSupplier, TestResourceDataSupplier},
+        DiskCache, DukascopyService, Tick,
+    };
+
+    #[tokio::test]
+    async fn serves_cached_files_without_fetching() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path());
+
+        let online = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(TestResourceDataSupplier {}),
+        )
+        .with_cache(cache.clone());
+        let offline = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(InMemoryDataSupplier { data: None }),
+        )
+        .with_cache(cache.clone());
+
+        let downloaded = online
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let cached = offline
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert!(!downloaded.is_empty());
+        assert_eq!(cached, downloaded);
+        assert!(dir.path().join("EURGBP/2020/02/12/06h_ticks.bi5").exists());
+    }
+
+    #[tokio::test]
+    async fn reports_stats_and_clears() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path().join("cache"));
+
+        assert_eq!(cache.stats().await.unwrap().files, 0);
+
+        cache
+            .put("EURGBP/2020/02/12/06h_ticks.bi5", &"abc".into())
+            .await
+            .unwrap();
+        cache
+            .put("EURGBP/2020/02/12/07h_ticks.bi5", &"de".into())
+            .await
+            .unwrap();
+
+        let stats = cache.stats().await.unwrap();
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.bytes, 5);
+
+        cache.clear().await.unwrap();
+        assert_eq!(cache.stats().await.unwrap().files, 0);
+    }
+}
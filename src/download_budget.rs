@@ -0,0 +1,134 @@
+use std::sync::{Arc, Mutex};
+
+use futures::{future, stream, Stream, StreamExt};
+use time::PrimitiveDateTime;
+use tokio_util::sync::CancellationToken;
+
+/// Limits how many requests and bytes a single download is allowed to use,
+/// and allows it to be cancelled
+///
+/// Once the budget runs out or the download is cancelled, the download stream ends
+/// gracefully and the [summary](DownloadBudget::summary) tells where to resume from.
+/// Clones of a budget share what has been spent so far.
+#[derive(Clone, Default)]
+pub struct DownloadBudget {
+    max_requests: Option<usize>,
+    max_bytes: Option<u64>,
+    cancellation: Option<CancellationToken>,
+    spent: Arc<Mutex<BudgetSummary>>,
+}
+
+/// What a download has spent from its [DownloadBudget](DownloadBudget)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BudgetSummary {
+    /// Number of requests made
+    pub requests: usize,
+
+    /// Number of (compressed) bytes downloaded
+    pub bytes: u64,
+
+    /// The first hour that was not downloaded because the budget ran out or the download
+    /// was cancelled, use it as the start of the next download in order to resume
+    pub resume_from: Option<PrimitiveDateTime>,
+}
+
+impl DownloadBudget {
+    /// Creates an unlimited budget
+    pub fn new() -> DownloadBudget {
+        DownloadBudget::default()
+    }
+
+    /// Stops the download after a given number of requests
+    pub fn max_requests(mut self, max_requests: usize) -> DownloadBudget {
+        self.max_requests = Some(max_requests);
+        self
+    }
+
+    /// Stops the download once a given number of bytes has been downloaded
+    pub fn max_bytes(mut self, max_bytes: u64) -> DownloadBudget {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Stops the download once a given token is cancelled. Requests in flight are aborted,
+    /// so the download can be resumed from the first hour that wasn't completed.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> DownloadBudget {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Returns what has been spent so far
+    pub fn summary(&self) -> BudgetSummary {
+        self.spent.lock().unwrap().clone()
+    }
+
+    /// Returns whether the budget ran out
+    pub fn is_exhausted(&self) -> bool {
+        self.summary().resume_from.is_some()
+    }
+
+    /// Spends a request for a given hour, returns false if there is no budget left for it
+    pub(crate) fn spend_request(&self, hour: PrimitiveDateTime) -> bool {
+        let mut spent = self.spent.lock().unwrap();
+
+        let out_of_requests = self.max_requests.is_some_and(|e| spent.requests >= e);
+        let out_of_bytes = self.max_bytes.is_some_and(|e| spent.bytes >= e);
+        let cancelled = self.cancellation.as_ref().is_some_and(|e| e.is_cancelled());
+        if out_of_requests || out_of_bytes || cancelled {
+            spent.resume_from.get_or_insert(hour);
+            return false;
+        }
+
+        spent.requests += 1;
+        true
+    }
+
+    pub(crate) fn spend_bytes(&self, bytes: u64) {
+        self.spent.lock().unwrap().bytes += bytes;
+    }
+
+    /// Ends a stream of completed hours once the download is cancelled, dropping the hours
+    /// in flight and recording the first of the `planned` hours that wasn't completed
+    pub(crate) fn until_cancelled<S, T>(
+        &self,
+        hours: S,
+        planned: Vec<PrimitiveDateTime>,
+    ) -> impl Stream<Item = (PrimitiveDateTime, T)>
+    where
+        S: Stream<Item = (PrimitiveDateTime, T)>,
+    {
+        let token = match &self.cancellation {
+            Some(token) => token.clone(),
+            None => return hours.left_stream(),
+        };
+
+        let completed = Arc::new(Mutex::new(None::<PrimitiveDateTime>));
+        let tracker = completed.clone();
+        let budget = self.clone();
+        let cancelled = token.clone().cancelled_owned();
+        let checkpoint = stream::once(async move {
+            if token.is_cancelled() {
+                let last = *completed.lock().unwrap();
+                let next = planned
+                    .into_iter()
+                    .find(|e| last.is_none_or(|last| *e > last));
+                if let Some(hour) = next {
+                    budget.interrupt(hour);
+                }
+            }
+        })
+        .filter_map(|()| future::ready(None));
+
+        hours
+            .take_until(cancelled)
+            .inspect(move |(hour, _)| *tracker.lock().unwrap() = Some(*hour))
+            .chain(checkpoint)
+            .right_stream()
+    }
+
+    /// Records that the download stopped before a given hour
+    fn interrupt(&self, hour: PrimitiveDateTime) {
+        let mut spent = self.spent.lock().unwrap();
+        spent.resume_from = Some(spent.resume_from.map_or(hour, |e| e.min(hour)));
+    }
+}
@@ -0,0 +1,11 @@
+use time::PrimitiveDateTime;
+
+/// Single request a download would make, see [plan_download](crate::DukascopyService::plan_download)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedHour {
+    /// Start of the hour whose ticks are requested
+    pub hour: PrimitiveDateTime,
+
+    /// URL of the hour's file
+    pub url: String,
+}
@@ -1,23 +1,80 @@
-use byteorder::{BigEndian, ByteOrder};
-use bytes::Bytes;
-use crate::Tick;
-use crate::{data_supplier::ReqwestDataSupplier, DataSupplier};
+use crate::bi5;
+use crate::chart_api::{chart_api_url, parse_chart_response, CHART_API_URL};
 use crate::error::Kind;
-use futures::{stream, Stream, StreamExt};
-use lzma_rs::lzma_decompress;
-use time::{macros::offset, Duration, PrimitiveDateTime};
+use crate::instrument::unknown_instrument;
+use crate::instrument_list::{parse_instrument_list, INSTRUMENTS_URL};
+use crate::merge::MergeByTime;
+use crate::pacing::Pacer;
+use crate::prefetch::Prefetch;
+use crate::progress::{Progress, ProgressReporter};
+#[cfg(feature = "chrono")]
+use crate::tick::chrono_to_date_time;
+use crate::trace::{debug_event, in_hour_span, warn_event};
+use crate::Tick;
+use crate::{
+    ticks_to_candles, AdaptiveConcurrency, Candle, CandleSource, DiskCache, DownloadBudget,
+    ErrorPolicy, ErrorPolicyExt, FollowOptions, HourBatch, Instrument, InstrumentTick, OfferSide,
+    PacingProfile, PlannedHour, PriceSource, RateBudget, RawTick, RetryPolicy, SourcedCandle,
+    TickBatch, Timeframe,
+};
+use crate::{DataSupplier, ReqwestDataSupplier};
+use bytes::Bytes;
+use futures::{future, stream, Stream, StreamExt};
+use std::sync::{Arc, RwLock};
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+/// Base URL of the public datafeed
+pub(crate) const DATAFEED_URL: &str = "https://datafeed.dukascopy.com/datafeed";
+
+/// How many hours back [current_quote](DukascopyService::current_quote) looks for data
+const QUOTE_PROBE_HOURS: i64 = 24 * 7;
+
+/// How many closed hours in the past [follow_ticks](DukascopyService::follow_ticks) skips
+/// before yielding to the runtime
+const MAX_CLOSED_HOURS_SKIPPED: u32 = 24 * 7;
+
+/// How many times a request is attempted before it is reported as stalled
+const STALL_ATTEMPTS: usize = 2;
 
 /// Processes the data from a given [DataSupplier](DataSupplier)
 pub struct DukascopyService {
     pub base_url: String,
     pub data_supplier: Box<dyn DataSupplier>,
+    stall_timeout: Option<std::time::Duration>,
+    rate_budget: Option<RateBudget>,
+    adaptive_concurrency: Option<AdaptiveConcurrency>,
+    chart_api_url: String,
+    pacer: Option<Pacer>,
+    concurrency: usize,
+    prefetch: usize,
+    cache: Option<DiskCache>,
+    retry: Option<Box<dyn RetryPolicy>>,
+    instruments: RwLock<Vec<Instrument>>,
+    skip_closed_hours: bool,
+    validate_ticks: bool,
+    error_policy: ErrorPolicy,
+    progress: Option<Arc<dyn ProgressReporter>>,
 }
 
 impl Default for DukascopyService {
     fn default() -> Self {
         DukascopyService {
-            base_url: "https://datafeed.dukascopy.com/datafeed".to_string(),
+            base_url: DATAFEED_URL.to_string(),
             data_supplier: Box::new(ReqwestDataSupplier::new()),
+            stall_timeout: None,
+            rate_budget: None,
+            adaptive_concurrency: None,
+            chart_api_url: CHART_API_URL.to_string(),
+            pacer: None,
+            concurrency: 1,
+            prefetch: 0,
+            cache: None,
+            retry: None,
+            instruments: RwLock::new(Vec::new()),
+            skip_closed_hours: true,
+            validate_ticks: false,
+            error_policy: ErrorPolicy::Inline,
+            progress: None,
         }
     }
 }
@@ -27,191 +84,2375 @@ impl DukascopyService {
         DukascopyService {
             base_url,
             data_supplier,
+            stall_timeout: None,
+            rate_budget: None,
+            adaptive_concurrency: None,
+            chart_api_url: CHART_API_URL.to_string(),
+            pacer: None,
+            concurrency: 1,
+            prefetch: 0,
+            cache: None,
+            retry: None,
+            instruments: RwLock::new(Vec::new()),
+            skip_closed_hours: true,
+            validate_ticks: false,
+            error_policy: ErrorPolicy::Inline,
+            progress: None,
         }
     }
 
+    /// Enables a watchdog that aborts requests which made no progress for a given time.
+    ///
+    /// A stalled request is retried once, after that the hour is reported
+    /// as an error of [Stalled](crate::Kind::Stalled) kind.
+    pub fn with_stall_timeout(mut self, timeout: std::time::Duration) -> DukascopyService {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits requests made by this service according to a given [RateBudget](RateBudget),
+    /// which can be shared with other services
+    pub fn with_rate_budget(mut self, budget: RateBudget) -> DukascopyService {
+        self.rate_budget = Some(budget);
+        self
+    }
+
+    /// Starts consecutive requests made by this service no sooner than `delay` apart.
+    ///
+    /// This is a shorthand for [with_rate_budget](DukascopyService::with_rate_budget)
+    /// with [RateBudget::interval](RateBudget::interval) and replaces any previously set budget.
+    pub fn with_min_delay(self, delay: std::time::Duration) -> DukascopyService {
+        self.with_rate_budget(RateBudget::interval(delay))
+    }
+
+    /// Whether hours in which an instrument is not [traded](crate::trading_hours) are skipped
+    /// instead of requested, enabled by default. Each instrument has its own
+    /// [trading hours](Instrument::trading_hours), e.g. spot metals have a daily break
+    /// and the DAX is only traded during the day.
+    pub fn with_closed_hours_skipped(mut self, skip: bool) -> DukascopyService {
+        self.skip_closed_hours = skip;
+        self
+    }
+
+    /// Whether decoded hours are [validated](bi5::validate_hour), disabled by default.
+    ///
+    /// With validation enabled, an hour with ticks out of chronological order or with
+    /// non-positive prices is reported as an error of [Decode](crate::Kind::Decode) kind,
+    /// so that corrupt data is caught when it's downloaded rather than when it's analyzed.
+    pub fn with_strict_validation(mut self, validate: bool) -> DukascopyService {
+        self.validate_ticks = validate;
+        self
+    }
+
+    /// Downloads up to `concurrency` hours at the same time, ticks are still emitted
+    /// in chronological order. By default hours are downloaded one by one, and so they are
+    /// with a `concurrency` of 0.
+    ///
+    /// Note that with more than one request in flight, a [DownloadBudget](DownloadBudget)
+    /// limiting bytes can be exceeded by the requests that were already started.
+    pub fn with_concurrency(mut self, concurrency: usize) -> DukascopyService {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Adjusts the number of requests in flight with a given [AdaptiveConcurrency](AdaptiveConcurrency)
+    /// controller instead of keeping it fixed, replacing any [concurrency](DukascopyService::with_concurrency)
+    /// set before with the controller's maximum
+    pub fn with_adaptive_concurrency(
+        mut self,
+        controller: AdaptiveConcurrency,
+    ) -> DukascopyService {
+        self.concurrency = controller.max();
+        self.adaptive_concurrency = Some(controller);
+        self
+    }
+
+    /// Sets the URL of the chart API [download_candles_with_fallback](DukascopyService::download_candles_with_fallback)
+    /// falls back to, Dukascopy's public one by default
+    pub fn with_chart_api_url(mut self, url: impl Into<String>) -> DukascopyService {
+        self.chart_api_url = url.into();
+        self
+    }
+
+    /// Keeps downloading up to `hours` following hours while the ticks of the current hour
+    /// are consumed, ticks are still emitted in chronological order. By default the next hour
+    /// is requested only once the current one is consumed.
+    ///
+    /// At most [concurrency](DukascopyService::with_concurrency) requests are made at the same time,
+    /// so to download several hours ahead in parallel, raise both.
+    pub fn with_prefetch(mut self, hours: usize) -> DukascopyService {
+        self.prefetch = hours;
+        self
+    }
+
+    /// Stores downloaded files in a given [DiskCache](DiskCache) and serves
+    /// subsequent requests for them from disk
+    pub fn with_cache(mut self, cache: DiskCache) -> DukascopyService {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Retries failed requests according to a given policy, e.g. [ExponentialBackoff](ExponentialBackoff)
+    /// or a custom [RetryPolicy](RetryPolicy), by default failed requests are not retried
+    pub fn with_retry(mut self, policy: impl RetryPolicy + 'static) -> DukascopyService {
+        self.retry = Some(Box::new(policy));
+        self
+    }
+
+    /// Spaces out requests made by this service according to a given [PacingProfile](PacingProfile)
+    pub fn with_pacing(mut self, profile: PacingProfile) -> DukascopyService {
+        self.pacer = Some(Pacer::new(profile));
+        self
+    }
+
+    /// Handles the errors of tick and candle downloads according to a given [ErrorPolicy](ErrorPolicy),
+    /// by default they're emitted inline.
+    ///
+    /// To use a different policy for a single download, apply
+    /// [with_error_policy](crate::ErrorPolicyExt::with_error_policy) to the returned stream instead.
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> DukascopyService {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Reports the [Progress](crate::Progress) of tick downloads to a given reporter after each hour
+    pub fn with_progress(mut self, reporter: impl ProgressReporter + 'static) -> DukascopyService {
+        self.progress = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Makes a given instrument available to this service, in addition to the
+    /// [built-in ones](Instrument::built_in). If a built-in instrument has the same name,
+    /// it's replaced, e.g. to use a different [decimal factor](Instrument::decimal_factor).
+    pub fn with_instrument(mut self, instrument: Instrument) -> DukascopyService {
+        self.instruments.get_mut().unwrap().push(instrument);
+        self
+    }
+
     /// Returns a stream of ticks for a given instrument and time interval.
     ///
     /// # Arguments
     ///
-    /// * `instrument` - instrument for which data is fetched
+    /// * `instrument` - [Instrument](Instrument) for which data is fetched, or its name or alias,
+    ///   e.g. `EURUSD` or `EUR/USD`
     ///
-    /// In order to get a ticker for a desired instrument, go to
-    /// https://www.dukascopy.com/swiss/english/marketwatch/historical/
+    /// Instruments that are neither [built-in](Instrument::built_in) nor registered with
+    /// [with_instrument](DukascopyService::with_instrument) are rejected. In order to get a ticker
+    /// for a missing instrument, go to https://www.dukascopy.com/swiss/english/marketwatch/historical/
     /// and open the network tab in browser's console. For any instrument you select
     /// there will be a request made with the URL along the lines of
     /// `https://datafeed.dukascopy.com/datafeed/EURJPY/metadata/HistoryStart.bi5`.
     /// What you're looking for is the next segment after `datafeed` part.
     ///
     /// * `start` and `end` - UTC time intervals between which the data is fetched,
-    /// **for now they have to be rounded to the nearest hour.**
+    ///   **for now they have to be rounded to the nearest hour.**
+    ///   Hours in which the instrument is not [traded](crate::trading_hours) are not requested.
     ///
     /// # Returned items
     ///
     /// * Ok - when data is successfully fetched and parsed
     /// * Err - when some kind of error has occurred (check the error's kind and/or inner error to see which one is it)
+    ///
+    /// If the instrument is unknown, the stream consists of a single error of
+    /// [UnknownInstrument](crate::Kind::UnknownInstrument) kind, if `start` and `end` don't make a valid range
+    /// of a single error of [InvalidArgument](crate::Kind::InvalidArgument) kind.
     pub fn download_ticks(
         &'_ self,
-        instrument: String,
+        instrument: impl AsRef<str>,
         start: PrimitiveDateTime,
         end: PrimitiveDateTime,
     ) -> impl Stream<Item = Result<Tick, crate::error::Error>> + '_ {
-        assert_eq!(start.replace_hour(0).unwrap().as_hms_nano(), (0, 0, 0, 0));
-        assert_eq!(end.replace_hour(0).unwrap().as_hms_nano(), (0, 0, 0, 0));
+        self.download_ticks_with_budget(instrument, start, end, DownloadBudget::new())
+    }
 
-        stream::iter(self.compute_tick_download_times(start, end))
-            .map(move |date| (date, self.generate_tick_download_url(date, &instrument)))
-            .then(move |(date, url)| async move {
-                self.data_supplier.fetch(&url).await.map(|b| (date, b))
-            })
-            .map(
-                |r: Result<(PrimitiveDateTime, Option<Bytes>), crate::error::Error>| {
-                    r.and_then(|(date, bytes)| {
-                        self.decompress_data(bytes)
-                            .map(|decompressed| (date, decompressed))
-                    })
-                },
-            )
-            .flat_map(
-                |r: Result<(PrimitiveDateTime, Vec<u8>), crate::error::Error>| {
+    /// Same as [download_ticks](DukascopyService::download_ticks), but `start` and `end`
+    /// can be in any UTC offset, they're converted to UTC before downloading.
+    ///
+    /// Emitted ticks are still in UTC, use [shift_to_offset](crate::OffsetExt::shift_to_offset)
+    /// to move them to a different offset.
+    pub fn download_ticks_between(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> impl Stream<Item = Result<Tick, crate::error::Error>> + '_ {
+        let to_utc = |e: OffsetDateTime| {
+            let utc = e.to_offset(UtcOffset::UTC);
+            PrimitiveDateTime::new(utc.date(), utc.time())
+        };
+
+        self.download_ticks(instrument, to_utc(start), to_utc(end))
+    }
+
+    /// Same as [download_ticks](DukascopyService::download_ticks), but `start` and `end`
+    /// are chrono date times.
+    ///
+    /// If either of them is out of the range supported by `time`, the stream consists
+    /// of a single error of [InvalidArgument](crate::Kind::InvalidArgument) kind.
+    #[cfg(feature = "chrono")]
+    pub fn download_ticks_chrono(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> impl Stream<Item = Result<Tick, crate::error::Error>> + '_ {
+        match (chrono_to_date_time(start), chrono_to_date_time(end)) {
+            (Some(start), Some(end)) => self
+                .download_ticks_between(instrument, start, end)
+                .left_stream(),
+            _ => stream::once(future::ready(Err(crate::error::Error::new(
+                Kind::InvalidArgument,
+                "start and end have to be in the range supported by time",
+            ))))
+            .right_stream(),
+        }
+    }
+
+    /// Same as [download_ticks](DukascopyService::download_ticks), but the stream ends
+    /// gracefully once a given [DownloadBudget](DownloadBudget) runs out.
+    ///
+    /// After the stream ends, check the budget's [summary](DownloadBudget::summary)
+    /// to see what has been spent and which hour the download can be resumed from.
+    pub fn download_ticks_with_budget(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        budget: DownloadBudget,
+    ) -> impl Stream<Item = Result<Tick, crate::error::Error>> + '_ {
+        let hours = self.download_hour_batches(instrument.as_ref(), start, end, budget, false);
+
+        self.flatten_hours(hours)
+    }
+
+    /// Same as [download_ticks](DukascopyService::download_ticks), but hours are downloaded
+    /// from `end` to `start` and ticks are emitted in reverse chronological order,
+    /// e.g. to get the most recent ticks first.
+    pub fn download_ticks_newest_first(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<Tick, crate::error::Error>> + '_ {
+        let hours = self.download_hour_batches(
+            instrument.as_ref(),
+            start,
+            end,
+            DownloadBudget::new(),
+            true,
+        );
+
+        self.flatten_hours(hours)
+    }
+
+    /// Emits the ticks of downloaded hours one by one, prefetching following hours if enabled
+    fn flatten_hours<S>(&self, hours: S) -> impl Stream<Item = Result<Tick, crate::error::Error>>
+    where
+        S: Stream<Item = Result<HourBatch, crate::error::Error>>,
+    {
+        let hours = hours.map(|r| r.map(|batch| batch.ticks));
+        let ticks = if self.prefetch > 0 {
+            Prefetch::new(hours, self.prefetch).left_stream()
+        } else {
+            hours
+                .flat_map(|r: Result<Vec<Tick>, crate::error::Error>| {
                     let items = match r {
-                        Ok((date, buf)) => self
-                            .buffer_to_ticks(date, buf)
-                            .into_iter()
-                            .map(Ok)
-                            .collect(),
+                        Ok(ticks) => ticks.into_iter().map(Ok).collect(),
                         Err(e) => vec![Err(e)],
                     };
 
                     stream::iter(items)
-                },
-            )
+                })
+                .right_stream()
+        };
+
+        ticks.with_error_policy(self.error_policy)
     }
 
-    fn generate_tick_download_url(&self, time: PrimitiveDateTime, instrument: &str) -> String {
-        let (year, month, day, hour) =
-            (time.year(), time.month() as u8 - 1, time.day(), time.hour());
-        let base_url = &self.base_url;
+    /// Returns a single stream of the ticks of several instruments in chronological order,
+    /// each tick [tagged](InstrumentTick) with its instrument. Ticks with the same time are
+    /// emitted in the order the instruments were given.
+    ///
+    /// The instruments are downloaded concurrently, each of them at the configured
+    /// [concurrency](DukascopyService::with_concurrency). Errors are emitted as they arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruments` - [Instruments](Instrument) for which data is fetched, or their names or aliases
+    /// * `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    pub fn download_ticks_multi<I>(
+        &'_ self,
+        instruments: &[I],
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<InstrumentTick, crate::error::Error>> + '_
+    where
+        I: AsRef<str>,
+    {
+        let streams = instruments
+            .iter()
+            .map(|name| match self.resolve_instrument(name.as_ref()) {
+                Ok(instrument) => {
+                    let name = Arc::<str>::from(instrument.name.as_str());
+                    self.download_ticks(instrument, start, end)
+                        .map(move |r| {
+                            r.map(|tick| InstrumentTick {
+                                instrument: name.clone(),
+                                tick,
+                            })
+                        })
+                        .left_stream()
+                }
+                Err(e) => stream::iter(vec![Err(e)]).right_stream(),
+            })
+            .collect();
 
-        format!("{base_url}/{instrument}/{year}/{month:02}/{day:02}/{hour:02}h_ticks.bi5")
+        MergeByTime::new(streams, |e: &InstrumentTick| e.time)
     }
 
-    fn compute_tick_download_times(
-        &self,
+    /// Same as [download_ticks](DukascopyService::download_ticks), but ticks are emitted
+    /// as Arrow record batches, one for each hour containing any ticks.
+    ///
+    /// See [tick_schema](crate::record_batch::tick_schema) for the schema of the batches.
+    #[cfg(feature = "arrow")]
+    pub fn download_ticks_arrow(
+        &'_ self,
+        instrument: impl AsRef<str>,
         start: PrimitiveDateTime,
         end: PrimitiveDateTime,
-    ) -> Vec<PrimitiveDateTime> {
-        let time_span = end - start;
-        (0..time_span.whole_hours())
-            .map(|e| start + Duration::hours(e))
-            .collect()
+    ) -> impl Stream<Item = Result<arrow_array::RecordBatch, crate::error::Error>> + '_ {
+        self.download_hour_batches(
+            instrument.as_ref(),
+            start,
+            end,
+            DownloadBudget::new(),
+            false,
+        )
+        .filter(|r| future::ready(r.as_ref().map_or(true, |e| !e.ticks.is_empty())))
+        .map(|r| r.and_then(|e| crate::record_batch::ticks_to_record_batch(&e.ticks)))
     }
 
-    fn decompress_data(&self, bytes: Option<Bytes>) -> Result<Vec<u8>, crate::error::Error> {
-        let mut buf = Vec::<u8>::new();
-        match bytes {
-            Some(bytes) => match lzma_decompress(&mut bytes.as_ref(), &mut buf) {
-                Ok(_) => Ok(buf),
-                Err(e) => Err(crate::error::Error {
-                    kind: Kind::Decode,
-                    inner: Box::new(e),
-                }),
-            },
-            None => Ok(Vec::new()),
-        }
+    /// Same as [download_ticks](DukascopyService::download_ticks), but ticks are emitted
+    /// in [batches](HourBatch), one for each downloaded hour, including the hours without
+    /// any ticks. Hours in which the instrument is not traded are skipped as usual.
+    ///
+    /// An error of a failed hour is emitted in place of its batch.
+    pub fn download_hours(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<HourBatch, crate::error::Error>> + '_ {
+        self.download_hour_batches(
+            instrument.as_ref(),
+            start,
+            end,
+            DownloadBudget::new(),
+            false,
+        )
+        .with_error_policy(self.error_policy)
     }
 
-    fn buffer_to_ticks(&self, date: PrimitiveDateTime, bytes: Vec<u8>) -> Vec<Tick> {
-        let offset_date = date.assume_offset(offset!(UTC));
-        let millis_since_epoch = offset_date.unix_timestamp();
+    /// Same as [download_hours](DukascopyService::download_hours), but ticks are stored
+    /// [column by column](TickBatch), e.g. for vectorized processing.
+    pub fn download_tick_batches(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<TickBatch, crate::error::Error>> + '_ {
+        self.download_hours(instrument, start, end)
+            .map(|e| e.map(TickBatch::from))
+    }
 
-        bytes
-            .chunks(20)
-            .map(|e| self.create_tick(millis_since_epoch, e))
-            .collect()
+    /// Returns a stream of the raw hourly files of a given instrument and time interval,
+    /// as served by Dukascopy, i.e. LZMA compressed and not decoded. Useful for mirroring
+    /// the datafeed or decoding the files later with [bi5::parse_hour](crate::bi5::parse_hour).
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    ///
+    /// # Returned items
+    ///
+    /// * Ok - the start of an hour and its file, hours without a published file are skipped
+    /// * Err - when some kind of error has occurred (check the error's kind and/or inner error to see which one is it)
+    ///
+    /// If the instrument is unknown, the stream consists of a single error of
+    /// [UnknownInstrument](crate::Kind::UnknownInstrument) kind, if `start` and `end` don't make a valid range
+    /// of a single error of [InvalidArgument](crate::Kind::InvalidArgument) kind.
+    pub fn download_raw(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<(PrimitiveDateTime, Bytes), crate::error::Error>> + '_ {
+        let instrument = self.resolve_download(instrument.as_ref(), start, end);
+
+        self.download_raw_files(instrument, start, end)
+            .with_error_policy(self.error_policy)
     }
 
-    fn create_tick(&self, millis_since_epoch: i64, bytes: &[u8]) -> Tick {
-        debug_assert!(bytes.len() == 20);
+    /// Same as [download_ticks](DukascopyService::download_ticks), but prices are kept
+    /// as the integers stored in the datafeed's files, see [RawTick](RawTick).
+    pub fn download_raw_ticks(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<RawTick, crate::error::Error>> + '_ {
+        let instrument = self.resolve_download(instrument.as_ref(), start, end);
+        // only used for files, which aren't fetched when the instrument is unknown
+        let name = instrument
+            .as_ref()
+            .map_or_else(|_| String::new(), |e| e.name.clone());
 
-        Tick {
-            time: millis_since_epoch + BigEndian::read_u32(&bytes[0..4]) as i64,
-            ask: BigEndian::read_u32(&bytes[4..8]) as f64 / 100_000f64,
-            bid: BigEndian::read_u32(&bytes[8..12]) as f64 / 100_000f64,
-            ask_volume: BigEndian::read_f32(&bytes[12..16]) as f64,
-            bid_volume: BigEndian::read_f32(&bytes[16..20]) as f64,
-        }
+        self.download_raw_files(instrument, start, end)
+            .flat_map(move |e| {
+                let ticks = e.and_then(|(hour, bytes)| {
+                    bi5::parse_hour_raw(&bytes, hour).map_err(|e| {
+                        e.with_url(&self.generate_tick_download_url(hour, &name))
+                            .with_item(&name, hour)
+                    })
+                });
+
+                match ticks {
+                    Ok(ticks) => stream::iter(ticks.into_iter().map(Ok).collect::<Vec<_>>()),
+                    Err(e) => stream::iter(vec![Err(e)]),
+                }
+            })
+            .with_error_policy(self.error_policy)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use approx::assert_abs_diff_eq;
-    use byteorder::{BigEndian, ByteOrder};
-    use bytes::Bytes;
-    use futures::StreamExt;
-    use lzma_rs::lzma_compress;
-    use time::macros::datetime;
+    /// Returns a stream of the raw hourly files of an already resolved instrument,
+    /// or a single error if it couldn't be resolved
+    fn download_raw_files(
+        &'_ self,
+        instrument: Result<Instrument, crate::error::Error>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> impl Stream<Item = Result<(PrimitiveDateTime, Bytes), crate::error::Error>> + '_ {
+        let (times, invalid_range) = match instrument {
+            Ok(instrument) => (
+                self.compute_tick_download_times(&instrument, start, end)
+                    .into_iter()
+                    .map(|e| (instrument.name.clone(), e))
+                    .collect(),
+                None,
+            ),
+            Err(e) => (Vec::new(), Some(Err(e))),
+        };
 
-    use crate::{
-        data_supplier::tests::{InMemoryDataSupplier, TestResourceDataSupplier},
-        tick::Tick,
-        DukascopyService,
-    };
+        let files = stream::iter(times)
+            .map(move |(name, hour)| async move {
+                let bytes = self.fetch_raw_hour(&name, hour).await;
+                bytes.map(|e| e.map(|bytes| (hour, bytes))).transpose()
+            })
+            .buffered(self.concurrency)
+            .filter_map(future::ready);
 
-    #[tokio::test]
-    async fn parses_bi5_file_to_ticks() {
-        let mut bytes = vec![0u8; 20];
+        stream::iter(invalid_range).chain(files)
+    }
 
-        BigEndian::write_i32(&mut bytes[0..4], 0x000000DA);
-        BigEndian::write_i32(&mut bytes[4..8], 0x0001B4C7);
-        BigEndian::write_i32(&mut bytes[8..12], 0x0001B4C4);
-        BigEndian::write_i32(&mut bytes[12..16], 0x3F8F5C29);
-        BigEndian::write_i32(&mut bytes[16..20], 0x3F400000);
+    /// Returns a never-ending stream of ticks of a given instrument, starting at `start`
+    /// and following the new data as it's published.
+    ///
+    /// Dukascopy publishes a file once its hour is over, usually with a delay of a few
+    /// minutes. Once the stream catches up with the present, it waits for the hours to
+    /// end and polls for their files as described by given [FollowOptions](FollowOptions).
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument` - see [download_ticks](DukascopyService::download_ticks)
+    /// * `start` - UTC time from which the data is fetched, rounded to the nearest hour
+    /// * `options` - how often and for how long the files not published yet are polled for
+    ///
+    /// # Returned items
+    ///
+    /// * Ok - when data is successfully fetched and parsed
+    /// * Err - when some kind of error has occurred, the stream continues with the next hour
+    ///
+    /// If the instrument is unknown, the stream consists of a single error of
+    /// [UnknownInstrument](crate::Kind::UnknownInstrument) kind, if `start` isn't rounded to the nearest hour
+    /// of a single error of [InvalidArgument](crate::Kind::InvalidArgument) kind.
+    pub fn follow_ticks(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        options: FollowOptions,
+    ) -> impl Stream<Item = Result<Tick, crate::error::Error>> + '_ {
+        let instrument = self.resolve_download(instrument.as_ref(), start, start + Duration::HOUR);
 
-        let mut compressed_bytes = Vec::<u8>::new();
-        lzma_compress(&mut &bytes[..], &mut compressed_bytes).unwrap();
+        stream::unfold(Some((instrument, start)), move |state| async move {
+            let (instrument, mut hour) = match state? {
+                (Ok(instrument), hour) => (instrument, hour),
+                (Err(e), _) => return Some((vec![Err(e)], None)),
+            };
 
-        let service = DukascopyService::new(
-            String::from(""),
-            Box::new(InMemoryDataSupplier {
-                data: Some(Bytes::from_iter(compressed_bytes)),
-            }),
-        );
+            let mut closed_hours = 0;
+            loop {
+                if !self.is_traded(&instrument, hour) {
+                    // closed hours in the future are waited out, so that the stream never gets
+                    // ahead of the present, and long runs of closed hours in the past yield
+                    // now and then, so that the runtime isn't blocked
+                    let wait = (hour + Duration::HOUR).assume_utc() - OffsetDateTime::now_utc();
+                    if wait.is_positive() {
+                        debug_event!(hour = %hour, "waiting for a closed hour to end");
+                        tokio::time::sleep(wait.unsigned_abs()).await;
+                    } else {
+                        closed_hours += 1;
+                        if closed_hours % MAX_CLOSED_HOURS_SKIPPED == 0 {
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                    hour += Duration::HOUR;
+                    continue;
+                }
 
-        let ticks = service
-            .download_ticks(
-                String::from("EURGBP"),
-                datetime!(2020-03-12 01:00),
-                datetime!(2020-03-12 02:00),
-            )
-            .collect::<Vec<Result<Tick, crate::error::Error>>>()
-            .await;
+                let published = (hour + Duration::HOUR).assume_utc();
+                let wait = published - OffsetDateTime::now_utc();
+                if wait.is_positive() {
+                    debug_event!(hour = %hour, "waiting for the hour to end");
+                    tokio::time::sleep(wait.unsigned_abs()).await;
+                }
 
-        assert_eq!(ticks.len(), 1);
+                let bytes = match self.fetch_raw_hour(&instrument.name, hour).await {
+                    Ok(None) if OffsetDateTime::now_utc() < published + options.max_delay() => {
+                        tokio::time::sleep(options.poll_interval()).await;
+                        continue;
+                    }
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        return Some((vec![Err(e)], Some((Ok(instrument), hour + Duration::HOUR))))
+                    }
+                };
 
-        let tick = ticks[0].as_ref().unwrap();
-        assert_eq!(
-            tick.time,
-            datetime!(2020-03-12 01:00 UTC).unix_timestamp() + 218
-        );
-        assert_eq!(tick.ask, 1.11815);
-        assert_eq!(tick.bid, 1.11812);
-        assert_abs_diff_eq!(tick.ask_volume, 1.12, epsilon = 0.000_001);
-        assert_eq!(tick.bid_volume, 0.75);
+                let items = match self.decode_hour(&instrument, hour, bytes) {
+                    Ok(ticks) => ticks.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+
+                return Some((items, Some((Ok(instrument), hour + Duration::HOUR))));
+            }
+        })
+        .flat_map(stream::iter)
+        .with_error_policy(self.error_policy)
     }
 
-    #[tokio::test]
-    async fn fetches_and_parses_multiple_bi5_files() {
-        let service =
-            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+    /// Returns a stream of ticks of each downloaded hour, in reverse order if `newest_first` is set
+    fn download_hour_batches(
+        &'_ self,
+        instrument: &str,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        budget: DownloadBudget,
+        newest_first: bool,
+    ) -> impl Stream<Item = Result<HourBatch, crate::error::Error>> + '_ {
+        let (mut times, invalid_range) = match self.resolve_download(instrument, start, end) {
+            Ok(instrument) => (
+                self.compute_tick_download_times(&instrument, start, end)
+                    .into_iter()
+                    .map(|e| (instrument.clone(), e))
+                    .collect::<Vec<_>>(),
+                None,
+            ),
+            Err(e) => (Vec::new(), Some(Err(e))),
+        };
+        if newest_first {
+            times.reverse();
+        }
 
-        let ticks = service.download_ticks(
-            String::from("EURGBP"),
-            datetime!(2020-03-12 00:00),
-            datetime!(2020-03-13 00:00),
+        let request_budget = budget.clone();
+        let cancellation_budget = budget.clone();
+        let planned = times.iter().map(|(_, e)| *e).collect();
+        let mut progress = Progress::new(start, times.len() as u64);
+        let hours = stream::iter(times)
+            .take_while(move |(_, date)| future::ready(request_budget.spend_request(*date)))
+            .map(move |(instrument, date)| {
+                let budget = budget.clone();
+                let name = instrument.name.clone();
+                let hour = async move {
+                    let bytes = match self.fetch_raw_hour(&instrument.name, date).await {
+                        Ok(bytes) => bytes,
+                        Err(e) => return (date, 0, Err(e)),
+                    };
+                    let size = bytes.as_ref().map_or(0, |e| e.len() as u64);
+                    budget.spend_bytes(size);
+
+                    (date, size, self.decode_hour(&instrument, date, bytes))
+                };
+
+                in_hour_span(&name, date, hour)
+            })
+            .buffered(self.concurrency)
+            .map(move |(date, size, result)| {
+                if let Some(reporter) = &self.progress {
+                    progress.complete(date, size, &result);
+                    reporter.on_hour(&progress);
+                }
+
+                (date, result)
+            });
+
+        stream::iter(invalid_range).chain(cancellation_budget.until_cancelled(hours, planned).map(
+            move |(hour, result)| {
+                result.map(|mut ticks| {
+                    if newest_first {
+                        ticks.reverse();
+                    }
+                    HourBatch { hour, ticks }
+                })
+            },
+        ))
+    }
+
+    /// Returns a stream of candles for a given instrument and time interval,
+    /// aggregated from the ticks returned by [download_ticks](DukascopyService::download_ticks).
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    /// * `timeframe` - period each candle covers, e.g. [Timeframe::M1](Timeframe::M1)
+    /// * `source` - whether candles are built from bid, ask or mid prices
+    ///
+    /// # Returned items
+    ///
+    /// * Ok - a candle for each period with at least one tick
+    /// * Err - when some kind of error has occurred (check the error's kind and/or inner error to see which one is it)
+    pub fn download_candles(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        timeframe: Timeframe,
+        source: PriceSource,
+    ) -> impl Stream<Item = Result<Candle, crate::error::Error>> + '_ {
+        ticks_to_candles(
+            self.download_ticks(instrument, start, end),
+            timeframe,
+            source,
+        )
+    }
+
+    /// Returns a stream of one minute candles for a given instrument and time interval,
+    /// fetched directly from the daily candle files served by Dukascopy.
+    ///
+    /// This requires a single request per day rather than per hour, and is therefore
+    /// much faster than [download_candles](DukascopyService::download_candles) for long ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    /// * `side` - whether bid or ask candles are fetched
+    ///
+    /// # Returned items
+    ///
+    /// * Ok - candles within the range, as published by Dukascopy (including periods without trading)
+    /// * Err - when some kind of error has occurred (check the error's kind and/or inner error to see which one is it)
+    pub fn download_minute_candles(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        side: OfferSide,
+    ) -> impl Stream<Item = Result<Candle, crate::error::Error>> + '_ {
+        let (days, invalid_range) = match self.resolve_download(instrument.as_ref(), start, end) {
+            Ok(instrument) => (
+                self.compute_candle_download_days(start, end)
+                    .into_iter()
+                    .map(|e| (instrument.clone(), e))
+                    .collect(),
+                None,
+            ),
+            Err(e) => (Vec::new(), Some(Err(e))),
+        };
+        let (start, end) = (
+            start.assume_utc().unix_timestamp() * 1000,
+            end.assume_utc().unix_timestamp() * 1000,
         );
 
-        assert_eq!(ticks.count().await, 12464)
+        stream::iter(invalid_range)
+            .chain(
+                stream::iter(days)
+                    .map(move |(instrument, day): (Instrument, Date)| {
+                        let url = self.generate_candle_download_url(day, &instrument.name, side);
+                        async move {
+                            let bytes = self.fetch_url(&url).await;
+                            bytes
+                                .and_then(|bytes| {
+                                    bi5::parse_minute_candles(
+                                        &bytes.unwrap_or_default(),
+                                        day,
+                                        instrument.decimal_factor,
+                                    )
+                                })
+                                .map_err(|e| {
+                                    e.with_url(&url).with_item(&instrument.name, day.midnight())
+                                })
+                        }
+                    })
+                    .buffered(self.concurrency)
+                    .flat_map(move |r: Result<Vec<Candle>, crate::error::Error>| {
+                        let items = match r {
+                            Ok(candles) => candles
+                                .into_iter()
+                                .filter(|e| start <= e.time && e.time < end)
+                                .map(Ok)
+                                .collect(),
+                            Err(e) => vec![Err(e)],
+                        };
+
+                        stream::iter(items)
+                    }),
+            )
+            .with_error_policy(self.error_policy)
+    }
+
+    /// Returns a stream of one minute candles for a given instrument and time interval,
+    /// aggregated from the datafeed's ticks like [download_candles](DukascopyService::download_candles),
+    /// except that hours whose files are missing or corrupt are filled in from the JSON candle API
+    /// behind Dukascopy's web charts.
+    ///
+    /// Each candle is flagged with its [source](CandleSource), since the chart API only publishes
+    /// minute candles and is an undocumented service that can change without notice. It may
+    /// require a `Referer` header, which can be set with [with_header](crate::DukascopyServiceBuilder::with_header).
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    /// * `side` - whether candles are built from bid or ask prices
+    ///
+    /// # Returned items
+    ///
+    /// * Ok - a candle for each minute with at least one tick, in chronological order
+    /// * Err - when an hour couldn't be fetched from either source (check the error's kind and/or inner error to see which one is it)
+    pub fn download_candles_with_fallback(
+        &'_ self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        side: OfferSide,
+    ) -> impl Stream<Item = Result<SourcedCandle, crate::error::Error>> + '_ {
+        let (hours, invalid_range) = match self.resolve_download(instrument.as_ref(), start, end) {
+            Ok(instrument) => (
+                self.compute_tick_download_times(&instrument, start, end)
+                    .into_iter()
+                    .map(|e| (instrument.clone(), e))
+                    .collect(),
+                None,
+            ),
+            Err(e) => (Vec::new(), Some(Err(e))),
+        };
+
+        stream::iter(invalid_range)
+            .chain(
+                stream::iter(hours)
+                    .map(
+                        move |(instrument, hour): (Instrument, PrimitiveDateTime)| async move {
+                            self.fetch_candles_with_fallback(&instrument, hour, side)
+                                .await
+                                .map_err(|e| e.with_item(&instrument.name, hour))
+                        },
+                    )
+                    .buffered(self.concurrency)
+                    .flat_map(|r: Result<Vec<SourcedCandle>, crate::error::Error>| {
+                        let items = match r {
+                            Ok(candles) => candles.into_iter().map(Ok).collect(),
+                            Err(e) => vec![Err(e)],
+                        };
+
+                        stream::iter(items)
+                    }),
+            )
+            .with_error_policy(self.error_policy)
+    }
+
+    async fn fetch_candles_with_fallback(
+        &self,
+        instrument: &Instrument,
+        hour: PrimitiveDateTime,
+        side: OfferSide,
+    ) -> Result<Vec<SourcedCandle>, crate::error::Error> {
+        let error = match self.fetch_raw_hour(&instrument.name, hour).await {
+            Ok(Some(bytes)) => match self.decode_hour(instrument, hour, Some(bytes)) {
+                Ok(ticks) => {
+                    let source = match side {
+                        OfferSide::Bid => PriceSource::Bid,
+                        OfferSide::Ask => PriceSource::Ask,
+                    };
+                    let candles = ticks_to_candles(
+                        stream::iter(ticks.into_iter().map(Ok)),
+                        Timeframe::M1,
+                        source,
+                    );
+
+                    return candles
+                        .map(|e| {
+                            e.map(|candle| SourcedCandle {
+                                candle,
+                                source: CandleSource::Ticks,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                        .await
+                        .into_iter()
+                        .collect();
+                }
+                Err(e) if e.kind == Kind::Decode => Some(e),
+                Err(e) => return Err(e),
+            },
+            Ok(None) => None,
+            Err(e) => return Err(e),
+        };
+
+        let url = chart_api_url(&self.chart_api_url, &instrument.name, hour, side);
+        warn_event!(
+            url,
+            "hour missing or corrupt, falling back to the chart API"
+        );
+        let (start, end) = (
+            hour.assume_utc().unix_timestamp() * 1000,
+            (hour + Duration::HOUR).assume_utc().unix_timestamp() * 1000,
+        );
+
+        let candles = self
+            .fetch_from_supplier(&url)
+            .await
+            .and_then(|bytes| parse_chart_response(&bytes.unwrap_or_default()))
+            .map_err(|e| e.with_url(&url));
+
+        match (candles, error) {
+            (Ok(candles), _) => Ok(candles
+                .into_iter()
+                .filter(|e| start <= e.time && e.time < end)
+                .map(|candle| SourcedCandle {
+                    candle,
+                    source: CandleSource::ChartApi,
+                })
+                .collect()),
+            // the datafeed's error explains why the fallback was needed in the first place
+            (Err(_), Some(error)) => Err(error),
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    /// Returns the most recent tick available for a given instrument.
+    ///
+    /// Dukascopy publishes hourly files with a delay, so starting from the current hour
+    /// the service probes back one hour at a time until it finds an hour that contains data.
+    /// Hours in which the instrument is not [traded](crate::trading_hours) are skipped.
+    /// At most a week is probed, which is enough to get past weekends and holidays.
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument` - instrument for which data is fetched, see [download_ticks](DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok(Some) - the last tick of the most recent hour containing any data
+    /// * Ok(None) - when there was no data in the probed period
+    /// * Err - when some kind of error has occurred (check the error's kind and/or inner error to see which one is it).
+    ///   Hours that are missing or can't be decoded are skipped, their error is returned
+    ///   only when no earlier hour had any data.
+    pub async fn current_quote(
+        &self,
+        instrument: impl AsRef<str>,
+    ) -> Result<Option<Tick>, crate::error::Error> {
+        let now = OffsetDateTime::now_utc();
+        let current_hour = PrimitiveDateTime::new(now.date(), Time::MIDNIGHT)
+            .replace_hour(now.hour())
+            .unwrap();
+
+        let instrument = self.resolve_instrument(instrument.as_ref())?;
+        let open_hours = (0..QUOTE_PROBE_HOURS)
+            .map(|e| current_hour - Duration::hours(e))
+            .filter(|e| self.is_traded(&instrument, *e));
+
+        // a missing or corrupt hour doesn't mean there's no data before it
+        let mut skipped = None;
+        for hour in open_hours {
+            match self.fetch_hour(&instrument, hour).await {
+                Ok(ticks) => {
+                    if let Some(tick) = ticks.last() {
+                        return Ok(Some(*tick));
+                    }
+                }
+                Err(e) if matches!(e.kind, Kind::NotFound | Kind::Decode) => {
+                    warn_event!(hour = %hour, error = %e, "skipping an hour while probing for a quote");
+                    skipped.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        match skipped {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the earliest time for which Dukascopy has data of a given instrument,
+    /// as published in the instrument's `metadata/HistoryStart.bi5` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument` - instrument for which data is fetched, see [download_ticks](DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok(Some) - the earliest time with data, in UTC
+    /// * Ok(None) - when Dukascopy doesn't publish the start of the instrument's history
+    /// * Err - when some kind of error has occurred (check the error's kind and/or inner error to see which one is it)
+    pub async fn history_start(
+        &self,
+        instrument: impl AsRef<str>,
+    ) -> Result<Option<PrimitiveDateTime>, crate::error::Error> {
+        let instrument = self.resolve_instrument(instrument.as_ref())?;
+        let url = format!(
+            "{}/{}/metadata/HistoryStart.bi5",
+            self.base_url, instrument.name
+        );
+
+        let bytes = self.fetch_url(&url).await?;
+        bytes.map(|e| bi5::parse_history_start(&e)).transpose()
+    }
+
+    /// Moves `start` forward to the first full hour of the instrument's
+    /// [history](DukascopyService::history_start), so that no requests are made
+    /// for hours before Dukascopy has any data.
+    ///
+    /// # Returned value
+    ///
+    /// * Ok(Some) - the clamped range, ready to be passed to [download_ticks](DukascopyService::download_ticks)
+    /// * Ok(None) - when the whole range lies before the start of the history
+    /// * Err - when the range is invalid or fetching the history start failed
+    pub async fn clamp_to_history_start(
+        &self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> Result<Option<(PrimitiveDateTime, PrimitiveDateTime)>, crate::error::Error> {
+        validate_range(start, end)?;
+
+        let history_start = match self.history_start(instrument).await? {
+            Some(history_start) => history_start,
+            None => return Ok(Some((start, end))),
+        };
+
+        // history usually starts in the middle of an hour, whose file contains data as well
+        let first_hour =
+            history_start.replace_time(Time::from_hms(history_start.hour(), 0, 0).unwrap());
+        let start = start.max(first_hour);
+
+        Ok((start < end).then_some((start, end)))
+    }
+
+    /// Returns the hours [download_ticks](DukascopyService::download_ticks) would request
+    /// for a given instrument and time interval, without downloading them, e.g. to estimate
+    /// the number of requests up front.
+    ///
+    /// Hours in which the instrument is not [traded](crate::trading_hours) are left out
+    /// and the range is [clamped](DukascopyService::clamp_to_history_start) to the start
+    /// of the instrument's history, which is the only request made. Hours stored in the
+    /// [cache](DukascopyService::with_cache) are still listed, although they won't be requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - the planned hours in chronological order, empty when the whole range lies
+    ///   before the start of the history
+    /// * Err - when the range or instrument is invalid or fetching the history start failed
+    pub async fn plan_download(
+        &self,
+        instrument: impl AsRef<str>,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> Result<Vec<PlannedHour>, crate::error::Error> {
+        let instrument = self.resolve_download(instrument.as_ref(), start, end)?;
+        let (start, end) = match self.clamp_to_history_start(&instrument, start, end).await? {
+            Some(range) => range,
+            None => return Ok(Vec::new()),
+        };
+
+        let hours = self
+            .compute_tick_download_times(&instrument, start, end)
+            .into_iter()
+            .map(|hour| PlannedHour {
+                hour,
+                url: self.generate_tick_download_url(hour, &instrument.name),
+            })
+            .collect();
+
+        Ok(hours)
+    }
+
+    /// Downloads the list of instruments published by Dukascopy and makes them
+    /// available to this service, so instruments that are not [built-in](Instrument::built_in)
+    /// can be downloaded without registering them manually.
+    ///
+    /// Dukascopy doesn't publish how prices of instruments are stored, for instruments
+    /// that are not built-in the [decimal factor](Instrument::decimal_factor) is derived
+    /// from the pip size, so double-check it before relying on the prices.
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - all instruments in the list
+    /// * Err - when fetching or parsing the list failed, the registered instruments are left as they were
+    pub async fn list_instruments(&self) -> Result<Vec<Instrument>, crate::error::Error> {
+        let bytes = self
+            .fetch_from_supplier(INSTRUMENTS_URL)
+            .await
+            .map_err(|e| e.with_url(INSTRUMENTS_URL))?
+            .ok_or_else(|| {
+                crate::error::Error::new(Kind::NotFound, "instrument list is not available")
+                    .with_url(INSTRUMENTS_URL)
+            })?;
+        let instruments = parse_instrument_list(&bytes)?;
+
+        let mut registered = self.instruments.write().unwrap();
+        registered.retain(|e| !instruments.iter().any(|i| i.name == e.name));
+        registered.extend(instruments.iter().cloned());
+
+        Ok(instruments)
+    }
+
+    /// Returns the instrument with a given name or alias, either registered
+    /// with [with_instrument](DukascopyService::with_instrument) or a built-in one
+    pub fn resolve_instrument(&self, name: &str) -> Result<Instrument, crate::error::Error> {
+        self.instruments
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|e| e.matches(name))
+            .cloned()
+            .or_else(|| Instrument::lookup(name))
+            .ok_or_else(|| unknown_instrument(name, &self.instruments.read().unwrap()))
+    }
+
+    pub(crate) fn resolve_download(
+        &self,
+        instrument: &str,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> Result<Instrument, crate::error::Error> {
+        validate_range(start, end)?;
+        self.resolve_instrument(instrument)
+    }
+
+    pub(crate) fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub(crate) async fn fetch_hour(
+        &self,
+        instrument: &Instrument,
+        date: PrimitiveDateTime,
+    ) -> Result<Vec<Tick>, crate::error::Error> {
+        let bytes = self.fetch_raw_hour(&instrument.name, date).await?;
+        self.decode_hour(instrument, date, bytes)
+    }
+
+    async fn fetch_raw_hour(
+        &self,
+        instrument: &str,
+        date: PrimitiveDateTime,
+    ) -> Result<Option<Bytes>, crate::error::Error> {
+        let url = self.generate_tick_download_url(date, instrument);
+        self.fetch_url(&url)
+            .await
+            .map_err(|e| e.with_item(instrument, date))
+    }
+
+    async fn fetch_url(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let cache = self.cache.as_ref().map(|cache| {
+            let path = url.strip_prefix(&self.base_url).unwrap_or(url);
+            (cache, path.trim_start_matches('/'))
+        });
+
+        if let Some((cache, path)) = cache {
+            if let Some(bytes) = cache.get(path).await.map_err(|e| e.with_url(url))? {
+                debug_event!(url, "served from cache");
+                return Ok(Some(bytes));
+            }
+        }
+
+        let bytes = self
+            .fetch_from_supplier(url)
+            .await
+            .map_err(|e| e.with_url(url))?;
+        if let (Some((cache, path)), Some(bytes)) = (cache, &bytes) {
+            cache.put(path, bytes).await.map_err(|e| e.with_url(url))?;
+        }
+
+        Ok(bytes)
+    }
+
+    async fn fetch_from_supplier(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let mut attempt = 1;
+        loop {
+            let delay = {
+                let result = self.fetch_once(url).await;
+                let delay = match (&self.retry, &result) {
+                    (Some(retry), Err(e)) => retry.decide(attempt, e),
+                    _ => None,
+                };
+                match delay {
+                    Some(delay) => {
+                        #[cfg(feature = "tracing")]
+                        if let Err(e) = &result {
+                            tracing::warn!(
+                                url,
+                                attempt,
+                                delay_ms = delay.as_millis() as u64,
+                                error = %e,
+                                "request failed, retrying"
+                            );
+                        }
+                        delay
+                    }
+                    None => return result,
+                }
+            };
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn fetch_once(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        if let Some(pacer) = &self.pacer {
+            pacer.wait().await;
+        }
+
+        debug_event!(url, "request started");
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let result = match &self.adaptive_concurrency {
+            Some(controller) => {
+                let permit = controller.acquire().await;
+                let started = std::time::Instant::now();
+                let result = self.fetch_within_budget(url).await;
+                controller.record(permit, &result, started.elapsed());
+                result
+            }
+            None => self.fetch_within_budget(url).await,
+        };
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(bytes) => tracing::debug!(
+                url,
+                bytes = bytes.as_ref().map_or(0, |e| e.len()),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "request finished"
+            ),
+            Err(e) => tracing::debug!(
+                url,
+                error = %e,
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "request failed"
+            ),
+        }
+
+        result
+    }
+
+    async fn fetch_within_budget(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        match &self.rate_budget {
+            Some(budget) => {
+                let _permit = budget.acquire().await;
+                self.fetch_with_watchdog(url).await
+            }
+            None => self.fetch_with_watchdog(url).await,
+        }
+    }
+
+    fn decode_hour(
+        &self,
+        instrument: &Instrument,
+        date: PrimitiveDateTime,
+        bytes: Option<Bytes>,
+    ) -> Result<Vec<Tick>, crate::error::Error> {
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let result = bi5::parse_hour(&bytes.unwrap_or_default(), date, instrument.decimal_factor)
+            .and_then(|ticks| {
+                if self.validate_ticks {
+                    bi5::validate_hour(&ticks, date)?;
+                }
+                Ok(ticks)
+            })
+            .map_err(|e| {
+                e.with_url(&self.generate_tick_download_url(date, &instrument.name))
+                    .with_item(&instrument.name, date)
+            });
+
+        #[cfg(feature = "tracing")]
+        if let Ok(ticks) = &result {
+            tracing::debug!(
+                ticks = ticks.len(),
+                decode_us = started.elapsed().as_micros() as u64,
+                "hour decoded"
+            );
+        }
+
+        result
+    }
+
+    async fn fetch_with_watchdog(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let timeout = match self.stall_timeout {
+            Some(timeout) => timeout,
+            None => return self.data_supplier.fetch(url).await,
+        };
+
+        let mut attempt = 1;
+        loop {
+            match tokio::time::timeout(timeout, self.data_supplier.fetch(url)).await {
+                Ok(result) => return result,
+                Err(elapsed) if attempt == STALL_ATTEMPTS => {
+                    return Err(crate::error::Error::new(Kind::Stalled, elapsed))
+                }
+                Err(_) => {
+                    warn_event!(url, attempt, "request stalled, retrying");
+                    attempt += 1
+                }
+            }
+        }
+    }
+
+    fn generate_tick_download_url(&self, time: PrimitiveDateTime, instrument: &str) -> String {
+        format!("{}/{}", self.base_url, tick_file_path(instrument, time))
+    }
+
+    fn generate_candle_download_url(&self, day: Date, instrument: &str, side: OfferSide) -> String {
+        let (year, month, day) = (day.year(), day.month() as u8 - 1, day.day());
+        let base_url = &self.base_url;
+        let side = match side {
+            OfferSide::Bid => "BID",
+            OfferSide::Ask => "ASK",
+        };
+
+        format!("{base_url}/{instrument}/{year}/{month:02}/{day:02}/{side}_candles_min_1.bi5")
+    }
+
+    pub(crate) fn compute_tick_download_times(
+        &self,
+        instrument: &Instrument,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> Vec<PrimitiveDateTime> {
+        let time_span = end - start;
+        (0..time_span.whole_hours())
+            .map(|e| start + Duration::hours(e))
+            .filter(|e| self.is_traded(instrument, *e))
+            .collect()
+    }
+
+    fn is_traded(&self, instrument: &Instrument, hour: PrimitiveDateTime) -> bool {
+        !self.skip_closed_hours || instrument.trading_hours.is_open(hour.assume_utc())
+    }
+
+    fn compute_candle_download_days(
+        &self,
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> Vec<Date> {
+        let last_day = (end - Duration::nanoseconds(1)).date();
+        (0..)
+            .map(|e| start.date() + Duration::days(e))
+            .take_while(|e| *e <= last_day)
+            .collect()
+    }
+}
+
+/// Returns the path of the file with ticks of a given hour relative to the datafeed's base URL,
+/// e.g. `EURUSD/2020/02/12/10h_ticks.bi5` (months are counted from zero)
+pub(crate) fn tick_file_path(instrument: &str, time: PrimitiveDateTime) -> String {
+    let (year, month, day, hour) = (time.year(), time.month() as u8 - 1, time.day(), time.hour());
+
+    format!("{instrument}/{year}/{month:02}/{day:02}/{hour:02}h_ticks.bi5")
+}
+
+/// Checks that `start` and `end` are rounded to full hours and make a non-empty range
+pub(crate) fn validate_range(
+    start: PrimitiveDateTime,
+    end: PrimitiveDateTime,
+) -> Result<(), crate::error::Error> {
+    let is_full_hour = |e: PrimitiveDateTime| (e.minute(), e.second(), e.nanosecond()) == (0, 0, 0);
+
+    let message = if !is_full_hour(start) || !is_full_hour(end) {
+        "start and end have to be rounded to full hours"
+    } else if end <= start {
+        "end has to be after start"
+    } else {
+        return Ok(());
+    };
+
+    Err(crate::error::Error::new(Kind::InvalidArgument, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use approx::assert_abs_diff_eq;
+    use async_trait::async_trait;
+    use byteorder::{BigEndian, ByteOrder};
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use lzma_rs::lzma_compress;
+    use time::macros::datetime;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::trading_hours::{TimeZone, TradingHours};
+    use crate::{
+        bi5,
+        data_supplier::tests::{
+            InMemoryDataSupplier, PendingDataSupplier, TestResourceDataSupplier,
+        },
+        tick::Tick,
+        Candle, CandleSource, DataSupplier, DownloadBudget, DukascopyService, ErrorContext,
+        FollowOptions, HourBatch, Instrument, InstrumentTick, Kind, OfferSide, PlannedHour,
+        PriceSource, Timeframe,
+    };
+
+    #[tokio::test]
+    async fn attaches_context_to_errors() {
+        let service = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from_static(b"not lzma")),
+            }),
+        );
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        let error = ticks[0].as_ref().unwrap_err();
+        assert!(matches!(error.kind, Kind::Decode));
+        assert_eq!(
+            error.context,
+            ErrorContext {
+                url: Some(String::from(
+                    "https://example.com/EURGBP/2020/02/12/06h_ticks.bi5"
+                )),
+                instrument: Some(String::from("EURGBP")),
+                hour: Some(datetime!(2020-03-12 06:00)),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_implausible_ticks_in_strict_mode() {
+        let hour = datetime!(2020-03-12 06:00);
+        let tick = |offset: i64| Tick {
+            time: hour.assume_utc().unix_timestamp() * 1000 + offset,
+            ask: 0.86,
+            bid: 0.8599,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+        let bytes = bi5::encode_hour(&[tick(1_000), tick(500)], hour, 100_000.0).unwrap();
+
+        let download = |validate: bool| {
+            let service = DukascopyService::new(
+                String::from(""),
+                Box::new(InMemoryDataSupplier {
+                    data: Some(Bytes::from(bytes.clone())),
+                }),
+            )
+            .with_strict_validation(validate);
+
+            async move {
+                service
+                    .download_ticks("EURGBP", hour, datetime!(2020-03-12 07:00))
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+
+        assert!(download(false).await.iter().all(|e| e.is_ok()));
+
+        let results = download(true).await;
+        assert_eq!(results.len(), 1);
+        let error = results[0].as_ref().unwrap_err();
+        assert!(matches!(error.kind, Kind::Decode));
+        assert_eq!(error.context.instrument.as_deref(), Some("EURGBP"));
+    }
+
+    #[tokio::test]
+    async fn parses_bi5_file_to_ticks() {
+        let mut bytes = [0u8; 20];
+
+        BigEndian::write_i32(&mut bytes[0..4], 0x000000DA);
+        BigEndian::write_i32(&mut bytes[4..8], 0x0001B4C7);
+        BigEndian::write_i32(&mut bytes[8..12], 0x0001B4C4);
+        BigEndian::write_i32(&mut bytes[12..16], 0x3F8F5C29);
+        BigEndian::write_i32(&mut bytes[16..20], 0x3F400000);
+
+        let mut compressed_bytes = Vec::<u8>::new();
+        lzma_compress(&mut &bytes[..], &mut compressed_bytes).unwrap();
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from_iter(compressed_bytes)),
+            }),
+        );
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 01:00),
+                datetime!(2020-03-12 02:00),
+            )
+            .collect::<Vec<Result<Tick, crate::error::Error>>>()
+            .await;
+
+        assert_eq!(ticks.len(), 1);
+
+        let tick = ticks[0].as_ref().unwrap();
+        assert_eq!(
+            tick.time,
+            datetime!(2020-03-12 01:00 UTC).unix_timestamp() * 1000 + 218
+        );
+        assert_eq!(tick.ask, 1.11815);
+        assert_eq!(tick.bid, 1.11812);
+        assert_abs_diff_eq!(tick.ask_volume, 1.12, epsilon = 0.000_001);
+        assert_eq!(tick.bid_volume, 0.75);
+    }
+
+    #[tokio::test]
+    async fn scales_prices_per_instrument() {
+        let mut bytes = [0u8; 20];
+        BigEndian::write_i32(&mut bytes[4..8], 0x0001B4C7);
+        BigEndian::write_i32(&mut bytes[8..12], 0x0001B4C4);
+
+        let mut compressed_bytes = Vec::<u8>::new();
+        lzma_compress(&mut &bytes[..], &mut compressed_bytes).unwrap();
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from_iter(compressed_bytes)),
+            }),
+        )
+        .with_instrument(Instrument {
+            decimal_factor: 10.0,
+            ..Instrument::lookup("EURGBP").unwrap()
+        });
+
+        let ask = |instrument: &'static str| {
+            let service = &service;
+            async move {
+                let ticks = service
+                    .download_ticks(
+                        String::from(instrument),
+                        datetime!(2020-03-12 01:00),
+                        datetime!(2020-03-12 02:00),
+                    )
+                    .collect::<Vec<_>>()
+                    .await;
+                ticks[0].as_ref().unwrap().ask
+            }
+        };
+
+        assert_eq!(ask("EURUSD").await, 1.11815);
+        assert_eq!(ask("USDJPY").await, 111.815);
+        assert_eq!(ask("EURGBP").await, 11181.5);
+    }
+
+    #[tokio::test]
+    async fn follows_an_always_closed_instrument_without_spinning() {
+        let service = DukascopyService::new(String::from(""), Box::new(PendingDataSupplier))
+            .with_instrument(Instrument {
+                trading_hours: TradingHours::Weekdays {
+                    zone: TimeZone::Utc,
+                    open: 0,
+                    close: 0,
+                },
+                ..Instrument::lookup("EURGBP").unwrap()
+            });
+
+        let mut ticks = Box::pin(service.follow_ticks(
+            String::from("EURGBP"),
+            datetime!(2020-03-12 06:00),
+            FollowOptions::new(),
+        ));
+        let next = tokio::time::timeout(std::time::Duration::from_millis(200), ticks.next()).await;
+
+        assert!(next.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetches_and_parses_multiple_bi5_files() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let ticks = service.download_ticks(
+            String::from("EURGBP"),
+            datetime!(2020-03-12 00:00),
+            datetime!(2020-03-13 00:00),
+        );
+
+        assert_eq!(ticks.count().await, 12464)
+    }
+
+    #[tokio::test]
+    async fn current_quote_returns_last_tick_of_most_recent_hour() {
+        let mut bytes = [0u8; 40];
+
+        BigEndian::write_i32(&mut bytes[0..4], 0x000000DA);
+        BigEndian::write_i32(&mut bytes[4..8], 0x0001B4C7);
+        BigEndian::write_i32(&mut bytes[8..12], 0x0001B4C4);
+        BigEndian::write_i32(&mut bytes[20..24], 0x000000DB);
+        BigEndian::write_i32(&mut bytes[24..28], 0x0001B4C8);
+        BigEndian::write_i32(&mut bytes[28..32], 0x0001B4C5);
+
+        let mut compressed_bytes = Vec::<u8>::new();
+        lzma_compress(&mut &bytes[..], &mut compressed_bytes).unwrap();
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from_iter(compressed_bytes)),
+            }),
+        );
+
+        let tick = service
+            .current_quote(String::from("EURGBP"))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(tick.ask, 1.11816);
+        assert_eq!(tick.bid, 1.11813);
+    }
+
+    /// Serves corrupt data for the first few requests and given data afterwards
+    struct CorruptFirstDataSupplier {
+        corrupt: AtomicUsize,
+        data: Option<Bytes>,
+    }
+
+    #[async_trait]
+    impl DataSupplier for CorruptFirstDataSupplier {
+        async fn fetch(&self, _url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            let corrupt = self
+                .corrupt
+                .fetch_update(SeqCst, SeqCst, |e| e.checked_sub(1))
+                .is_ok();
+            match corrupt {
+                true => Ok(Some(Bytes::from_static(b"not lzma"))),
+                false => Ok(self.data.clone()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn current_quote_skips_corrupt_hours() {
+        let hour = datetime!(2020-03-12 06:00);
+        let tick = Tick {
+            time: hour.assume_utc().unix_timestamp() * 1000,
+            ask: 0.86,
+            bid: 0.8599,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+        let bytes = bi5::encode_hour(&[tick], hour, 100_000.0).unwrap();
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(CorruptFirstDataSupplier {
+                corrupt: 2.into(),
+                data: Some(Bytes::from(bytes)),
+            }),
+        );
+        let quote = service
+            .current_quote(String::from("EURGBP"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.ask, 0.86);
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(CorruptFirstDataSupplier {
+                corrupt: 3.into(),
+                data: None,
+            }),
+        );
+        let error = service
+            .current_quote(String::from("EURGBP"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind, Kind::Decode);
+    }
+
+    #[tokio::test]
+    async fn current_quote_returns_none_when_there_is_no_data() {
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier { data: None }),
+        );
+
+        let tick = service.current_quote(String::from("EURGBP")).await.unwrap();
+
+        assert!(tick.is_none());
+    }
+
+    #[test]
+    fn skips_hours_outside_of_trading_schedule() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let eurusd = Instrument::lookup("EURUSD").unwrap();
+
+        let times = service.compute_tick_download_times(
+            &eurusd,
+            datetime!(2020-03-13 00:00),
+            datetime!(2020-03-16 00:00),
+        );
+
+        assert_eq!(times.len(), 24);
+        assert_eq!(times[20], datetime!(2020-03-13 20:00));
+        assert_eq!(times[21], datetime!(2020-03-15 21:00));
+
+        let times = service
+            .with_closed_hours_skipped(false)
+            .compute_tick_download_times(
+                &eurusd,
+                datetime!(2020-03-13 00:00),
+                datetime!(2020-03-16 00:00),
+            );
+
+        assert_eq!(times.len(), 72);
+    }
+
+    #[test]
+    fn skips_overnight_hours_of_indices() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let times = service.compute_tick_download_times(
+            &Instrument::lookup("DEUIDXEUR").unwrap(),
+            datetime!(2020-03-12 00:00),
+            datetime!(2020-03-13 00:00),
+        );
+
+        // 8:00 to 22:00 Frankfurt time, which is UTC+1 in March
+        assert_eq!(times.len(), 14);
+        assert_eq!(times[0], datetime!(2020-03-12 07:00));
+        assert_eq!(times[13], datetime!(2020-03-12 20:00));
+    }
+
+    #[tokio::test]
+    async fn reports_stalled_requests() {
+        let service = DukascopyService::new(String::from(""), Box::new(PendingDataSupplier {}))
+            .with_stall_timeout(std::time::Duration::from_millis(10));
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 01:00),
+                datetime!(2020-03-12 02:00),
+            )
+            .collect::<Vec<Result<Tick, crate::error::Error>>>()
+            .await;
+
+        assert_eq!(ticks.len(), 1);
+        assert!(matches!(
+            ticks[0].as_ref().unwrap_err().kind,
+            crate::Kind::Stalled
+        ));
+    }
+
+    #[tokio::test]
+    async fn stops_gracefully_when_budget_runs_out() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let budget = DownloadBudget::new().max_requests(2);
+
+        let ticks = service
+            .download_ticks_with_budget(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+                budget.clone(),
+            )
+            .collect::<Vec<Result<Tick, crate::error::Error>>>()
+            .await;
+        let expected_count = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+            )
+            .count()
+            .await;
+
+        let summary = budget.summary();
+        assert_eq!(ticks.len(), expected_count);
+        assert!(ticks.iter().all(|e| e.is_ok()));
+        assert_eq!(summary.requests, 2);
+        assert!(summary.bytes > 0);
+        assert_eq!(summary.resume_from, Some(datetime!(2020-03-12 08:00)));
+    }
+
+    #[tokio::test]
+    async fn merges_instruments_chronologically() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let ticks = service
+            .download_ticks_multi(
+                &["EURGBP", "EURUSD"],
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<InstrumentTick>>()
+            .await;
+        let single = service
+            .download_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+            )
+            .count()
+            .await;
+
+        assert_eq!(ticks.len(), single * 2);
+        assert!(ticks.windows(2).all(|e| e[0].time <= e[1].time));
+        assert_eq!(
+            ticks.iter().filter(|e| &*e.instrument == "EURUSD").count(),
+            single
+        );
+    }
+
+    #[tokio::test]
+    async fn emits_a_batch_per_hour() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let batches = service
+            .download_hours(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<HourBatch>>()
+            .await;
+
+        assert_eq!(
+            batches.iter().map(|e| e.hour).collect::<Vec<_>>(),
+            vec![datetime!(2020-03-12 06:00), datetime!(2020-03-12 07:00)]
+        );
+        assert!(batches.iter().all(|batch| !batch.ticks.is_empty()
+            && batch
+                .ticks
+                .iter()
+                .all(|e| e.datetime().unwrap().hour() == batch.hour.hour())));
+    }
+
+    #[tokio::test]
+    async fn emits_newest_ticks_first() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let chronological = service
+            .download_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let mut newest_first = service
+            .download_ticks_newest_first(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert_eq!(newest_first[0].datetime().unwrap().hour(), 8);
+        newest_first.reverse();
+        assert_eq!(newest_first, chronological);
+    }
+
+    #[tokio::test]
+    async fn downloads_raw_ticks() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let ticks = service
+            .download_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let raw = service
+            .download_raw_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap().to_tick(100_000.0))
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert_eq!(raw, ticks);
+    }
+
+    #[tokio::test]
+    async fn converts_offset_ranges_to_utc() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let utc = service
+            .download_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let offset = service
+            .download_ticks_between(
+                "EURGBP",
+                datetime!(2020-03-12 08:00 +2),
+                datetime!(2020-03-12 09:00 +2),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert!(!utc.is_empty());
+        assert_eq!(offset, utc);
+    }
+
+    #[tokio::test]
+    async fn downloads_raw_files() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let files = service
+            .download_raw(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 10:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            files.iter().map(|(hour, _)| *hour).collect::<Vec<_>>(),
+            vec![
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+                datetime!(2020-03-12 08:00),
+            ]
+        );
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/06h_ticks.bi5");
+        assert_eq!(files[0].1, std::fs::read(path).unwrap());
+    }
+
+    #[tokio::test]
+    async fn stops_when_cancelled() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let token = CancellationToken::new();
+        let budget = DownloadBudget::new().with_cancellation(token.clone());
+
+        let mut ticks = Box::pin(service.download_ticks_with_budget(
+            String::from("EURGBP"),
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 09:00),
+            budget.clone(),
+        ));
+        let first = ticks.next().await.unwrap().unwrap();
+        token.cancel();
+        let rest = ticks.collect::<Vec<_>>().await;
+
+        assert_eq!(first.datetime().unwrap().hour(), 6);
+        assert!(!rest.is_empty());
+        assert!(rest
+            .iter()
+            .all(|e| e.as_ref().unwrap().datetime().unwrap().hour() == 6));
+        assert_eq!(
+            budget.summary().resume_from,
+            Some(datetime!(2020-03-12 07:00))
+        );
+    }
+
+    #[tokio::test]
+    async fn emits_an_error_for_invalid_ranges() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        for (start, end) in [
+            (datetime!(2020-03-12 06:30), datetime!(2020-03-12 08:00)),
+            (datetime!(2020-03-12 08:00), datetime!(2020-03-12 06:00)),
+            (datetime!(2020-03-12 08:00), datetime!(2020-03-12 08:00)),
+        ] {
+            let ticks = service
+                .download_ticks(String::from("EURGBP"), start, end)
+                .collect::<Vec<Result<Tick, crate::error::Error>>>()
+                .await;
+
+            assert_eq!(ticks.len(), 1);
+            assert!(matches!(
+                ticks[0].as_ref().unwrap_err().kind,
+                crate::Kind::InvalidArgument
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn emits_an_error_for_unknown_instruments() {
+        let service = DukascopyService::new(String::from(""), Box::new(PendingDataSupplier {}));
+
+        let ticks = service
+            .download_ticks(
+                "EURXYZ",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+            )
+            .collect::<Vec<Result<Tick, crate::error::Error>>>()
+            .await;
+
+        assert_eq!(ticks.len(), 1);
+        assert!(matches!(
+            ticks[0].as_ref().unwrap_err().kind,
+            crate::Kind::UnknownInstrument
+        ));
+    }
+
+    #[tokio::test]
+    async fn downloads_instruments_from_the_published_list() {
+        let json = r#"{"instruments": {"USD/THB": {"historical_filename": "USDTHB",
+            "description": "USD vs THB", "pipValue": 0.001, "tag_list": ["CURRENCY"]}}}"#;
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from(json)),
+            }),
+        );
+
+        assert!(service.resolve_instrument("USD/THB").is_err());
+
+        let instruments = service.list_instruments().await.unwrap();
+
+        assert_eq!(instruments.len(), 1);
+        assert_eq!(
+            service.resolve_instrument("USD/THB").unwrap(),
+            instruments[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn clamps_ranges_to_history_start() {
+        let mut bytes = [0u8; 8];
+        BigEndian::write_i64(
+            &mut bytes,
+            datetime!(2020-03-12 06:30 UTC).unix_timestamp() * 1000,
+        );
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::copy_from_slice(&bytes)),
+            }),
+        );
+
+        assert_eq!(
+            service.history_start("EURGBP").await.unwrap(),
+            Some(datetime!(2020-03-12 06:30))
+        );
+        assert_eq!(
+            service
+                .clamp_to_history_start(
+                    "EURGBP",
+                    datetime!(2020-03-11 00:00),
+                    datetime!(2020-03-13 00:00)
+                )
+                .await
+                .unwrap(),
+            Some((datetime!(2020-03-12 06:00), datetime!(2020-03-13 00:00)))
+        );
+        assert_eq!(
+            service
+                .clamp_to_history_start(
+                    "EURGBP",
+                    datetime!(2020-03-11 00:00),
+                    datetime!(2020-03-12 06:00)
+                )
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn plans_downloads_without_fetching_ticks() {
+        let mut bytes = [0u8; 8];
+        BigEndian::write_i64(
+            &mut bytes,
+            datetime!(2020-03-13 19:30 UTC).unix_timestamp() * 1000,
+        );
+        let service = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::copy_from_slice(&bytes)),
+            }),
+        );
+
+        let plan = service
+            .plan_download(
+                "EURUSD",
+                datetime!(2020-03-13 00:00),
+                datetime!(2020-03-16 00:00),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            plan.iter().map(|e| e.hour).collect::<Vec<_>>(),
+            vec![
+                datetime!(2020-03-13 19:00),
+                datetime!(2020-03-13 20:00),
+                datetime!(2020-03-15 21:00),
+                datetime!(2020-03-15 22:00),
+                datetime!(2020-03-15 23:00),
+            ]
+        );
+        assert_eq!(
+            plan[0],
+            PlannedHour {
+                hour: datetime!(2020-03-13 19:00),
+                url: String::from("https://example.com/EURUSD/2020/02/13/19h_ticks.bi5"),
+            }
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    #[tokio::test]
+    async fn emits_a_record_batch_per_hour() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let ticks = service
+            .download_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .count()
+            .await;
+        let batches = service
+            .download_ticks_arrow(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches.iter().map(|e| e.num_rows()).sum::<usize>(), ticks);
+    }
+
+    #[tokio::test]
+    async fn prefetches_following_hours_while_consuming() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        struct CountingDataSupplier {
+            requests: Arc<AtomicUsize>,
+        }
+
+        #[async_trait]
+        impl DataSupplier for CountingDataSupplier {
+            async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+                self.requests.fetch_add(1, Ordering::SeqCst);
+                TestResourceDataSupplier {}.fetch(url).await
+            }
+        }
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(CountingDataSupplier {
+                requests: requests.clone(),
+            }),
+        )
+        .with_concurrency(2)
+        .with_prefetch(2);
+        let expected =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}))
+                .download_ticks(
+                    "EURGBP",
+                    datetime!(2020-03-12 06:00),
+                    datetime!(2020-03-12 09:00),
+                )
+                .map(|e| e.unwrap())
+                .collect::<Vec<Tick>>()
+                .await;
+
+        let mut ticks = Box::pin(service.download_ticks(
+            "EURGBP",
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 09:00),
+        ));
+        let first = ticks.next().await.unwrap().unwrap();
+
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let mut consumed = vec![first];
+        consumed.extend(ticks.map(|e| e.unwrap()).collect::<Vec<Tick>>().await);
+        assert_eq!(consumed, expected);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_chart_api_for_corrupt_hours() {
+        struct CorruptSecondHourDataSupplier;
+
+        #[async_trait]
+        impl DataSupplier for CorruptSecondHourDataSupplier {
+            async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+                if url.starts_with("chart?") {
+                    assert!(url.contains("instrument=EUR/GBP&offer_side=B"));
+                    let body = "_callbacks([[1583996400000,0.87,0.88,0.86,0.875,1.5],\
+                                [1584000000000,0.9,0.9,0.9,0.9,1]]);";
+                    return Ok(Some(Bytes::from(body)));
+                }
+                if url.ends_with("07h_ticks.bi5") {
+                    return Ok(Some(Bytes::from_static(b"corrupt")));
+                }
+                TestResourceDataSupplier {}.fetch(url).await
+            }
+        }
+
+        let service =
+            DukascopyService::new(String::from(""), Box::new(CorruptSecondHourDataSupplier))
+                .with_chart_api_url("chart");
+
+        let candles = service
+            .download_candles_with_fallback(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+                OfferSide::Bid,
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        let (ticks, chart): (Vec<_>, Vec<_>) = candles
+            .into_iter()
+            .partition(|e| e.source == CandleSource::Ticks);
+        assert!(!ticks.is_empty());
+        assert!(ticks.iter().all(|e| e.candle.time < 1_583_996_400_000));
+        assert_eq!(chart.len(), 1);
+        assert_eq!(chart[0].candle.time, 1_583_996_400_000);
+        assert_eq!(chart[0].candle.close, 0.875);
+    }
+
+    #[tokio::test]
+    async fn keeps_chronological_order_when_downloading_concurrently() {
+        struct SlowFirstHourDataSupplier;
+
+        #[async_trait]
+        impl DataSupplier for SlowFirstHourDataSupplier {
+            async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+                if url.ends_with("06h_ticks.bi5") {
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                }
+                TestResourceDataSupplier {}.fetch(url).await
+            }
+        }
+
+        let sequential =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let concurrent =
+            DukascopyService::new(String::from(""), Box::new(SlowFirstHourDataSupplier {}))
+                .with_concurrency(3);
+
+        let expected = sequential
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let ticks = concurrent
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert_eq!(ticks, expected);
+    }
+
+    #[tokio::test]
+    async fn downloads_candles() {
+        let mut bytes = [0u8; 40];
+
+        BigEndian::write_i32(&mut bytes[0..4], 0x00000000);
+        BigEndian::write_i32(&mut bytes[4..8], 0x0001B4C7);
+        BigEndian::write_i32(&mut bytes[8..12], 0x0001B4C4);
+        BigEndian::write_i32(&mut bytes[20..24], 0x00000001);
+        BigEndian::write_i32(&mut bytes[24..28], 0x0001B4C8);
+        BigEndian::write_i32(&mut bytes[28..32], 0x0001B4C2);
+
+        let mut compressed_bytes = Vec::<u8>::new();
+        lzma_compress(&mut &bytes[..], &mut compressed_bytes).unwrap();
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from_iter(compressed_bytes)),
+            }),
+        );
+
+        let candles = service
+            .download_candles(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+                Timeframe::H1,
+                PriceSource::Bid,
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Candle>>()
+            .await;
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(
+            candles[1].time,
+            datetime!(2020-03-12 07:00 UTC).unix_timestamp() * 1000
+        );
+        assert_eq!(candles[1].open, 1.11812);
+        assert_eq!(candles[1].close, 1.1181);
+        assert_eq!(candles[1].low, 1.1181);
+    }
+
+    #[tokio::test]
+    async fn parses_minute_candles_within_range() {
+        let mut bytes = [0u8; 48];
+
+        BigEndian::write_i32(&mut bytes[0..4], 60);
+        BigEndian::write_i32(&mut bytes[4..8], 0x0001B4C7);
+        BigEndian::write_i32(&mut bytes[8..12], 0x0001B4C4);
+        BigEndian::write_i32(&mut bytes[12..16], 0x0001B4C2);
+        BigEndian::write_i32(&mut bytes[16..20], 0x0001B4C8);
+        BigEndian::write_f32(&mut bytes[20..24], 12.5);
+        BigEndian::write_i32(&mut bytes[24..28], 3600);
+
+        let mut compressed_bytes = Vec::<u8>::new();
+        lzma_compress(&mut &bytes[..], &mut compressed_bytes).unwrap();
+
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier {
+                data: Some(Bytes::from_iter(compressed_bytes)),
+            }),
+        );
+
+        let candles = service
+            .download_minute_candles(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 00:00),
+                datetime!(2020-03-12 01:00),
+                OfferSide::Bid,
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Candle>>()
+            .await;
+
+        assert_eq!(
+            candles,
+            vec![Candle {
+                time: datetime!(2020-03-12 00:01 UTC).unix_timestamp() * 1000,
+                open: 1.11815,
+                high: 1.11816,
+                low: 1.1181,
+                close: 1.11812,
+                volume: 12.5,
+            }]
+        );
+    }
+
+    #[test]
+    fn generates_candle_download_urls() {
+        let service =
+            DukascopyService::new(String::from("base"), Box::new(TestResourceDataSupplier {}));
+
+        assert_eq!(
+            service.generate_candle_download_url(
+                datetime!(2020-03-12 00:00).date(),
+                "EURGBP",
+                OfferSide::Ask
+            ),
+            "base/EURGBP/2020/02/12/ASK_candles_min_1.bi5"
+        );
+        assert_eq!(
+            service.compute_candle_download_days(
+                datetime!(2020-03-12 05:00),
+                datetime!(2020-03-14 00:00)
+            ),
+            vec![
+                datetime!(2020-03-12 00:00).date(),
+                datetime!(2020-03-13 00:00).date()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn waits_between_consecutive_requests() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}))
+                .with_min_delay(std::time::Duration::from_millis(20));
+
+        let started = std::time::Instant::now();
+        service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .count()
+            .await;
+
+        assert!(started.elapsed() >= std::time::Duration::from_millis(40));
     }
 }
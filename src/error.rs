@@ -1,20 +1,199 @@
+use std::fmt::Display;
+
+use time::PrimitiveDateTime;
+
 /// Represents different possible error types that could happen when
 /// interacting with the Dukascopy API.
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Kind {
     /// Emitted when data was fetched but is malformed and therefore cannot be decoded
     Decode,
 
-    /// Emitted when a network error occurred, e.g. when the server is not reachable or
-    /// when the server is rate-limiting the client
+    /// Emitted when a network error occurred, e.g. when the server is not reachable
+    /// or responded with an unexpected status
     Network,
+
+    /// Emitted when a requested resource doesn't exist, e.g. the list of instruments.
+    /// Hours without any ticks are not errors, they are simply empty.
+    NotFound,
+
+    /// Emitted when the server is rate-limiting the client, i.e. responded with `429 Too Many Requests`
+    RateLimited,
+
+    /// Emitted when a request didn't complete within the configured HTTP timeout
+    Timeout,
+
+    /// Emitted when the server failed to handle a request, i.e. responded with a `5xx` status
+    ServerError,
+
+    /// Emitted when a request made no progress for longer than the configured stall timeout
+    Stalled,
+
+    /// Emitted when a method was called with invalid arguments, e.g. a time range
+    /// that is empty or not rounded to full hours
+    InvalidArgument,
+
+    /// Emitted when an instrument is neither built in nor registered with the service,
+    /// the message suggests instruments with similar names
+    UnknownInstrument,
+
+    /// Emitted when reading or writing local files failed, e.g. the cache
+    Io,
+
+    /// Emitted in place of a tick considered a misprint, see [filter_spikes](crate::SpikeFilterExt::filter_spikes)
+    Outlier,
 }
 
-pub(crate) type BoxError = Box<dyn std::error::Error>;
+pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
 /// Error that can be emitted when interacting with [DukascopyService](crate::DukascopyService)
+///
+/// Errors are `Send` and `Sync`, so they can be passed between threads and wrapped by
+/// other error types. The underlying error is returned by [source](std::error::Error::source).
 #[derive(Debug)]
 pub struct Error {
     pub inner: BoxError,
     pub kind: Kind,
+
+    /// What was being downloaded when the error occurred, as far as it's known
+    pub context: ErrorContext,
+}
+
+/// Identifies the item a failed download was for, e.g. to retry it later
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// URL of the failed request
+    pub url: Option<String>,
+
+    /// Name of the instrument, e.g. `EURUSD`
+    pub instrument: Option<String>,
+
+    /// Start of the hour (or the day, for daily files) the data was requested for, in UTC
+    pub hour: Option<PrimitiveDateTime>,
+}
+
+impl Error {
+    /// Creates an error of a given kind without any context
+    pub fn new(kind: Kind, inner: impl Into<BoxError>) -> Error {
+        Error {
+            inner: inner.into(),
+            kind,
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// Sets the URL of the failed request, unless it's already known
+    #[cfg(feature = "client")]
+    pub(crate) fn with_url(mut self, url: &str) -> Error {
+        self.context.url.get_or_insert_with(|| url.to_string());
+        self
+    }
+
+    /// Sets the hour the data was requested for, unless it's already known
+    pub(crate) fn with_hour(mut self, hour: PrimitiveDateTime) -> Error {
+        self.context.hour.get_or_insert(hour);
+        self
+    }
+
+    /// Sets the instrument and the hour the data was requested for, unless they're already known
+    #[cfg(feature = "client")]
+    pub(crate) fn with_item(mut self, instrument: &str, hour: PrimitiveDateTime) -> Error {
+        self.context
+            .instrument
+            .get_or_insert_with(|| instrument.to_string());
+        self.context.hour.get_or_insert(hour);
+        self
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Kind::Decode => "failed to decode data",
+            Kind::Network => "network error",
+            Kind::NotFound => "not found",
+            Kind::RateLimited => "rate limited",
+            Kind::Timeout => "request timed out",
+            Kind::ServerError => "server error",
+            Kind::Stalled => "request stalled",
+            Kind::InvalidArgument => "invalid argument",
+            Kind::UnknownInstrument => "unknown instrument",
+            Kind::Io => "I/O error",
+            Kind::Outlier => "outlier",
+        };
+
+        write!(f, "{description}")
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind, self.inner)?;
+
+        let context = &self.context;
+        match (&context.instrument, context.hour) {
+            (Some(instrument), Some(hour)) => {
+                write!(f, " ({instrument} at {} {})", hour.date(), hour.time())?
+            }
+            (None, Some(hour)) => write!(f, " (at {} {})", hour.date(), hour.time())?,
+            _ => {}
+        }
+        if let Some(url) = &context.url {
+            write!(f, " [{url}]")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.inner.as_ref())
+    }
+}
+
+pub(crate) fn io_error(error: std::io::Error) -> Error {
+    Error::new(Kind::Io, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use time::macros::datetime;
+
+    use super::{Error, ErrorContext, Kind};
+
+    #[test]
+    fn displays_kind_message_and_context() {
+        let mut error = Error::new(Kind::Decode, "unexpected end of input");
+        assert_eq!(
+            error.to_string(),
+            "failed to decode data: unexpected end of input"
+        );
+
+        error.context = ErrorContext {
+            url: Some(String::from(
+                "https://example.com/EURUSD/2020/02/12/10h_ticks.bi5",
+            )),
+            instrument: Some(String::from("EURUSD")),
+            hour: Some(datetime!(2020-03-12 10:00)),
+        };
+        assert_eq!(
+            error.to_string(),
+            "failed to decode data: unexpected end of input (EURUSD at 2020-03-12 10:00:00.0) \
+             [https://example.com/EURUSD/2020/02/12/10h_ticks.bi5]"
+        );
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            "unexpected end of input"
+        );
+    }
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+        assert_send_sync::<Error>();
+    }
 }
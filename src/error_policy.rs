@@ -0,0 +1,109 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+/// What a stream does with the errors emitted by the download, e.g. for a failed hour
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Errors are emitted in place of the items that failed and the stream continues
+    #[default]
+    Inline,
+
+    /// Errors are dropped, so items that failed are silently missing from the stream
+    Skip,
+
+    /// The first error is emitted and the stream ends right after it
+    FailFast,
+}
+
+/// Adds [with_error_policy](ErrorPolicyExt::with_error_policy) to streams of results,
+/// e.g. the ones returned by [DukascopyService](crate::DukascopyService)
+pub trait ErrorPolicyExt<T>: Stream<Item = Result<T, crate::error::Error>> + Sized {
+    /// Handles the errors emitted by the stream according to a given [ErrorPolicy](ErrorPolicy)
+    fn with_error_policy(self, policy: ErrorPolicy) -> WithErrorPolicy<Self> {
+        WithErrorPolicy {
+            stream: Box::pin(self),
+            policy,
+            finished: false,
+        }
+    }
+}
+
+impl<S, T> ErrorPolicyExt<T> for S where S: Stream<Item = Result<T, crate::error::Error>> {}
+
+/// Stream returned by [with_error_policy](ErrorPolicyExt::with_error_policy)
+pub struct WithErrorPolicy<S> {
+    stream: Pin<Box<S>>,
+    policy: ErrorPolicy,
+    finished: bool,
+}
+
+impl<S, T> Stream for WithErrorPolicy<S>
+where
+    S: Stream<Item = Result<T, crate::error::Error>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            return match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Err(_))) if this.policy == ErrorPolicy::Skip => continue,
+                Poll::Ready(Some(Err(e))) => {
+                    this.finished = this.policy == ErrorPolicy::FailFast;
+                    Poll::Ready(Some(Err(e)))
+                }
+                Poll::Ready(None) => {
+                    this.finished = true;
+                    Poll::Ready(None)
+                }
+                result => result,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{ErrorPolicy, ErrorPolicyExt};
+    use crate::Kind;
+
+    fn results() -> Vec<Result<u32, crate::error::Error>> {
+        vec![
+            Ok(1),
+            Err(crate::error::Error::new(Kind::Network, "connection reset")),
+            Ok(2),
+            Err(crate::error::Error::new(Kind::Timeout, "timed out")),
+            Ok(3),
+        ]
+    }
+
+    fn apply(policy: ErrorPolicy) -> Vec<Result<u32, Kind>> {
+        block_on(
+            stream::iter(results())
+                .with_error_policy(policy)
+                .map(|e| e.map_err(|e| e.kind))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn handles_errors_according_to_policy() {
+        assert_eq!(
+            apply(ErrorPolicy::Inline),
+            vec![Ok(1), Err(Kind::Network), Ok(2), Err(Kind::Timeout), Ok(3)]
+        );
+        assert_eq!(apply(ErrorPolicy::Skip), vec![Ok(1), Ok(2), Ok(3)]);
+        assert_eq!(
+            apply(ErrorPolicy::FailFast),
+            vec![Ok(1), Err(Kind::Network)]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Writers that store downloaded ticks in files and databases
+//!
+//! Parquet files can be written with `ParquetSink` when the `parquet` feature is enabled.
+//! Ticks can be loaded into PostgreSQL or TimescaleDB with [PostgresCopySink](PostgresCopySink)
+//! and, when the `client` feature is enabled, into ClickHouse with `ClickHouseSink`.
+//! [LineProtocolSink](LineProtocolSink) streams them into InfluxDB or QuestDB.
+
+use std::io::{BufRead, Write};
+
+use futures::{Stream, StreamExt};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::error::{io_error, Kind};
+use crate::tick::millis_to_date_time;
+use crate::Tick;
+
+mod atomic_file;
+#[cfg(feature = "client")]
+mod clickhouse_sink;
+mod line_protocol;
+#[cfg(feature = "parquet")]
+mod parquet_sink;
+mod postgres_sink;
+pub use atomic_file::AtomicFile;
+#[cfg(feature = "client")]
+pub use clickhouse_sink::ClickHouseSink;
+#[cfg(feature = "client")]
+pub use line_protocol::LineProtocolHttpSink;
+pub use line_protocol::LineProtocolSink;
+#[cfg(feature = "parquet")]
+pub use parquet_sink::ParquetSink;
+pub use postgres_sink::{OnConflict, PostgresCopySink, PostgresTable};
+
+/// Field of a [Tick](Tick) written as a column by [CsvSink](CsvSink)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Column {
+    Time,
+    Ask,
+    Bid,
+    AskVolume,
+    BidVolume,
+}
+
+impl Column {
+    /// All columns, in the order of [Tick's](Tick) fields
+    pub const ALL: [Column; 5] = [
+        Column::Time,
+        Column::Ask,
+        Column::Bid,
+        Column::AskVolume,
+        Column::BidVolume,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Column::Time => "time",
+            Column::Ask => "ask",
+            Column::Bid => "bid",
+            Column::AskVolume => "ask_volume",
+            Column::BidVolume => "bid_volume",
+        }
+    }
+}
+
+/// How [CsvSink](CsvSink) writes the [Time](Column::Time) column
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// The value of [Tick::time](Tick::time) as it is
+    Unix,
+
+    /// RFC 3339 date and time in UTC, e.g. `2020-03-12T06:00:00.25Z`
+    Rfc3339,
+}
+
+/// Writes ticks as delimited text, e.g. CSV
+///
+/// By default all [columns](Column::ALL) are written, separated by commas,
+/// with a header and unix timestamps.
+pub struct CsvSink<W: Write> {
+    writer: W,
+    columns: Vec<Column>,
+    delimiter: char,
+    timestamp_format: TimestampFormat,
+    header: bool,
+    header_written: bool,
+    written_until: Option<i64>,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> CsvSink<W> {
+        CsvSink {
+            writer,
+            columns: Column::ALL.to_vec(),
+            delimiter: ',',
+            timestamp_format: TimestampFormat::Unix,
+            header: true,
+            header_written: false,
+            written_until: None,
+        }
+    }
+
+    /// Writes only the given columns, in the given order, no columns at all keeps writing all of them
+    pub fn with_columns(mut self, columns: &[Column]) -> CsvSink<W> {
+        self.columns = match columns.is_empty() {
+            true => Column::ALL.to_vec(),
+            false => columns.to_vec(),
+        };
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> CsvSink<W> {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_timestamp_format(mut self, format: TimestampFormat) -> CsvSink<W> {
+        self.timestamp_format = format;
+        self
+    }
+
+    /// Whether the first line contains the names of the columns, enabled by default
+    pub fn with_header(mut self, header: bool) -> CsvSink<W> {
+        self.header = header;
+        self
+    }
+
+    /// Prepares appending to existing output written by a sink with the same settings,
+    /// e.g. a file opened for appending, so that writing an overlapping range again
+    /// doesn't duplicate any lines
+    ///
+    /// The header is only written when the existing output is empty, and ticks at or before
+    /// the time of the last existing line are skipped. Ticks are expected to be written in
+    /// chronological order, as they're emitted by [download_ticks](crate::DukascopyService::download_ticks).
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - the sink ready for appending
+    /// * Err - when the existing output can't be read, doesn't have the [Time](Column::Time)
+    ///   column or its last line can't be parsed
+    pub fn resume_from(
+        mut self,
+        existing: impl BufRead,
+    ) -> Result<CsvSink<W>, crate::error::Error> {
+        let mut lines = 0;
+        let mut last = None;
+        for line in existing.lines() {
+            let line = line.map_err(io_error)?;
+            if !line.is_empty() {
+                lines += 1;
+                last = Some(line);
+            }
+        }
+
+        self.header_written = lines > 0;
+        let last = match last {
+            Some(last) if !(self.header && lines == 1) => last,
+            _ => return Ok(self),
+        };
+
+        let invalid = || {
+            crate::error::Error::new(
+                Kind::InvalidArgument,
+                format!("can't find the time of the last line: {last}"),
+            )
+        };
+        let field = self
+            .columns
+            .iter()
+            .position(|e| *e == Column::Time)
+            .and_then(|e| last.split(self.delimiter).nth(e))
+            .ok_or_else(invalid)?;
+        let time = match self.timestamp_format {
+            TimestampFormat::Unix => field.parse().ok(),
+            TimestampFormat::Rfc3339 => OffsetDateTime::parse(field, &Rfc3339)
+                .ok()
+                .map(|e| (e.unix_timestamp_nanos() / 1_000_000) as i64),
+        };
+
+        self.written_until = Some(time.ok_or_else(invalid)?);
+        Ok(self)
+    }
+
+    /// Writes a single tick, unless it's already in the output the sink [resumed from](CsvSink::resume_from)
+    pub fn write(&mut self, tick: &Tick) -> Result<(), crate::error::Error> {
+        self.write_tick(tick).map(|_| ())
+    }
+
+    fn write_tick(&mut self, tick: &Tick) -> Result<bool, crate::error::Error> {
+        if self.written_until.is_some_and(|e| tick.time <= e) {
+            return Ok(false);
+        }
+
+        if self.header && !self.header_written {
+            let names = self.columns.iter().map(|e| e.name().to_string()).collect();
+            self.write_line(names)?;
+            self.header_written = true;
+        }
+
+        let fields = self
+            .columns
+            .iter()
+            .map(|e| self.format(tick, *e))
+            .collect::<Result<_, _>>()?;
+        self.write_line(fields)?;
+
+        Ok(true)
+    }
+
+    /// Writes all ticks of a stream, e.g. returned by
+    /// [download_ticks](crate::DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of written ticks
+    /// * Err - the first error emitted by the stream or encountered while writing,
+    ///   ticks preceding it are already written
+    pub async fn write_all<S>(&mut self, ticks: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Tick, crate::error::Error>>,
+    {
+        let mut ticks = Box::pin(ticks);
+        let mut written = 0;
+
+        while let Some(tick) = ticks.next().await {
+            written += self.write_tick(&tick?)? as u64;
+        }
+
+        self.writer.flush().map_err(io_error)?;
+        Ok(written)
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> Result<W, crate::error::Error> {
+        self.writer.flush().map_err(io_error)?;
+        Ok(self.writer)
+    }
+
+    fn format(&self, tick: &Tick, column: Column) -> Result<String, crate::error::Error> {
+        let field = match column {
+            Column::Time => match self.timestamp_format {
+                TimestampFormat::Unix => tick.time.to_string(),
+                TimestampFormat::Rfc3339 => millis_to_date_time(tick.time)
+                    .and_then(|e| e.format(&Rfc3339).ok())
+                    .ok_or_else(|| {
+                        crate::error::Error::new(
+                            Kind::InvalidArgument,
+                            format!("tick time {} is out of range", tick.time),
+                        )
+                    })?,
+            },
+            Column::Ask => tick.ask.to_string(),
+            Column::Bid => tick.bid.to_string(),
+            Column::AskVolume => tick.ask_volume.to_string(),
+            Column::BidVolume => tick.bid_volume.to_string(),
+        };
+
+        Ok(field)
+    }
+
+    fn write_line(&mut self, fields: Vec<String>) -> Result<(), crate::error::Error> {
+        let line = fields.join(&self.delimiter.to_string());
+        writeln!(self.writer, "{line}").map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream};
+
+    use super::{Column, CsvSink, TimestampFormat};
+    use crate::Tick;
+
+    fn tick(time: i64) -> Tick {
+        Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        }
+    }
+
+    #[test]
+    fn writes_header_and_all_columns() {
+        let mut sink = CsvSink::new(Vec::new());
+
+        let written = block_on(sink.write_all(stream::iter([Ok(tick(0)), Ok(tick(1))]))).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(
+            String::from_utf8(sink.into_inner().unwrap()).unwrap(),
+            "time,ask,bid,ask_volume,bid_volume\n0,1.5,1.25,2,0.75\n1,1.5,1.25,2,0.75\n"
+        );
+    }
+
+    #[test]
+    fn writes_configured_columns_and_timestamps() {
+        let mut sink = CsvSink::new(Vec::new())
+            .with_columns(&[Column::Time, Column::Bid])
+            .with_delimiter(';')
+            .with_timestamp_format(TimestampFormat::Rfc3339)
+            .with_header(false);
+
+        sink.write(&tick(1_584_000_000_250)).unwrap();
+
+        assert_eq!(
+            String::from_utf8(sink.into_inner().unwrap()).unwrap(),
+            "2020-03-12T08:00:00.25Z;1.25\n"
+        );
+    }
+
+    #[test]
+    fn resumes_without_duplicating_lines() {
+        let mut output = Vec::new();
+        for (from, to) in [(0, 3), (2, 5)] {
+            let mut sink = CsvSink::new(Vec::new())
+                .with_columns(&[Column::Bid, Column::Time])
+                .with_timestamp_format(TimestampFormat::Rfc3339)
+                .resume_from(output.as_slice())
+                .unwrap();
+            block_on(sink.write_all(stream::iter((from..to).map(|e| Ok(tick(e * 1000)))))).unwrap();
+            output.extend(sink.into_inner().unwrap());
+        }
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "bid,time\n\
+             1.25,1970-01-01T00:00:00Z\n\
+             1.25,1970-01-01T00:00:01Z\n\
+             1.25,1970-01-01T00:00:02Z\n\
+             1.25,1970-01-01T00:00:03Z\n\
+             1.25,1970-01-01T00:00:04Z\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::io_error;
+
+/// A file that's written under a temporary name and only moved to its path by
+/// [commit](AtomicFile::commit), so an interrupted write never leaves a truncated file behind
+///
+/// The temporary file is `{path}.part`, it's removed if the file is dropped without
+/// being committed. Missing parent directories are created.
+pub struct AtomicFile {
+    file: File,
+    path: PathBuf,
+    temporary: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub fn create(path: impl Into<PathBuf>) -> Result<AtomicFile, crate::error::Error> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".part");
+        let temporary = PathBuf::from(temporary);
+
+        Ok(AtomicFile {
+            file: File::create(&temporary).map_err(io_error)?,
+            path,
+            temporary,
+            committed: false,
+        })
+    }
+
+    /// Returns the path the file is moved to once committed
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the written file to its path, replacing any existing file
+    pub fn commit(mut self) -> Result<(), crate::error::Error> {
+        self.file.flush().map_err(io_error)?;
+        std::fs::rename(&self.temporary, &self.path).map_err(io_error)?;
+
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temporary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::AtomicFile;
+
+    #[test]
+    fn writes_files_only_once_committed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("EURUSD").join("2020-03-12.csv");
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"time,ask,bid").unwrap();
+        assert!(!path.exists());
+        file.commit().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"time,ask,bid");
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"truncated").unwrap();
+        drop(file);
+        assert_eq!(std::fs::read(&path).unwrap(), b"time,ask,bid");
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+}
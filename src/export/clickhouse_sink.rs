@@ -0,0 +1,326 @@
+use futures::{Stream, StreamExt};
+use time::format_description::FormatItem;
+use time::macros::format_description;
+
+use crate::error::Kind;
+use crate::reqwest_data_supplier::network_error;
+use crate::tick::millis_to_date_time;
+use crate::{Candle, Tick};
+
+/// Format of times accepted by `DateTime64(3)` columns
+const TIME_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]");
+
+const TICK_COLUMNS: &str = "instrument, time, ask, bid, ask_volume, bid_volume";
+const CANDLE_COLUMNS: &str = "instrument, time, open, high, low, close, volume";
+
+/// Inserts ticks or candles into a ClickHouse table over its HTTP interface
+///
+/// Rows are sent as `TabSeparated` in blocks of 100 000 by default, each block being
+/// a single `INSERT`. See [create_ticks_statement](ClickHouseSink::create_ticks_statement)
+/// and [create_candles_statement](ClickHouseSink::create_candles_statement) for the expected columns.
+pub struct ClickHouseSink {
+    client: reqwest::Client,
+    url: String,
+    table: String,
+    instrument: String,
+    block_size: usize,
+    credentials: Option<(String, String)>,
+}
+
+impl ClickHouseSink {
+    /// Inserts into a given table of the server at `url`, e.g. `http://localhost:8123`,
+    /// tagging each row with a given instrument
+    pub fn new(
+        url: impl Into<String>,
+        table: impl Into<String>,
+        instrument: impl Into<String>,
+    ) -> ClickHouseSink {
+        ClickHouseSink {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            table: table.into(),
+            instrument: instrument.into(),
+            block_size: 100_000,
+            credentials: None,
+        }
+    }
+
+    /// Number of rows sent in a single insert, at least one
+    pub fn with_block_size(mut self, block_size: usize) -> ClickHouseSink {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Authenticates as a given user, by default the server's default user is used
+    pub fn with_credentials(
+        mut self,
+        user: impl Into<String>,
+        password: impl Into<String>,
+    ) -> ClickHouseSink {
+        self.credentials = Some((user.into(), password.into()));
+        self
+    }
+
+    /// Returns the statement creating a table for ticks unless it already exists,
+    /// duplicates of the same instrument and time are eventually merged
+    pub fn create_ticks_statement(&self) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\
+             instrument LowCardinality(String), \
+             time DateTime64(3, 'UTC'), \
+             ask Float64, \
+             bid Float64, \
+             ask_volume Float64, \
+             bid_volume Float64\
+             ) ENGINE = ReplacingMergeTree ORDER BY (instrument, time)",
+            self.table
+        )
+    }
+
+    /// Returns the statement creating a table for candles unless it already exists,
+    /// duplicates of the same instrument and time are eventually merged
+    pub fn create_candles_statement(&self) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\
+             instrument LowCardinality(String), \
+             time DateTime64(3, 'UTC'), \
+             open Float64, \
+             high Float64, \
+             low Float64, \
+             close Float64, \
+             volume Float64\
+             ) ENGINE = ReplacingMergeTree ORDER BY (instrument, time)",
+            self.table
+        )
+    }
+
+    /// Runs a given statement, e.g. one creating the table
+    pub async fn execute(&self, statement: &str) -> Result<(), crate::error::Error> {
+        self.send(statement, String::new()).await
+    }
+
+    /// Inserts all ticks of a stream, e.g. returned by
+    /// [download_ticks](crate::DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of inserted ticks
+    /// * Err - the first error emitted by the stream or encountered while inserting,
+    ///   blocks preceding it are already inserted
+    pub async fn write_ticks<S>(&self, ticks: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Tick, crate::error::Error>>,
+    {
+        self.write_rows(ticks, TICK_COLUMNS, |tick: &Tick| {
+            Ok(format!(
+                "{}\t{}\t{}\t{}\t{}",
+                format_time(tick.time)?,
+                tick.ask,
+                tick.bid,
+                tick.ask_volume,
+                tick.bid_volume
+            ))
+        })
+        .await
+    }
+
+    /// Inserts all candles of a stream, e.g. returned by
+    /// [download_candles](crate::DukascopyService::download_candles)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of inserted candles
+    /// * Err - the first error emitted by the stream or encountered while inserting,
+    ///   blocks preceding it are already inserted
+    pub async fn write_candles<S>(&self, candles: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Candle, crate::error::Error>>,
+    {
+        self.write_rows(candles, CANDLE_COLUMNS, |candle: &Candle| {
+            Ok(format!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                format_time(candle.time)?,
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume
+            ))
+        })
+        .await
+    }
+
+    async fn write_rows<S, T>(
+        &self,
+        rows: S,
+        columns: &str,
+        format: impl Fn(&T) -> Result<String, crate::error::Error>,
+    ) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<T, crate::error::Error>>,
+    {
+        let query = format!("INSERT INTO {} ({columns}) FORMAT TabSeparated", self.table);
+        let instrument = escape(&self.instrument);
+        let mut rows = Box::pin(rows);
+        let mut block = String::new();
+        let mut batched = 0usize;
+        let mut written = 0u64;
+
+        while let Some(row) = rows.next().await {
+            block.push_str(&instrument);
+            block.push('\t');
+            block.push_str(&format(&row?)?);
+            block.push('\n');
+            batched += 1;
+
+            if batched == self.block_size {
+                self.send(&query, std::mem::take(&mut block)).await?;
+                written += batched as u64;
+                batched = 0;
+            }
+        }
+
+        if batched > 0 {
+            self.send(&query, block).await?;
+            written += batched as u64;
+        }
+
+        Ok(written)
+    }
+
+    async fn send(&self, query: &str, body: String) -> Result<(), crate::error::Error> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .query(&[("query", query)])
+            .body(body);
+        if let Some((user, password)) = &self.credentials {
+            request = request
+                .header("X-ClickHouse-User", user)
+                .header("X-ClickHouse-Key", password);
+        }
+
+        let response = request.send().await.map_err(network_error)?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        // ClickHouse explains what went wrong in the body
+        let message = response.text().await.map_err(network_error)?;
+        let kind = if status.is_server_error() {
+            Kind::ServerError
+        } else {
+            Kind::Network
+        };
+
+        Err(crate::error::Error::new(
+            kind,
+            format!("ClickHouse responded with {status}: {}", message.trim()),
+        ))
+    }
+}
+
+fn format_time(time: i64) -> Result<String, crate::error::Error> {
+    millis_to_date_time(time)
+        .and_then(|e| e.format(TIME_FORMAT).ok())
+        .ok_or_else(|| {
+            crate::error::Error::new(
+                Kind::InvalidArgument,
+                format!("time {time} is out of range"),
+            )
+        })
+}
+
+/// Escapes a value of a `TabSeparated` row
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use futures::stream;
+    use warp::{http::StatusCode, Filter};
+
+    use super::ClickHouseSink;
+    use crate::{Kind, Tick};
+
+    fn tick(time: i64) -> Tick {
+        Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        }
+    }
+
+    #[tokio::test]
+    async fn inserts_ticks_in_blocks() {
+        let inserts = Arc::new(Mutex::new(Vec::new()));
+        let recorded = inserts.clone();
+        let route = warp::query::<HashMap<String, String>>()
+            .and(warp::body::bytes())
+            .map(move |query: HashMap<String, String>, body: bytes::Bytes| {
+                recorded.lock().unwrap().push((
+                    query["query"].clone(),
+                    String::from_utf8(body.to_vec()).unwrap(),
+                ));
+                warp::reply()
+            });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let sink =
+            ClickHouseSink::new(format!("http://{address}"), "ticks", "EURUSD").with_block_size(2);
+        let ticks = [0, 250, 1_584_000_000_250].map(|e| Ok(tick(e)));
+
+        let written = sink.write_ticks(stream::iter(ticks)).await.unwrap();
+
+        let inserts = inserts.lock().unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(inserts.len(), 2);
+        assert_eq!(
+            inserts[0].0,
+            "INSERT INTO ticks (instrument, time, ask, bid, ask_volume, bid_volume) FORMAT TabSeparated"
+        );
+        assert_eq!(
+            inserts[0].1,
+            "EURUSD\t1970-01-01 00:00:00.000\t1.5\t1.25\t2\t0.75\n\
+             EURUSD\t1970-01-01 00:00:00.250\t1.5\t1.25\t2\t0.75\n"
+        );
+        assert_eq!(
+            inserts[1].1,
+            "EURUSD\t2020-03-12 08:00:00.250\t1.5\t1.25\t2\t0.75\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_rejected_inserts() {
+        let route = warp::any().map(|| {
+            warp::reply::with_status(
+                "Code: 60. DB::Exception: Table default.ticks does not exist.",
+                StatusCode::NOT_FOUND,
+            )
+        });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let sink = ClickHouseSink::new(format!("http://{address}"), "ticks", "EURUSD");
+
+        let error = sink
+            .write_ticks(stream::iter([Ok(tick(0))]))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error.kind, Kind::Network));
+        assert!(error.to_string().contains("does not exist"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::io::Write;
+
+use futures::{Stream, StreamExt};
+
+use crate::error::io_error;
+use crate::Tick;
+
+/// Writes ticks in the InfluxDB line protocol (ILP), understood by InfluxDB, QuestDB
+/// and other time-series databases
+///
+/// Each tick becomes a line of a measurement named `ticks` by default, with the instrument
+/// as a tag, `ask`, `bid`, `ask_volume` and `bid_volume` as fields and a timestamp
+/// in nanoseconds, e.g.
+///
+/// ```text
+/// ticks,instrument=EURUSD ask=1.5,bid=1.25,ask_volume=2,bid_volume=0.75 1584000000250000000
+/// ```
+///
+/// To stream ticks into QuestDB over TCP, pass a [TcpStream](std::net::TcpStream) connected
+/// to its ILP port (9009 by default). To send them over HTTP, see `LineProtocolHttpSink`.
+pub struct LineProtocolSink<W: Write> {
+    writer: W,
+    instrument: String,
+    prefix: String,
+}
+
+impl<W: Write> LineProtocolSink<W> {
+    pub fn new(writer: W, instrument: &str) -> LineProtocolSink<W> {
+        LineProtocolSink {
+            writer,
+            instrument: instrument.to_string(),
+            prefix: line_prefix("ticks", instrument),
+        }
+    }
+
+    /// Name of the measurement (or table) ticks are written to, `ticks` by default
+    pub fn with_measurement(mut self, measurement: &str) -> LineProtocolSink<W> {
+        self.prefix = line_prefix(measurement, &self.instrument);
+        self
+    }
+
+    /// Writes a single tick
+    pub fn write(&mut self, tick: &Tick) -> Result<(), crate::error::Error> {
+        self.writer
+            .write_all(format_line(&self.prefix, tick).as_bytes())
+            .map_err(io_error)
+    }
+
+    /// Writes all ticks of a stream, e.g. returned by
+    /// [download_ticks](crate::DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of written ticks
+    /// * Err - the first error emitted by the stream or encountered while writing,
+    ///   ticks preceding it are already written
+    pub async fn write_all<S>(&mut self, ticks: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Tick, crate::error::Error>>,
+    {
+        let mut ticks = Box::pin(ticks);
+        let mut written = 0;
+
+        while let Some(tick) = ticks.next().await {
+            self.write(&tick?)?;
+            written += 1;
+        }
+
+        self.writer.flush().map_err(io_error)?;
+        Ok(written)
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> Result<W, crate::error::Error> {
+        self.writer.flush().map_err(io_error)?;
+        Ok(self.writer)
+    }
+}
+
+/// Sends ticks in the InfluxDB line protocol over HTTP, in batches of 5 000 by default
+///
+/// The URL is the database's write endpoint, e.g. `http://localhost:8086/api/v2/write?org=my-org&bucket=ticks`
+/// for InfluxDB or `http://localhost:9000/write` for QuestDB. Timestamps are in nanoseconds,
+/// which is the default precision of both. See [LineProtocolSink](LineProtocolSink) for the format.
+#[cfg(feature = "client")]
+pub struct LineProtocolHttpSink {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    instrument: String,
+    prefix: String,
+    batch_size: usize,
+}
+
+#[cfg(feature = "client")]
+impl LineProtocolHttpSink {
+    pub fn new(url: impl Into<String>, instrument: &str) -> LineProtocolHttpSink {
+        LineProtocolHttpSink {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            token: None,
+            instrument: instrument.to_string(),
+            prefix: line_prefix("ticks", instrument),
+            batch_size: 5_000,
+        }
+    }
+
+    /// Name of the measurement (or table) ticks are written to, `ticks` by default
+    pub fn with_measurement(mut self, measurement: &str) -> LineProtocolHttpSink {
+        self.prefix = line_prefix(measurement, &self.instrument);
+        self
+    }
+
+    /// Authenticates with an InfluxDB API token
+    pub fn with_token(mut self, token: impl Into<String>) -> LineProtocolHttpSink {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Number of ticks sent in a single request, at least one
+    pub fn with_batch_size(mut self, batch_size: usize) -> LineProtocolHttpSink {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sends all ticks of a stream, e.g. returned by
+    /// [download_ticks](crate::DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of sent ticks
+    /// * Err - the first error emitted by the stream or encountered while sending,
+    ///   batches preceding it are already sent
+    pub async fn write_all<S>(&self, ticks: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Tick, crate::error::Error>>,
+    {
+        let mut ticks = Box::pin(ticks);
+        let mut batch = String::new();
+        let mut batched = 0usize;
+        let mut written = 0u64;
+
+        while let Some(tick) = ticks.next().await {
+            batch.push_str(&format_line(&self.prefix, &tick?));
+            batched += 1;
+
+            if batched == self.batch_size {
+                self.send(std::mem::take(&mut batch)).await?;
+                written += batched as u64;
+                batched = 0;
+            }
+        }
+
+        if batched > 0 {
+            self.send(batch).await?;
+            written += batched as u64;
+        }
+
+        Ok(written)
+    }
+
+    async fn send(&self, body: String) -> Result<(), crate::error::Error> {
+        use crate::reqwest_data_supplier::network_error;
+
+        let mut request = self.client.post(&self.url).body(body);
+        if let Some(token) = &self.token {
+            request = request.header(reqwest::header::AUTHORIZATION, format!("Token {token}"));
+        }
+
+        request
+            .send()
+            .await
+            .and_then(|e| e.error_for_status())
+            .map_err(network_error)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the escaped measurement and tag starting each line
+fn line_prefix(measurement: &str, instrument: &str) -> String {
+    let measurement = escape(measurement, &[',', ' ']);
+    let instrument = escape(instrument, &[',', '=', ' ']);
+
+    format!("{measurement},instrument={instrument}")
+}
+
+fn format_line(prefix: &str, tick: &Tick) -> String {
+    format!(
+        "{prefix} ask={},bid={},ask_volume={},bid_volume={} {}\n",
+        tick.ask,
+        tick.bid,
+        tick.ask_volume,
+        tick.bid_volume,
+        tick.time as i128 * 1_000_000
+    )
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream};
+
+    use super::LineProtocolSink;
+    use crate::Tick;
+
+    fn tick(time: i64) -> Tick {
+        Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        }
+    }
+
+    #[test]
+    fn writes_a_line_per_tick() {
+        let mut sink = LineProtocolSink::new(Vec::new(), "EURUSD");
+
+        let written =
+            block_on(sink.write_all(stream::iter([Ok(tick(0)), Ok(tick(1_584_000_000_250))])))
+                .unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(
+            String::from_utf8(sink.into_inner().unwrap()).unwrap(),
+            "ticks,instrument=EURUSD ask=1.5,bid=1.25,ask_volume=2,bid_volume=0.75 0\n\
+             ticks,instrument=EURUSD ask=1.5,bid=1.25,ask_volume=2,bid_volume=0.75 1584000000250000000\n"
+        );
+    }
+
+    #[test]
+    fn escapes_measurements_and_tags() {
+        let mut sink = LineProtocolSink::new(Vec::new(), "EUR,USD=x").with_measurement("fx ticks");
+
+        sink.write(&tick(0)).unwrap();
+
+        let line = String::from_utf8(sink.into_inner().unwrap()).unwrap();
+        assert!(line.starts_with("fx\\ ticks,instrument=EUR\\,USD\\=x ask="));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn sends_batches_over_http() {
+        use std::sync::{Arc, Mutex};
+        use warp::Filter;
+
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        let route = warp::header::<String>("authorization")
+            .and(warp::body::bytes())
+            .map(move |authorization: String, body: bytes::Bytes| {
+                assert_eq!(authorization, "Token secret");
+                recorded.lock().unwrap().push(body.len());
+                warp::reply()
+            });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let sink = super::LineProtocolHttpSink::new(format!("http://{address}/write"), "EURUSD")
+            .with_token("secret")
+            .with_batch_size(2);
+        let ticks = [0, 1, 2].map(|e| Ok(tick(e)));
+
+        let written = sink.write_all(stream::iter(ticks)).await.unwrap();
+
+        assert_eq!(written, 3);
+        assert_eq!(bodies.lock().unwrap().len(), 2);
+    }
+}
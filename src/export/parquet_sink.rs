@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use arrow_array::RecordBatch;
+use futures::{Stream, StreamExt};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::{WriterProperties, DEFAULT_MAX_ROW_GROUP_ROW_COUNT};
+use time::Date;
+
+use crate::error::{io_error, Kind};
+use crate::export::AtomicFile;
+use crate::record_batch::{record_batch_to_ticks, ticks_to_record_batch};
+use crate::tick::millis_to_date_time;
+use crate::Tick;
+
+/// Writes ticks to Parquet files partitioned by instrument and day
+///
+/// Ticks of each UTC day are stored in `{dir}/instrument={instrument}/date={YYYY-MM-DD}/ticks.parquet`,
+/// a layout pandas, Spark and DuckDB understand as hive partitioning. Existing files of the
+/// same days are replaced, unless [appending](ParquetSink::with_append) is enabled.
+pub struct ParquetSink {
+    dir: PathBuf,
+    instrument: String,
+    row_group_size: usize,
+    row_group_bytes: Option<usize>,
+    append: bool,
+}
+
+impl ParquetSink {
+    pub fn new(dir: impl Into<PathBuf>, instrument: impl Into<String>) -> ParquetSink {
+        ParquetSink {
+            dir: dir.into(),
+            instrument: instrument.into(),
+            row_group_size: DEFAULT_MAX_ROW_GROUP_ROW_COUNT,
+            row_group_bytes: None,
+            append: false,
+        }
+    }
+
+    /// Sets the maximum number of ticks in a row group, 1 048 576 by default.
+    /// Smaller row groups let readers skip more data when filtering by time,
+    /// at the cost of larger files. Zero is treated as 1.
+    pub fn with_row_group_size(mut self, rows: usize) -> ParquetSink {
+        self.row_group_size = rows.max(1);
+        self
+    }
+
+    /// Also limits row groups to roughly a given number of (encoded) bytes,
+    /// by default they're only limited by [row count](ParquetSink::with_row_group_size).
+    /// Zero is treated as 1.
+    pub fn with_row_group_bytes(mut self, bytes: usize) -> ParquetSink {
+        self.row_group_bytes = Some(bytes.max(1));
+        self
+    }
+
+    /// Whether ticks are merged into the existing files of their days instead of replacing them,
+    /// disabled by default
+    ///
+    /// Ticks at a time the file already has ticks at are skipped, so writing an overlapping range
+    /// again (e.g. after an interrupted sync) doesn't duplicate any rows.
+    pub fn with_append(mut self, append: bool) -> ParquetSink {
+        self.append = append;
+        self
+    }
+
+    /// Returns the directory ticks of a given day are written to
+    pub fn partition_dir(&self, date: Date) -> PathBuf {
+        self.dir
+            .join(format!("instrument={}", self.instrument))
+            .join(format!("date={date}"))
+    }
+
+    /// Writes all ticks of a stream, e.g. returned by
+    /// [download_ticks](crate::DukascopyService::download_ticks)
+    ///
+    /// Ticks are expected to be in chronological order, a day is written
+    /// once the first tick of the following day arrives.
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of written ticks
+    /// * Err - the first error emitted by the stream or encountered while writing,
+    ///   days preceding it are already written
+    pub async fn write_all<S>(&self, ticks: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Tick, crate::error::Error>>,
+    {
+        let mut ticks = Box::pin(ticks);
+        let mut day = Vec::<Tick>::new();
+        let mut written = 0;
+
+        while let Some(tick) = ticks.next().await {
+            let tick = tick?;
+            if let Some(first) = day.first() {
+                if date_of(first)? != date_of(&tick)? {
+                    written += self.write_day(&day)?;
+                    day.clear();
+                }
+            }
+            day.push(tick);
+        }
+
+        if !day.is_empty() {
+            written += self.write_day(&day)?;
+        }
+
+        Ok(written)
+    }
+
+    fn write_day(&self, ticks: &[Tick]) -> Result<u64, crate::error::Error> {
+        let path = self
+            .partition_dir(date_of(&ticks[0])?)
+            .join("ticks.parquet");
+
+        let mut day = match self.append {
+            true => read_file(&path)?,
+            false => Vec::new(),
+        };
+        let existing = day.iter().map(|e| e.time).collect::<HashSet<_>>();
+        let new = ticks
+            .iter()
+            .filter(|e| !existing.contains(&e.time))
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return Ok(0);
+        }
+
+        day.extend(new.iter().copied());
+        day.sort_by_key(|e| e.time);
+        let batch = ticks_to_record_batch(&day)?;
+
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_row_count(Some(self.row_group_size))
+            .set_max_row_group_bytes(self.row_group_bytes)
+            .build();
+
+        write_file(&path, &batch, properties)?;
+        Ok(new.len() as u64)
+    }
+}
+
+fn write_file(
+    path: &Path,
+    batch: &RecordBatch,
+    properties: WriterProperties,
+) -> Result<(), crate::error::Error> {
+    let mut file = AtomicFile::create(path)?;
+    let mut writer =
+        ArrowWriter::try_new(&mut file, batch.schema(), Some(properties)).map_err(encode_error)?;
+    writer.write(batch).map_err(encode_error)?;
+    writer.close().map_err(encode_error)?;
+
+    file.commit()
+}
+
+/// Returns the ticks of an existing file, no ticks if there's no such file
+fn read_file(path: &Path) -> Result<Vec<Tick>, crate::error::Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(e)),
+    };
+
+    let mut ticks = Vec::new();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|e| e.build())
+        .map_err(decode_error)?;
+    for batch in reader {
+        ticks.extend(record_batch_to_ticks(&batch.map_err(decode_error)?)?);
+    }
+
+    Ok(ticks)
+}
+
+fn date_of(tick: &Tick) -> Result<Date, crate::error::Error> {
+    millis_to_date_time(tick.time)
+        .map(|e| e.date())
+        .ok_or_else(|| {
+            crate::error::Error::new(
+                Kind::InvalidArgument,
+                format!("tick time {} is out of range", tick.time),
+            )
+        })
+}
+
+fn decode_error(error: impl std::error::Error + Send + Sync + 'static) -> crate::error::Error {
+    crate::error::Error::new(Kind::Decode, error)
+}
+
+fn encode_error(error: impl std::error::Error + Send + Sync + 'static) -> crate::error::Error {
+    crate::error::Error::new(Kind::Io, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use futures::{executor::block_on, stream};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use time::macros::{date, datetime};
+
+    use super::ParquetSink;
+    use crate::Tick;
+
+    fn tick(time: i64) -> Tick {
+        Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        }
+    }
+
+    #[test]
+    fn partitions_ticks_by_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ParquetSink::new(dir.path(), "EURUSD");
+        let midnight = datetime!(2020-03-13 00:00 UTC).unix_timestamp() * 1000;
+
+        let ticks = [midnight - 2, midnight - 1, midnight].map(|e| Ok(tick(e)));
+        let written = block_on(sink.write_all(stream::iter(ticks))).unwrap();
+
+        assert_eq!(written, 3);
+
+        let rows = |date| {
+            let path = sink.partition_dir(date).join("ticks.parquet");
+            ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().num_rows())
+                .sum::<usize>()
+        };
+        assert_eq!(rows(date!(2020 - 03 - 12)), 2);
+        assert_eq!(rows(date!(2020 - 03 - 13)), 1);
+        assert!(dir
+            .path()
+            .join("instrument=EURUSD/date=2020-03-12/ticks.parquet")
+            .exists());
+    }
+
+    #[test]
+    fn appends_without_duplicating_overlapping_ticks() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ParquetSink::new(dir.path(), "EURUSD").with_append(true);
+        let midnight = datetime!(2020-03-12 00:00 UTC).unix_timestamp() * 1000;
+
+        let first = (0..3).map(|e| Ok(tick(midnight + e)));
+        assert_eq!(block_on(sink.write_all(stream::iter(first))).unwrap(), 3);
+        let overlapping = (2..5).map(|e| Ok(tick(midnight + e)));
+        assert_eq!(
+            block_on(sink.write_all(stream::iter(overlapping))).unwrap(),
+            2
+        );
+
+        let path = sink
+            .partition_dir(date!(2020 - 03 - 12))
+            .join("ticks.parquet");
+        let ticks = super::read_file(&path).unwrap();
+        assert_eq!(
+            ticks.iter().map(|e| e.time - midnight).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn splits_days_into_row_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ParquetSink::new(dir.path(), "EURUSD").with_row_group_size(2);
+        let midnight = datetime!(2020-03-12 00:00 UTC).unix_timestamp() * 1000;
+
+        let ticks = (0..5).map(|e| Ok(tick(midnight + e)));
+        block_on(sink.write_all(stream::iter(ticks))).unwrap();
+
+        let path = sink
+            .partition_dir(date!(2020 - 03 - 12))
+            .join("ticks.parquet");
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+    }
+}
@@ -0,0 +1,328 @@
+use std::io::Write;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use futures::{Stream, StreamExt};
+
+use crate::error::io_error;
+use crate::Tick;
+
+/// Signature, flags and header extension length starting every binary COPY payload
+const HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0\0\0\0\0\0\0\0\0";
+
+/// Milliseconds between the unix epoch and the PostgreSQL epoch (2000-01-01)
+const POSTGRES_EPOCH_MILLIS: i64 = 946_684_800_000;
+
+/// What happens when a copied tick has the same instrument and time as a stored one
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnConflict {
+    /// The whole copy fails
+    Fail,
+
+    /// The stored tick is kept
+    Ignore,
+
+    /// The stored tick is replaced
+    Update,
+}
+
+/// Table of ticks in PostgreSQL or TimescaleDB, which provides the statements
+/// to run around a copy written by [PostgresCopySink](PostgresCopySink)
+///
+/// Ticks are stored in `instrument`, `time`, `ask`, `bid`, `ask_volume` and `bid_volume`
+/// columns, with `(instrument, time)` being the primary key. Unless conflicts
+/// [fail](OnConflict::Fail) the copy, ticks are copied into a temporary table first and
+/// then inserted, so all statements have to be run within a single transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostgresTable {
+    name: String,
+    on_conflict: OnConflict,
+    hypertable: bool,
+}
+
+impl PostgresTable {
+    /// Describes a table with a given name, optionally qualified with a schema, e.g. `market.ticks`
+    pub fn new(name: impl Into<String>) -> PostgresTable {
+        PostgresTable {
+            name: name.into(),
+            on_conflict: OnConflict::Fail,
+            hypertable: false,
+        }
+    }
+
+    /// How ticks that are already stored are handled, by default the copy fails
+    pub fn with_on_conflict(mut self, on_conflict: OnConflict) -> PostgresTable {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    /// Whether the table is created as a TimescaleDB hypertable partitioned by time
+    pub fn with_hypertable(mut self, hypertable: bool) -> PostgresTable {
+        self.hypertable = hypertable;
+        self
+    }
+
+    /// Returns the statements creating the table unless it already exists
+    pub fn create_statements(&self) -> Vec<String> {
+        let name = quote_identifier(&self.name);
+        let mut statements = vec![format!(
+            "CREATE TABLE IF NOT EXISTS {name} (\
+             instrument text NOT NULL, \
+             time timestamptz NOT NULL, \
+             ask double precision NOT NULL, \
+             bid double precision NOT NULL, \
+             ask_volume double precision NOT NULL, \
+             bid_volume double precision NOT NULL, \
+             PRIMARY KEY (instrument, time))"
+        )];
+
+        if self.hypertable {
+            // the regclass literal is parsed like an identifier, so it has to be quoted the same way
+            let name = name.replace('\'', "''");
+            statements.push(format!(
+                "SELECT create_hypertable('{name}', 'time', if_not_exists => TRUE)"
+            ));
+        }
+
+        statements
+    }
+
+    /// Returns the statements to run before the copy
+    pub fn before_copy_statements(&self) -> Vec<String> {
+        match self.on_conflict {
+            OnConflict::Fail => Vec::new(),
+            OnConflict::Ignore | OnConflict::Update => vec![format!(
+                "CREATE TEMPORARY TABLE {} (LIKE {} INCLUDING DEFAULTS) ON COMMIT DROP",
+                self.copy_target(),
+                quote_identifier(&self.name)
+            )],
+        }
+    }
+
+    /// Returns the statement starting the copy, e.g. to pass to the `copy_in` method of the `postgres` crate
+    pub fn copy_statement(&self) -> String {
+        format!(
+            "COPY {} (instrument, time, ask, bid, ask_volume, bid_volume) FROM STDIN (FORMAT binary)",
+            self.copy_target()
+        )
+    }
+
+    /// Returns the statements to run after the copy
+    pub fn after_copy_statements(&self) -> Vec<String> {
+        let action = match self.on_conflict {
+            OnConflict::Fail => return Vec::new(),
+            OnConflict::Ignore => "DO NOTHING",
+            OnConflict::Update => {
+                "DO UPDATE SET ask = EXCLUDED.ask, bid = EXCLUDED.bid, \
+                 ask_volume = EXCLUDED.ask_volume, bid_volume = EXCLUDED.bid_volume"
+            }
+        };
+
+        vec![format!(
+            "INSERT INTO {} SELECT * FROM {} ON CONFLICT (instrument, time) {action}",
+            quote_identifier(&self.name),
+            self.copy_target()
+        )]
+    }
+
+    fn copy_target(&self) -> String {
+        match self.on_conflict {
+            OnConflict::Fail => quote_identifier(&self.name),
+            OnConflict::Ignore | OnConflict::Update => {
+                let table = self.name.rsplit('.').next().unwrap_or(&self.name);
+                quote_identifier(&format!("{table}_staging"))
+            }
+        }
+    }
+}
+
+/// Writes ticks in PostgreSQL's binary COPY format, which is the fastest way
+/// to load them into PostgreSQL or TimescaleDB
+///
+/// The writer is expected to be a copy started with
+/// [copy_statement](PostgresTable::copy_statement), e.g. the writer returned by the
+/// `copy_in` method of the `postgres` crate. Ticks are buffered and passed
+/// to the writer in batches of 10 000 by default.
+pub struct PostgresCopySink<W: Write> {
+    writer: W,
+    instrument: String,
+    batch: Vec<u8>,
+    batch_size: usize,
+    batched: usize,
+    header_written: bool,
+}
+
+impl<W: Write> PostgresCopySink<W> {
+    pub fn new(writer: W, instrument: impl Into<String>) -> PostgresCopySink<W> {
+        PostgresCopySink {
+            writer,
+            instrument: instrument.into(),
+            batch: Vec::new(),
+            batch_size: 10_000,
+            batched: 0,
+            header_written: false,
+        }
+    }
+
+    /// Number of ticks buffered before they're passed to the writer, at least one
+    pub fn with_batch_size(mut self, batch_size: usize) -> PostgresCopySink<W> {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Writes a single tick
+    pub fn write(&mut self, tick: &Tick) -> Result<(), crate::error::Error> {
+        if !self.header_written {
+            self.batch.extend_from_slice(HEADER);
+            self.header_written = true;
+        }
+
+        encode_row(&mut self.batch, &self.instrument, tick);
+        self.batched += 1;
+
+        if self.batched >= self.batch_size {
+            self.write_batch()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes all ticks of a stream, e.g. returned by
+    /// [download_ticks](crate::DukascopyService::download_ticks)
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - number of written ticks
+    /// * Err - the first error emitted by the stream or encountered while writing,
+    ///   ticks preceding it may already be written
+    pub async fn write_all<S>(&mut self, ticks: S) -> Result<u64, crate::error::Error>
+    where
+        S: Stream<Item = Result<Tick, crate::error::Error>>,
+    {
+        let mut ticks = Box::pin(ticks);
+        let mut written = 0;
+
+        while let Some(tick) = ticks.next().await {
+            self.write(&tick?)?;
+            written += 1;
+        }
+
+        Ok(written)
+    }
+
+    /// Ends the copy and returns the underlying writer, which still has to be
+    /// finished, e.g. with the `finish` method of the `postgres` crate's writer
+    pub fn into_inner(mut self) -> Result<W, crate::error::Error> {
+        if !self.header_written {
+            self.batch.extend_from_slice(HEADER);
+        }
+        self.batch.write_i16::<BigEndian>(-1).map_err(io_error)?;
+        self.write_batch()?;
+
+        self.writer.flush().map_err(io_error)?;
+        Ok(self.writer)
+    }
+
+    fn write_batch(&mut self) -> Result<(), crate::error::Error> {
+        self.writer.write_all(&self.batch).map_err(io_error)?;
+        self.batch.clear();
+        self.batched = 0;
+
+        Ok(())
+    }
+}
+
+fn encode_row(buffer: &mut Vec<u8>, instrument: &str, tick: &Tick) {
+    let micros = (tick.time - POSTGRES_EPOCH_MILLIS) * 1000;
+
+    // writing into a Vec never fails
+    buffer.write_i16::<BigEndian>(6).unwrap();
+    buffer
+        .write_i32::<BigEndian>(instrument.len() as i32)
+        .unwrap();
+    buffer.extend_from_slice(instrument.as_bytes());
+    buffer.write_i32::<BigEndian>(8).unwrap();
+    buffer.write_i64::<BigEndian>(micros).unwrap();
+    for value in [tick.ask, tick.bid, tick.ask_volume, tick.bid_volume] {
+        buffer.write_i32::<BigEndian>(8).unwrap();
+        buffer.write_f64::<BigEndian>(value).unwrap();
+    }
+}
+
+/// Quotes each part of a possibly schema-qualified name
+fn quote_identifier(name: &str) -> String {
+    name.split('.')
+        .map(|e| format!("\"{}\"", e.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, ByteOrder};
+    use futures::{executor::block_on, stream};
+
+    use super::{OnConflict, PostgresCopySink, PostgresTable, HEADER};
+    use crate::Tick;
+
+    #[test]
+    fn encodes_ticks_in_binary_copy_format() {
+        let tick = Tick {
+            time: 946_684_800_001,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        };
+        let mut sink = PostgresCopySink::new(Vec::new(), "EURUSD").with_batch_size(1);
+
+        let written = block_on(sink.write_all(stream::iter([Ok(tick)]))).unwrap();
+        let bytes = sink.into_inner().unwrap();
+
+        assert_eq!(written, 1);
+        assert_eq!(&bytes[..HEADER.len()], HEADER);
+
+        let row = &bytes[HEADER.len()..];
+        assert_eq!(BigEndian::read_i16(&row[0..2]), 6);
+        assert_eq!(BigEndian::read_i32(&row[2..6]), 6);
+        assert_eq!(&row[6..12], b"EURUSD");
+        assert_eq!(BigEndian::read_i64(&row[16..24]), 1000);
+        assert_eq!(BigEndian::read_f64(&row[28..36]), 1.5);
+        assert_eq!(BigEndian::read_f64(&row[64..72]), 0.75);
+        assert_eq!(&row[72..], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn copies_through_a_staging_table_on_conflict() {
+        let table = PostgresTable::new("market.ticks").with_on_conflict(OnConflict::Ignore);
+
+        assert_eq!(
+            table.before_copy_statements(),
+            vec![
+                "CREATE TEMPORARY TABLE \"ticks_staging\" (LIKE \"market\".\"ticks\" INCLUDING DEFAULTS) ON COMMIT DROP"
+            ]
+        );
+        assert!(table
+            .copy_statement()
+            .starts_with("COPY \"ticks_staging\" ("));
+        assert_eq!(
+            table.after_copy_statements(),
+            vec![
+                "INSERT INTO \"market\".\"ticks\" SELECT * FROM \"ticks_staging\" ON CONFLICT (instrument, time) DO NOTHING"
+            ]
+        );
+
+        let table = PostgresTable::new("ticks");
+        assert!(table.before_copy_statements().is_empty());
+        assert!(table.copy_statement().starts_with("COPY \"ticks\" ("));
+    }
+
+    #[test]
+    fn creates_hypertables_with_quoted_names() {
+        let table = PostgresTable::new("Market.Ticks").with_hypertable(true);
+
+        assert_eq!(
+            table.create_statements()[1],
+            "SELECT create_hypertable('\"Market\".\"Ticks\"', 'time', if_not_exists => TRUE)"
+        );
+    }
+}
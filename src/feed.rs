@@ -0,0 +1,150 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::stream::Peekable;
+use futures::{Stream, StreamExt};
+
+use crate::{Candle, InstrumentTick, Tick};
+
+/// Event of a [MarketDataFeed](MarketDataFeed) happening at a point in time
+pub trait MarketEvent {
+    /// Time of the event, in the same unit as [Tick::time](Tick::time)
+    fn time(&self) -> i64;
+}
+
+impl MarketEvent for Tick {
+    fn time(&self) -> i64 {
+        self.time
+    }
+}
+
+impl MarketEvent for InstrumentTick {
+    fn time(&self) -> i64 {
+        self.tick.time
+    }
+}
+
+impl MarketEvent for Candle {
+    fn time(&self) -> i64 {
+        self.time
+    }
+}
+
+/// Source of market data events in chronological order, e.g. for a backtesting engine
+///
+/// Streams returned by [DukascopyService](crate::DukascopyService) become feeds
+/// with [into_feed](FeedExt::into_feed).
+#[async_trait(?Send)]
+pub trait MarketDataFeed {
+    type Event: MarketEvent;
+
+    /// Returns the next event, or None once the feed is exhausted
+    async fn next_event(&mut self) -> Option<Result<Self::Event, crate::error::Error>>;
+
+    /// Returns the time of the next event without consuming it, or None once the feed is exhausted
+    ///
+    /// An error has no time of its own, so it's reported as due right away, i.e. as `i64::MIN`,
+    /// for the engine to take it with [next_event](MarketDataFeed::next_event).
+    async fn peek_time(&mut self) -> Option<i64>;
+}
+
+/// Adds [into_feed](FeedExt::into_feed) to streams of market events
+pub trait FeedExt<T>: Stream<Item = Result<T, crate::error::Error>> + Sized {
+    /// Turns the stream into a [MarketDataFeed](MarketDataFeed)
+    fn into_feed(self) -> StreamFeed<Self> {
+        StreamFeed {
+            stream: Box::pin(self.peekable()),
+        }
+    }
+}
+
+impl<S, T> FeedExt<T> for S
+where
+    S: Stream<Item = Result<T, crate::error::Error>>,
+    T: MarketEvent,
+{
+}
+
+/// [MarketDataFeed](MarketDataFeed) returned by [into_feed](FeedExt::into_feed)
+pub struct StreamFeed<S: Stream> {
+    stream: Pin<Box<Peekable<S>>>,
+}
+
+#[async_trait(?Send)]
+impl<S, T> MarketDataFeed for StreamFeed<S>
+where
+    S: Stream<Item = Result<T, crate::error::Error>>,
+    T: MarketEvent,
+{
+    type Event = T;
+
+    async fn next_event(&mut self) -> Option<Result<T, crate::error::Error>> {
+        self.stream.next().await
+    }
+
+    async fn peek_time(&mut self) -> Option<i64> {
+        self.stream.as_mut().peek().await.map(|e| match e {
+            Ok(event) => event.time(),
+            Err(_) => i64::MIN,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream};
+
+    use super::{FeedExt, MarketDataFeed};
+    use crate::{Kind, Tick};
+
+    fn tick(time: i64) -> Tick {
+        Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        }
+    }
+
+    #[test]
+    fn peeks_without_consuming() {
+        let events = vec![
+            Ok(tick(10)),
+            Err(crate::error::Error::new(Kind::Network, "connection reset")),
+            Ok(tick(20)),
+        ];
+        let mut feed = stream::iter(events).into_feed();
+
+        block_on(async {
+            assert_eq!(feed.peek_time().await, Some(10));
+            assert_eq!(feed.peek_time().await, Some(10));
+            assert_eq!(feed.next_event().await.unwrap().unwrap().time, 10);
+            assert_eq!(feed.peek_time().await, Some(i64::MIN));
+            assert!(feed.next_event().await.unwrap().is_err());
+            assert_eq!(feed.next_event().await.unwrap().unwrap().time, 20);
+            assert_eq!(feed.peek_time().await, None);
+            assert!(feed.next_event().await.is_none());
+        });
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn feeds_downloaded_ticks() {
+        use crate::{data_supplier::tests::TestResourceDataSupplier, DukascopyService};
+        use time::macros::datetime;
+
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let mut feed = service
+            .download_ticks(
+                "EURGBP",
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .into_feed();
+
+        let time = feed.peek_time().await.unwrap();
+        assert_eq!(feed.next_event().await.unwrap().unwrap().time, time);
+    }
+}
@@ -0,0 +1,144 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::dukascopy_service::DATAFEED_URL;
+use crate::error::{io_error, Kind};
+use crate::{DataSupplier, DatafeedLayout, StorageLayout};
+
+/// A [DataSupplier](DataSupplier) reading the files from a local mirror of the datafeed,
+/// so previously downloaded data can be replayed without any network access
+///
+/// URLs are mapped to files relative to the mirrored base URL, e.g.
+/// `https://datafeed.dukascopy.com/datafeed/EURUSD/2020/02/12/10h_ticks.bi5` is read from
+/// `{dir}/EURUSD/2020/02/12/10h_ticks.bi5`. This is the same layout a [DiskCache](crate::DiskCache)
+/// uses by default, so its directory can be used as a mirror as well, other layouts can be set with
+/// [with_layout](FileSystemDataSupplier::with_layout). Missing files are treated as hours without any data.
+#[derive(Debug, Clone)]
+pub struct FileSystemDataSupplier {
+    dir: PathBuf,
+    base_url: String,
+    layout: Arc<dyn StorageLayout>,
+}
+
+impl FileSystemDataSupplier {
+    /// Creates a supplier reading a mirror of the public datafeed stored in a given directory
+    pub fn new(dir: impl Into<PathBuf>) -> FileSystemDataSupplier {
+        FileSystemDataSupplier {
+            dir: dir.into(),
+            base_url: DATAFEED_URL.to_string(),
+            layout: Arc::new(DatafeedLayout),
+        }
+    }
+
+    /// Sets the base URL the directory mirrors, it has to match the base URL of the
+    /// [DukascopyService](crate::DukascopyService) the supplier is used with
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> FileSystemDataSupplier {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the [layout](StorageLayout) the mirror is stored in
+    pub fn with_layout(mut self, layout: impl StorageLayout + 'static) -> FileSystemDataSupplier {
+        self.layout = Arc::new(layout);
+        self
+    }
+
+    /// Returns the directory the mirror is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, url: &str) -> Result<PathBuf, crate::error::Error> {
+        let relative = relative_path(&self.base_url, url).ok_or_else(|| {
+            crate::error::Error::new(
+                Kind::InvalidArgument,
+                format!(
+                    "{url} is not mirrored, the mirror's base URL is {}",
+                    self.base_url
+                ),
+            )
+        })?;
+
+        Ok(self.dir.join(self.layout.storage_path(relative)))
+    }
+}
+
+/// Returns the path of a URL relative to a given base URL, or None if it's not under it
+pub(crate) fn relative_path<'a>(base_url: &str, url: &'a str) -> Option<&'a str> {
+    url.strip_prefix(base_url.trim_end_matches('/'))
+        .filter(|e| e.starts_with('/'))
+        .map(|e| e.trim_start_matches('/'))
+}
+
+#[async_trait]
+impl DataSupplier for FileSystemDataSupplier {
+    async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let path = self.path(url)?;
+
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(Some(Bytes::from(bytes))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use super::FileSystemDataSupplier;
+    use crate::{DataSupplier, DukascopyService, Kind, Tick};
+
+    #[tokio::test]
+    async fn replays_mirrored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("EURGBP/2020/02/12/06h_ticks.bi5");
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/06h_ticks.bi5"),
+            &target,
+        )
+        .unwrap();
+
+        let service = DukascopyService::new(
+            String::from("https://example.com/datafeed"),
+            Box::new(
+                FileSystemDataSupplier::new(dir.path())
+                    .with_base_url("https://example.com/datafeed"),
+            ),
+        );
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 08:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert!(!ticks.is_empty());
+        assert!(ticks.iter().all(|e| e.datetime().unwrap().hour() == 6));
+    }
+
+    #[tokio::test]
+    async fn rejects_urls_outside_of_the_mirror() {
+        let supplier = FileSystemDataSupplier::new("mirror");
+
+        let error = supplier
+            .fetch("https://example.com/EURGBP/2020/02/12/06h_ticks.bi5")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error.kind, Kind::InvalidArgument));
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+/// Describes how [follow_ticks](crate::DukascopyService::follow_ticks) waits for
+/// the hourly files that haven't been published yet
+///
+/// A file of a given hour is requested once the hour is over. If it isn't there yet,
+/// it's requested again every `poll_interval` until `max_delay` after the end of the hour,
+/// after which the hour is considered to have no ticks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FollowOptions {
+    poll_interval: Duration,
+    max_delay: Duration,
+}
+
+impl Default for FollowOptions {
+    fn default() -> Self {
+        FollowOptions {
+            poll_interval: Duration::from_secs(60),
+            max_delay: Duration::from_secs(2 * 60 * 60),
+        }
+    }
+}
+
+impl FollowOptions {
+    pub fn new() -> FollowOptions {
+        FollowOptions::default()
+    }
+
+    /// How long to wait before requesting a file that isn't published yet again, a minute by default
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> FollowOptions {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// How long after the end of an hour its file is waited for, two hours by default
+    pub fn with_max_delay(mut self, max_delay: Duration) -> FollowOptions {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub(crate) fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    pub(crate) fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use time::macros::datetime;
+    use time::{OffsetDateTime, PrimitiveDateTime, Time};
+
+    use super::FollowOptions;
+    use crate::{data_supplier::tests::TestResourceDataSupplier, DataSupplier, DukascopyService};
+
+    /// Serves nothing for a given number of requests, then always the same file
+    struct DelayedDataSupplier {
+        misses: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DataSupplier for DelayedDataSupplier {
+        async fn fetch(&self, _url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            if self.misses.load(Ordering::SeqCst) > 0 {
+                self.misses.fetch_sub(1, Ordering::SeqCst);
+                return Ok(None);
+            }
+
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/06h_ticks.bi5");
+            Ok(Some(Bytes::from(fs::read(path).unwrap())))
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_published_hours() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let ticks = service
+            .follow_ticks("EURGBP", datetime!(2020-03-12 06:00), FollowOptions::new())
+            .map(|e| e.unwrap())
+            .skip_while(|e| futures::future::ready(e.datetime().unwrap().hour() < 8))
+            .take(1)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(ticks[0].datetime().unwrap().hour(), 8);
+    }
+
+    #[tokio::test]
+    async fn polls_for_hours_not_published_yet() {
+        let supplier = DelayedDataSupplier {
+            misses: AtomicUsize::new(2),
+        };
+        let service = DukascopyService::new(String::from(""), Box::new(supplier))
+            .with_closed_hours_skipped(false);
+        let now = OffsetDateTime::now_utc();
+        let last_hour = PrimitiveDateTime::new(now.date(), Time::MIDNIGHT)
+            .replace_hour(now.hour())
+            .unwrap()
+            - time::Duration::HOUR;
+
+        let tick = service
+            .follow_ticks(
+                "EURGBP",
+                last_hour,
+                FollowOptions::new().with_poll_interval(Duration::from_millis(1)),
+            )
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(tick.datetime().unwrap().hour(), last_hour.hour());
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::Kind;
+use crate::trading_hours::{TimeZone, TradingHours};
+
+/// Kind of market an [Instrument](Instrument) belongs to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssetClass {
+    Forex,
+    Metal,
+    Index,
+    Commodity,
+    Crypto,
+
+    /// Any other instrument, e.g. a stock CFD
+    Other,
+}
+
+/// Instrument served by the datafeed
+///
+/// Built-in instruments can be looked up with [lookup](Instrument::lookup) or parsed
+/// with [FromStr](std::str::FromStr), which also accept aliases such as `EUR/USD`
+/// or `usa500.idx/usd`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instrument {
+    /// Ticker used in the datafeed's URLs, e.g. `EURUSD`
+    pub name: String,
+
+    pub description: String,
+
+    /// Price of a single pip, e.g. `0.0001` for EURUSD and `0.01` for USDJPY
+    pub pip_size: f64,
+
+    /// Factor the integer prices stored in the datafeed's files are divided by
+    pub decimal_factor: f64,
+
+    pub asset_class: AssetClass,
+
+    /// When the instrument is traded, hours outside of it are skipped by downloads
+    pub trading_hours: TradingHours,
+}
+
+// index and commodity CFDs follow the extended session of the exchange or futures market
+// their prices are derived from, erring on the side of being open
+const FOREX: TradingHours = TradingHours::Forex;
+const DAILY_BREAK: TradingHours = TradingHours::DailyBreak;
+const CONTINUOUS: TradingHours = TradingHours::Continuous;
+const LONDON: TradingHours = TradingHours::Weekdays {
+    zone: TimeZone::London,
+    open: 7,
+    close: 21,
+};
+const ICE_BRENT: TradingHours = TradingHours::Weekdays {
+    zone: TimeZone::London,
+    open: 1,
+    close: 23,
+};
+const CENTRAL_EUROPE: TradingHours = TradingHours::Weekdays {
+    zone: TimeZone::CentralEurope,
+    open: 8,
+    close: 22,
+};
+
+#[rustfmt::skip]
+const BUILT_IN: [(&str, &str, f64, f64, AssetClass, TradingHours); 53] = [
+    ("EURUSD", "Euro vs US Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("GBPUSD", "Pound Sterling vs US Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("AUDUSD", "Australian Dollar vs US Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("NZDUSD", "New Zealand Dollar vs US Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDCAD", "US Dollar vs Canadian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDCHF", "US Dollar vs Swiss Franc", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDJPY", "US Dollar vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("EURGBP", "Euro vs Pound Sterling", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURCHF", "Euro vs Swiss Franc", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURJPY", "Euro vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("EURAUD", "Euro vs Australian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURCAD", "Euro vs Canadian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURNZD", "Euro vs New Zealand Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURNOK", "Euro vs Norwegian Krone", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURSEK", "Euro vs Swedish Krona", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURPLN", "Euro vs Polish Zloty", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("EURHUF", "Euro vs Hungarian Forint", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("EURTRY", "Euro vs Turkish Lira", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("GBPCHF", "Pound Sterling vs Swiss Franc", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("GBPJPY", "Pound Sterling vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("GBPAUD", "Pound Sterling vs Australian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("GBPCAD", "Pound Sterling vs Canadian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("GBPNZD", "Pound Sterling vs New Zealand Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("AUDCAD", "Australian Dollar vs Canadian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("AUDCHF", "Australian Dollar vs Swiss Franc", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("AUDJPY", "Australian Dollar vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("AUDNZD", "Australian Dollar vs New Zealand Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("NZDCAD", "New Zealand Dollar vs Canadian Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("NZDCHF", "New Zealand Dollar vs Swiss Franc", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("NZDJPY", "New Zealand Dollar vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("CADCHF", "Canadian Dollar vs Swiss Franc", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("CADJPY", "Canadian Dollar vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("CHFJPY", "Swiss Franc vs Japanese Yen", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("USDNOK", "US Dollar vs Norwegian Krone", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDSEK", "US Dollar vs Swedish Krona", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDPLN", "US Dollar vs Polish Zloty", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDHUF", "US Dollar vs Hungarian Forint", 0.01, 1_000.0, AssetClass::Forex, FOREX),
+    ("USDSGD", "US Dollar vs Singapore Dollar", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDMXN", "US Dollar vs Mexican Peso", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("USDZAR", "US Dollar vs South African Rand", 0.0001, 100_000.0, AssetClass::Forex, FOREX),
+    ("XAUUSD", "Gold vs US Dollar", 0.01, 1_000.0, AssetClass::Metal, DAILY_BREAK),
+    ("XAGUSD", "Silver vs US Dollar", 0.001, 1_000.0, AssetClass::Metal, DAILY_BREAK),
+    ("USA500IDXUSD", "S&P 500", 1.0, 1_000.0, AssetClass::Index, DAILY_BREAK),
+    ("USATECHIDXUSD", "Nasdaq 100", 1.0, 1_000.0, AssetClass::Index, DAILY_BREAK),
+    ("USA30IDXUSD", "Dow Jones Industrial Average", 1.0, 1_000.0, AssetClass::Index, DAILY_BREAK),
+    ("DEUIDXEUR", "DAX", 1.0, 1_000.0, AssetClass::Index, CENTRAL_EUROPE),
+    ("GBRIDXGBP", "FTSE 100", 1.0, 1_000.0, AssetClass::Index, LONDON),
+    ("FRAIDXEUR", "CAC 40", 1.0, 1_000.0, AssetClass::Index, CENTRAL_EUROPE),
+    ("JPNIDXJPY", "Nikkei 225", 1.0, 1_000.0, AssetClass::Index, DAILY_BREAK),
+    ("BRENTCMDUSD", "Brent Crude Oil", 0.01, 1_000.0, AssetClass::Commodity, ICE_BRENT),
+    ("LIGHTCMDUSD", "WTI Light Crude Oil", 0.01, 1_000.0, AssetClass::Commodity, DAILY_BREAK),
+    ("BTCUSD", "Bitcoin vs US Dollar", 1.0, 10.0, AssetClass::Crypto, CONTINUOUS),
+    ("ETHUSD", "Ether vs US Dollar", 1.0, 10.0, AssetClass::Crypto, CONTINUOUS),
+];
+
+impl Instrument {
+    /// Returns all built-in instruments
+    pub fn built_in() -> Vec<Instrument> {
+        BUILT_IN.iter().map(Instrument::from_entry).collect()
+    }
+
+    /// Returns the built-in instrument with a given name or alias, ignoring case
+    /// and separators, e.g. `EURUSD`, `EUR/USD` and `eur-usd` are the same instrument
+    pub fn lookup(name: &str) -> Option<Instrument> {
+        let name = normalize(name);
+
+        BUILT_IN
+            .iter()
+            .find(|e| e.0 == name)
+            .map(Instrument::from_entry)
+    }
+
+    /// Returns whether a given name or alias refers to this instrument
+    pub fn matches(&self, name: &str) -> bool {
+        normalize(&self.name) == normalize(name)
+    }
+
+    fn from_entry(entry: &(&str, &str, f64, f64, AssetClass, TradingHours)) -> Instrument {
+        let (name, description, pip_size, decimal_factor, asset_class, trading_hours) = *entry;
+
+        Instrument {
+            name: name.to_string(),
+            description: description.to_string(),
+            pip_size,
+            decimal_factor,
+            asset_class,
+            trading_hours,
+        }
+    }
+}
+
+impl FromStr for Instrument {
+    type Err = crate::error::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Instrument::lookup(name).ok_or_else(|| unknown_instrument(name, &[]))
+    }
+}
+
+impl AsRef<str> for Instrument {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Display for Instrument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Returns an error for an instrument that couldn't be resolved, suggesting up to three
+/// built-in or `registered` instruments whose names are at most two edits away
+pub(crate) fn unknown_instrument(name: &str, registered: &[Instrument]) -> crate::error::Error {
+    let normalized = normalize(name);
+    let mut suggestions = BUILT_IN
+        .iter()
+        .map(|e| e.0)
+        .chain(registered.iter().map(|e| e.name.as_str()))
+        .map(|e| (edit_distance(&normalized, &normalize(e)), e))
+        .filter(|(distance, _)| *distance <= 2)
+        .collect::<Vec<_>>();
+    suggestions.sort();
+    suggestions.dedup_by(|a, b| a.1 == b.1);
+
+    let message = match suggestions.len() {
+        0 => format!("unknown instrument: {name}"),
+        _ => format!(
+            "unknown instrument: {name}, did you mean {}?",
+            suggestions
+                .iter()
+                .take(3)
+                .map(|e| e.1)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    crate::error::Error::new(Kind::UnknownInstrument, message)
+}
+
+/// Levenshtein distance between two ASCII strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.bytes().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.bytes().enumerate() {
+            let substitution = previous[j] + (a != b) as usize;
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|e| e.is_ascii_alphanumeric())
+        .map(|e| e.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetClass, Instrument};
+    use crate::Kind;
+
+    #[test]
+    fn looks_up_instruments_by_alias() {
+        let instrument = "EUR/USD".parse::<Instrument>().unwrap();
+        assert_eq!(instrument.name, "EURUSD");
+        assert_eq!(instrument.asset_class, AssetClass::Forex);
+
+        assert_eq!(
+            Instrument::lookup("usdjpy").unwrap().decimal_factor,
+            1_000.0
+        );
+        assert_eq!(
+            Instrument::lookup("USA500.IDX/USD").unwrap().asset_class,
+            AssetClass::Index
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_instruments() {
+        let error = "EURXYZ".parse::<Instrument>().unwrap_err();
+
+        assert!(matches!(error.kind, Kind::UnknownInstrument));
+        assert_eq!(error.inner.to_string(), "unknown instrument: EURXYZ");
+    }
+
+    #[test]
+    fn suggests_similar_instruments() {
+        let error = "EUR/UDS".parse::<Instrument>().unwrap_err();
+        assert_eq!(
+            error.inner.to_string(),
+            "unknown instrument: EUR/UDS, did you mean EURAUD, EURUSD?"
+        );
+
+        let registered = Instrument {
+            name: String::from("USDTHB"),
+            ..Instrument::lookup("USDJPY").unwrap()
+        };
+        let error = super::unknown_instrument("USD-THX", &[registered]);
+        assert_eq!(
+            error.inner.to_string(),
+            "unknown instrument: USD-THX, did you mean USDTHB, USDCHF?"
+        );
+    }
+}
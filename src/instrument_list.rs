@@ -0,0 +1,130 @@
+use serde_json::Value;
+
+use crate::error::Kind;
+use crate::trading_hours::TradingHours;
+use crate::{AssetClass, Instrument};
+
+/// Instrument metadata used by Dukascopy's web widgets
+pub(crate) const INSTRUMENTS_URL: &str =
+    "https://freeserv.dukascopy.com/2.0/index.php?path=common%2Finstruments&json";
+
+/// Parses the instrument metadata served at [INSTRUMENTS_URL](INSTRUMENTS_URL)
+///
+/// The metadata doesn't say how prices are stored, so instruments that are not
+/// [built-in](Instrument::built_in) get a decimal factor derived from their pip size.
+pub(crate) fn parse_instrument_list(bytes: &[u8]) -> Result<Vec<Instrument>, crate::error::Error> {
+    let text = String::from_utf8_lossy(bytes);
+
+    // the metadata may be wrapped in a JSONP callback
+    let json = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => return Err(decode_error("instrument list is not a JSON object".into())),
+    };
+    let root: Value = serde_json::from_str(json).map_err(|e| decode_error(Box::new(e)))?;
+    let entries = root
+        .get("instruments")
+        .and_then(Value::as_object)
+        .ok_or_else(|| decode_error("instrument list has no instruments".into()))?;
+
+    Ok(entries.values().filter_map(parse_instrument).collect())
+}
+
+fn parse_instrument(entry: &Value) -> Option<Instrument> {
+    let name = entry.get("historical_filename")?.as_str()?;
+    let pip_size = entry.get("pipValue")?.as_f64()?;
+    let description = entry
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    if let Some(built_in) = Instrument::lookup(name) {
+        return Some(Instrument {
+            description: description.to_string(),
+            ..built_in
+        });
+    }
+
+    let tags = entry
+        .get("tag_list")
+        .and_then(Value::as_array)
+        .map(|e| e.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let asset_class = classify(name, &tags);
+    let decimal_factor = match asset_class {
+        AssetClass::Index | AssetClass::Commodity => 1_000.0,
+        _ => (10.0 / pip_size).round(),
+    };
+
+    Some(Instrument {
+        name: name.to_string(),
+        description: description.to_string(),
+        pip_size,
+        decimal_factor,
+        asset_class,
+        trading_hours: TradingHours::for_asset_class(asset_class),
+    })
+}
+
+fn classify(name: &str, tags: &[&str]) -> AssetClass {
+    let has_tag = |tag: &str| tags.iter().any(|e| e.to_ascii_uppercase().contains(tag));
+
+    if name.contains("IDX") {
+        AssetClass::Index
+    } else if name.contains("CMD") {
+        AssetClass::Commodity
+    } else if ["XAU", "XAG", "XPT", "XPD"]
+        .iter()
+        .any(|e| name.starts_with(e))
+    {
+        AssetClass::Metal
+    } else if has_tag("CRYPTO") {
+        AssetClass::Crypto
+    } else if has_tag("CURRENCY") || has_tag("FX") {
+        AssetClass::Forex
+    } else {
+        AssetClass::Other
+    }
+}
+
+fn decode_error(inner: crate::error::BoxError) -> crate::error::Error {
+    crate::error::Error::new(Kind::Decode, inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_instrument_list;
+    use crate::{AssetClass, Kind};
+
+    #[test]
+    fn parses_instrument_list() {
+        let json = r#"jsonp({"instruments": {
+            "EUR/USD": {"historical_filename": "EURUSD", "description": "EUR vs USD",
+                "pipValue": 0.0001, "tag_list": ["CURRENCY"]},
+            "USD/THB": {"historical_filename": "USDTHB", "description": "USD vs THB",
+                "pipValue": 0.001, "tag_list": ["CURRENCY"]},
+            "AAPL.US/USD": {"historical_filename": "AAPLUSUSD", "description": "Apple",
+                "pipValue": 0.01, "tag_list": ["STCK_CFD"]},
+            "BROKEN": {"description": "no file name"}
+        }})"#;
+
+        let mut instruments = parse_instrument_list(json.as_bytes()).unwrap();
+        instruments.sort_by(|l, r| l.name.cmp(&r.name));
+
+        assert_eq!(instruments.len(), 3);
+        assert_eq!(instruments[0].name, "AAPLUSUSD");
+        assert_eq!(instruments[0].asset_class, AssetClass::Other);
+        assert_eq!(instruments[1].name, "EURUSD");
+        assert_eq!(instruments[1].decimal_factor, 100_000.0);
+        assert_eq!(instruments[1].description, "EUR vs USD");
+        assert_eq!(instruments[2].name, "USDTHB");
+        assert_eq!(instruments[2].asset_class, AssetClass::Forex);
+        assert_eq!(instruments[2].decimal_factor, 10_000.0);
+    }
+
+    #[test]
+    fn rejects_malformed_instrument_list() {
+        let error = parse_instrument_list(b"<html></html>").unwrap_err();
+
+        assert!(matches!(error.kind, Kind::Decode));
+    }
+}
@@ -1,10 +1,147 @@
-#![doc = include_str!("../README.md")]
+#![cfg_attr(feature = "client", doc = include_str!("../README.md"))]
+#[cfg(feature = "client")]
+mod adaptive_concurrency;
+#[cfg(feature = "client")]
+mod archive_manifest;
+mod averages;
+mod bars;
+pub mod bi5;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "client")]
+mod bulk_download;
+mod candle;
+mod chained_data_supplier;
+#[cfg(feature = "client")]
+mod chart_api;
 mod data_supplier;
+mod dedup;
+#[cfg(feature = "client")]
+mod disk_cache;
+#[cfg(feature = "client")]
+mod download_budget;
+#[cfg(feature = "client")]
+mod download_plan;
+#[cfg(feature = "client")]
 mod dukascopy_service;
 mod error;
+mod error_policy;
+pub mod export;
+mod feed;
+#[cfg(feature = "client")]
+mod file_system_data_supplier;
+#[cfg(feature = "client")]
+mod follow;
+mod instrument;
+#[cfg(feature = "client")]
+mod instrument_list;
+#[cfg(feature = "client")]
+mod merge;
+#[cfg(feature = "client")]
+mod mirror_processor;
+mod offset;
+#[cfg(feature = "client")]
+mod pacing;
+#[cfg(feature = "client")]
+mod prefetch;
+#[cfg(feature = "client")]
+mod progress;
+#[cfg(feature = "client")]
+mod quality;
+#[cfg(feature = "client")]
+mod rate_budget;
+#[cfg(feature = "client")]
+mod reconciliation;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
+#[cfg(feature = "client")]
+mod recording_data_supplier;
+#[cfg(feature = "client")]
+mod replay;
+#[cfg(feature = "client")]
+mod reqwest_data_supplier;
+#[cfg(feature = "client")]
+mod retry;
+mod sampling;
+#[cfg(feature = "client")]
+mod service_builder;
+mod sessions;
+mod spike_filter;
+mod spread_stats;
+#[cfg(feature = "client")]
+mod storage_layout;
+mod throughput;
 mod tick;
+#[cfg(feature = "tower")]
+mod tower_data_supplier;
+#[cfg(feature = "client")]
+mod trace;
+pub mod trading_hours;
 
+#[cfg(feature = "client")]
+pub use adaptive_concurrency::AdaptiveConcurrency;
+#[cfg(feature = "client")]
+pub use archive_manifest::{ArchiveManifest, FileStatus, ManifestEntry};
+pub use averages::{ticks_to_averages, PriceAverages};
+pub use bars::{ticks_to_bars, ticks_to_range_bars, ticks_to_renko, BarThreshold};
+#[cfg(feature = "client")]
+pub use bulk_download::{BulkDownload, BulkSummary};
+pub use candle::{ticks_to_candles, Candle, OfferSide, PriceSource, Timeframe};
+pub use chained_data_supplier::ChainedDataSupplier;
+#[cfg(feature = "client")]
+pub use chart_api::{CandleSource, SourcedCandle};
 pub use data_supplier::DataSupplier;
+pub use dedup::{DedupExt, DedupTicks, Duplicates};
+#[cfg(feature = "client")]
+pub use disk_cache::{CacheStats, DiskCache};
+#[cfg(feature = "client")]
+pub use download_budget::{BudgetSummary, DownloadBudget};
+#[cfg(feature = "client")]
+pub use download_plan::PlannedHour;
+#[cfg(feature = "client")]
 pub use dukascopy_service::DukascopyService;
-pub use error::{Error, Kind};
-pub use tick::Tick;
+pub use error::{Error, ErrorContext, Kind};
+pub use error_policy::{ErrorPolicy, ErrorPolicyExt, WithErrorPolicy};
+pub use feed::{FeedExt, MarketDataFeed, MarketEvent, StreamFeed};
+#[cfg(feature = "client")]
+pub use file_system_data_supplier::FileSystemDataSupplier;
+#[cfg(feature = "client")]
+pub use follow::FollowOptions;
+pub use instrument::{AssetClass, Instrument};
+#[cfg(feature = "client")]
+pub use mirror_processor::{process_mirror, MirrorProcessor, MirrorSummary};
+pub use offset::{OffsetExt, ShiftToOffset};
+#[cfg(feature = "client")]
+pub use pacing::PacingProfile;
+#[cfg(feature = "client")]
+pub use progress::{Progress, ProgressReporter};
+#[cfg(feature = "client")]
+pub use quality::{check_quality, QualityReport};
+#[cfg(feature = "client")]
+pub use rate_budget::RateBudget;
+#[cfg(feature = "client")]
+pub use reconciliation::{compare_sources, HourDifference, SourceComparison};
+#[cfg(feature = "client")]
+pub use recording_data_supplier::RecordingDataSupplier;
+#[cfg(feature = "client")]
+pub use replay::{Replay, ReplayExt};
+#[cfg(feature = "client")]
+pub use reqwest_data_supplier::{Credentials, RateLimit, ReqwestDataSupplier};
+#[cfg(feature = "client")]
+pub use retry::{ExponentialBackoff, RetryPolicy};
+pub use sampling::{downsample_ticks, SampleStrategy};
+#[cfg(feature = "client")]
+pub use service_builder::DukascopyServiceBuilder;
+pub use sessions::{split_sessions, FilterSession, SessionExt, SessionTicks};
+pub use spike_filter::{
+    FilterSpikes, Spike, SpikeAction, SpikeFilter, SpikeFilterExt, SpikeReason,
+};
+pub use spread_stats::{spread_stats, SpreadStats};
+#[cfg(feature = "client")]
+pub use storage_layout::{CalendarLayout, DatafeedLayout, HashedLayout, StorageLayout};
+pub use throughput::{MeasureThroughput, ThroughputExt, ThroughputReport};
+pub use tick::{HourBatch, InstrumentTick, RawTick, Tick, TickBatch};
+#[cfg(feature = "client")]
+pub use tokio_util::sync::CancellationToken;
+#[cfg(feature = "tower")]
+pub use tower_data_supplier::TowerDataSupplier;
@@ -0,0 +1,125 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+/// Merges streams that are each in chronological order into a single chronological stream
+///
+/// All streams are polled concurrently. An item is emitted once every stream that hasn't
+/// ended has an item ready, so that the earliest one can be picked, items with the same time
+/// are emitted in the order of the streams. Errors are emitted as soon as they arrive.
+pub(crate) struct MergeByTime<S, T> {
+    streams: Vec<Source<S, T>>,
+    time: fn(&T) -> i64,
+}
+
+struct Source<S, T> {
+    stream: Pin<Box<S>>,
+    head: Option<T>,
+    done: bool,
+}
+
+impl<S, T> MergeByTime<S, T>
+where
+    S: Stream<Item = Result<T, crate::error::Error>>,
+{
+    pub(crate) fn new(streams: Vec<S>, time: fn(&T) -> i64) -> MergeByTime<S, T> {
+        MergeByTime {
+            streams: streams
+                .into_iter()
+                .map(|e| Source {
+                    stream: Box::pin(e),
+                    head: None,
+                    done: false,
+                })
+                .collect(),
+            time,
+        }
+    }
+}
+
+// streams are pinned in their boxes and items are never pinned, so the merge can be moved
+impl<S, T> Unpin for MergeByTime<S, T> {}
+
+impl<S, T> Stream for MergeByTime<S, T>
+where
+    S: Stream<Item = Result<T, crate::error::Error>>,
+{
+    type Item = Result<T, crate::error::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        let mut waiting = false;
+        for source in this.streams.iter_mut() {
+            if source.head.is_some() || source.done {
+                continue;
+            }
+
+            match source.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(item))) => source.head = Some(item),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => source.done = true,
+                Poll::Pending => waiting = true,
+            }
+        }
+
+        if waiting {
+            return Poll::Pending;
+        }
+
+        let time = this.time;
+        let earliest = this
+            .streams
+            .iter_mut()
+            .filter(|e| e.head.is_some())
+            .min_by_key(|e| e.head.as_ref().map(time));
+
+        match earliest {
+            Some(source) => Poll::Ready(source.head.take().map(Ok)),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::MergeByTime;
+    use crate::Kind;
+
+    #[test]
+    fn merges_streams_chronologically() {
+        let streams = vec![
+            stream::iter(vec![Ok(1), Ok(4), Ok(4)]),
+            stream::iter(vec![
+                Ok(2),
+                Err(crate::error::Error::new(Kind::Decode, "")),
+                Ok(5),
+            ]),
+            stream::iter(vec![]),
+            stream::iter(vec![Ok(0), Ok(4)]),
+        ];
+
+        let merged = block_on(
+            MergeByTime::new(streams, |e: &i64| *e)
+                .map(|e| e.map_err(|e| e.kind))
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            merged,
+            vec![
+                Ok(0),
+                Ok(1),
+                Ok(2),
+                Err(Kind::Decode),
+                Ok(4),
+                Ok(4),
+                Ok(4),
+                Ok(5)
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::{stream, StreamExt};
+use time::{Duration, PrimitiveDateTime};
+
+use crate::dukascopy_service::{tick_file_path, validate_range};
+use crate::error::{io_error, Kind};
+use crate::{bi5, DatafeedLayout, Instrument, StorageLayout, Tick};
+
+/// Parses the hours of ticks stored in a local mirror of the datafeed on a pool of workers,
+/// without any network access, see [process_mirror](process_mirror)
+///
+/// The mirror can be written by a [RecordingDataSupplier](crate::RecordingDataSupplier)
+/// or a [DiskCache](crate::DiskCache), using the same [layout](StorageLayout).
+#[derive(Debug, Clone)]
+pub struct MirrorProcessor {
+    dir: PathBuf,
+    layout: Arc<dyn StorageLayout>,
+    workers: usize,
+}
+
+/// Outcome of a single [run](MirrorProcessor::run)
+#[derive(Debug, Default)]
+pub struct MirrorSummary {
+    /// Number of files parsed and processed successfully
+    pub files: u64,
+
+    /// Number of ticks in those files
+    pub ticks: u64,
+
+    /// Number of hours without a file in the mirror, they're skipped
+    pub missing: u64,
+
+    /// Errors of the files that couldn't be read, parsed or processed. The
+    /// [context](crate::Error::context) of each error tells which hour it belongs to.
+    pub failed: Vec<crate::error::Error>,
+}
+
+impl MirrorProcessor {
+    /// Creates a processor of a mirror stored in a given directory using the datafeed's layout,
+    /// with as many workers as there are CPUs
+    pub fn new(dir: impl Into<PathBuf>) -> MirrorProcessor {
+        MirrorProcessor {
+            dir: dir.into(),
+            layout: Arc::new(DatafeedLayout),
+            workers: std::thread::available_parallelism().map_or(1, |e| e.get()),
+        }
+    }
+
+    /// Sets the [layout](StorageLayout) the mirror is stored in
+    pub fn with_layout(mut self, layout: impl StorageLayout + 'static) -> MirrorProcessor {
+        self.layout = Arc::new(layout);
+        self
+    }
+
+    /// Sets how many files are parsed and processed at the same time, zero is treated as 1
+    pub fn with_workers(mut self, workers: usize) -> MirrorProcessor {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Returns the directory the mirror is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Parses the hours of given instruments and time interval in which they're
+    /// [traded](Instrument::trading_hours), passing the ticks of each hour to `process`
+    ///
+    /// Hours are processed in parallel and in no particular order, so `process` is typically
+    /// a sink or an aggregator behind a lock, or sends the ticks to a channel. A file that fails
+    /// to be read, parsed or processed doesn't stop the run, it's reported in the [summary](MirrorSummary).
+    ///
+    /// # Arguments
+    ///
+    /// * `instruments` - instruments to process, e.g. [built-in](Instrument::lookup) ones
+    /// * `start` and `end` - see [download_ticks](crate::DukascopyService::download_ticks)
+    /// * `process` - consumes the ticks of an hour
+    ///
+    /// # Returns
+    ///
+    /// * Ok - summary of the run
+    /// * Err - when the time interval is invalid
+    pub async fn run<F>(
+        &self,
+        instruments: &[Instrument],
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        process: F,
+    ) -> Result<MirrorSummary, crate::error::Error>
+    where
+        F: Fn(&Instrument, PrimitiveDateTime, Vec<Tick>) -> Result<(), crate::error::Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        validate_range(start, end)?;
+
+        let hours = instruments.iter().flat_map(|instrument| {
+            (0..(end - start).whole_hours())
+                .map(move |e| start + Duration::hours(e))
+                .filter(|e| instrument.trading_hours.is_open(e.assume_utc()))
+                .map(move |e| (instrument.clone(), e))
+        });
+
+        let process = Arc::new(process);
+        let mut results = stream::iter(hours)
+            .map(|(instrument, hour)| {
+                let path = self.dir.join(
+                    self.layout
+                        .storage_path(&tick_file_path(&instrument.name, hour)),
+                );
+                let process = process.clone();
+
+                tokio::task::spawn_blocking(move || {
+                    process_file(&path, &instrument, hour, process.as_ref())
+                        .map_err(|e| e.with_item(&instrument.name, hour))
+                })
+            })
+            .buffer_unordered(self.workers);
+
+        let mut summary = MirrorSummary::default();
+        while let Some(result) = results.next().await {
+            match result.map_err(|e| crate::error::Error::new(Kind::Io, e))? {
+                Ok(Some(ticks)) => {
+                    summary.files += 1;
+                    summary.ticks += ticks;
+                }
+                Ok(None) => summary.missing += 1,
+                Err(e) => summary.failed.push(e),
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Parses the hours of ticks stored in a local mirror in a given directory, see [MirrorProcessor::run]
+pub async fn process_mirror<F>(
+    dir: impl Into<PathBuf>,
+    instruments: &[Instrument],
+    start: PrimitiveDateTime,
+    end: PrimitiveDateTime,
+    process: F,
+) -> Result<MirrorSummary, crate::error::Error>
+where
+    F: Fn(&Instrument, PrimitiveDateTime, Vec<Tick>) -> Result<(), crate::error::Error>
+        + Send
+        + Sync
+        + 'static,
+{
+    MirrorProcessor::new(dir)
+        .run(instruments, start, end, process)
+        .await
+}
+
+/// Returns the number of processed ticks, None if there's no such file
+fn process_file<F>(
+    path: &Path,
+    instrument: &Instrument,
+    hour: PrimitiveDateTime,
+    process: &F,
+) -> Result<Option<u64>, crate::error::Error>
+where
+    F: Fn(&Instrument, PrimitiveDateTime, Vec<Tick>) -> Result<(), crate::error::Error>,
+{
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(io_error(e)),
+    };
+
+    let ticks = bi5::parse_hour(&bytes, hour, instrument.decimal_factor)?;
+    let count = ticks.len() as u64;
+    process(instrument, hour, ticks)?;
+
+    Ok(Some(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use time::macros::datetime;
+
+    use super::process_mirror;
+    use crate::{Instrument, Kind};
+
+    #[tokio::test]
+    async fn processes_mirrored_hours_and_reports_bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let hours = dir.path().join("EURGBP/2020/02/12");
+        std::fs::create_dir_all(&hours).unwrap();
+        for hour in ["06h_ticks.bi5", "07h_ticks.bi5"] {
+            std::fs::copy(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("test-resources")
+                    .join(hour),
+                hours.join(hour),
+            )
+            .unwrap();
+        }
+        std::fs::write(hours.join("08h_ticks.bi5"), b"not lzma").unwrap();
+
+        let processed = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let summary = process_mirror(
+            dir.path(),
+            &[Instrument::lookup("EURGBP").unwrap()],
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 10:00),
+            {
+                let processed = processed.clone();
+                move |_: &Instrument, hour, ticks: Vec<_>| {
+                    processed.lock().unwrap().push((hour, ticks.len()));
+                    Ok(())
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut processed = processed.lock().unwrap().clone();
+        processed.sort();
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.missing, 1);
+        assert_eq!(
+            summary.ticks,
+            processed.iter().map(|e| e.1 as u64).sum::<u64>()
+        );
+        assert_eq!(
+            processed.iter().map(|e| e.0).collect::<Vec<_>>(),
+            [datetime!(2020-03-12 06:00), datetime!(2020-03-12 07:00)]
+        );
+
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].kind, Kind::Decode);
+        assert_eq!(
+            summary.failed[0].context.hour,
+            Some(datetime!(2020-03-12 08:00))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use time::UtcOffset;
+
+use crate::Tick;
+
+/// Adds [shift_to_offset](OffsetExt::shift_to_offset) to tick streams
+pub trait OffsetExt: Stream<Item = Result<Tick, crate::error::Error>> + Sized {
+    /// Shifts the time of ticks by a given UTC offset, so that it's the local time
+    /// of that offset expressed as if it was UTC, e.g. `UtcOffset::from_hms(-5, 0, 0)`
+    /// turns 22:00 UTC into 17:00.
+    ///
+    /// Shifted ticks are meant for output and for building candles aligned to local
+    /// midnight, e.g. daily candles closing at 17:00 New York time. They no longer hold
+    /// unix timestamps, so don't mix them with unshifted ticks. Errors are passed through as they come.
+    fn shift_to_offset(self, offset: UtcOffset) -> ShiftToOffset<Self> {
+        ShiftToOffset {
+            stream: Box::pin(self),
+            millis: offset.whole_seconds() as i64 * 1000,
+        }
+    }
+}
+
+impl<S> OffsetExt for S where S: Stream<Item = Result<Tick, crate::error::Error>> {}
+
+/// Stream returned by [shift_to_offset](OffsetExt::shift_to_offset)
+pub struct ShiftToOffset<S> {
+    stream: Pin<Box<S>>,
+    millis: i64,
+}
+
+impl<S> Stream for ShiftToOffset<S>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        this.stream.as_mut().poll_next(cx).map(|e| {
+            e.map(|e| {
+                e.map(|tick| Tick {
+                    time: tick.time + this.millis,
+                    ..tick
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+    use time::macros::{datetime, offset};
+
+    use super::OffsetExt;
+    use crate::Tick;
+
+    #[test]
+    fn shifts_ticks_to_local_time() {
+        let tick = Tick {
+            time: datetime!(2020-02-14 22:00 UTC).unix_timestamp() * 1000,
+            ask: 1.1001,
+            bid: 1.1,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+
+        let shifted = block_on(
+            stream::iter([Ok(tick)])
+                .shift_to_offset(offset!(-5))
+                .map(|e| e.unwrap())
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            shifted[0].datetime().unwrap(),
+            datetime!(2020-02-14 17:00 UTC)
+        );
+        assert_eq!(shifted[0].ask, tick.ask);
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::time::Instant;
+
+/// Describes how requests made by [DukascopyService](crate::DukascopyService) are spaced out,
+/// so that long backfills stay polite towards Dukascopy's servers
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PacingProfile {
+    min_delay: Duration,
+    max_delay: Duration,
+    burst: Option<(usize, Duration)>,
+}
+
+impl PacingProfile {
+    /// Waits a random delay between `min_delay` and `max_delay` between consecutive requests,
+    /// a `max_delay` shorter than `min_delay` is raised to it
+    pub fn new(min_delay: Duration, max_delay: Duration) -> PacingProfile {
+        PacingProfile {
+            min_delay,
+            max_delay: max_delay.max(min_delay),
+            burst: None,
+        }
+    }
+
+    /// Short random delays, suitable for downloads of a few days
+    pub fn moderate() -> PacingProfile {
+        PacingProfile::new(Duration::from_millis(100), Duration::from_millis(500))
+            .with_burst_limit(100, Duration::from_secs(5))
+    }
+
+    /// Longer random delays and pauses, suitable for backfills spanning months or years
+    pub fn gentle() -> PacingProfile {
+        PacingProfile::new(Duration::from_millis(500), Duration::from_millis(1500))
+            .with_burst_limit(50, Duration::from_secs(30))
+    }
+
+    /// After every `size` requests (at least one), pauses for `pause` before making the next one
+    pub fn with_burst_limit(mut self, size: usize, pause: Duration) -> PacingProfile {
+        self.burst = Some((size.max(1), pause));
+        self
+    }
+
+    fn delay(&self) -> Duration {
+        if self.min_delay == self.max_delay {
+            self.min_delay
+        } else {
+            rand::thread_rng().gen_range(self.min_delay..=self.max_delay)
+        }
+    }
+}
+
+/// Keeps track of the requests made according to a [PacingProfile](PacingProfile)
+pub(crate) struct Pacer {
+    profile: PacingProfile,
+    state: Mutex<PacerState>,
+}
+
+struct PacerState {
+    next_request: Option<Instant>,
+    burst_requests: usize,
+}
+
+impl Pacer {
+    pub(crate) fn new(profile: PacingProfile) -> Pacer {
+        Pacer {
+            profile,
+            state: Mutex::new(PacerState {
+                next_request: None,
+                burst_requests: 0,
+            }),
+        }
+    }
+
+    /// Waits until the next request is allowed to start
+    pub(crate) async fn wait(&self) {
+        let start_at = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let mut start_at = state.next_request.map_or(now, |e| e.max(now));
+
+            if let Some((size, pause)) = self.profile.burst {
+                if state.burst_requests == size {
+                    start_at += pause;
+                    state.burst_requests = 0;
+                }
+                state.burst_requests += 1;
+            }
+
+            state.next_request = Some(start_at + self.profile.delay());
+            start_at
+        };
+
+        tokio::time::sleep_until(start_at).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{Pacer, PacingProfile};
+
+    #[tokio::test]
+    async fn delays_consecutive_requests() {
+        let pacer = Pacer::new(PacingProfile::new(
+            Duration::from_millis(20),
+            Duration::from_millis(20),
+        ));
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            pacer.wait().await;
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn raises_max_delay_to_min_delay() {
+        let profile = PacingProfile::new(Duration::from_millis(20), Duration::ZERO);
+
+        assert_eq!(profile.delay(), Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn pauses_after_a_burst() {
+        let pacer = Pacer::new(
+            PacingProfile::new(Duration::ZERO, Duration::ZERO)
+                .with_burst_limit(2, Duration::from_millis(50)),
+        );
+
+        let started = Instant::now();
+        pacer.wait().await;
+        pacer.wait().await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        pacer.wait().await;
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+use crate::Tick;
+
+/// Flattens a stream of downloaded hours into ticks, polling the hours that follow
+/// every time a tick is requested, so they keep downloading while the consumer
+/// processes the current hour
+///
+/// At most `lookahead` downloaded hours are kept ahead of the consumer.
+pub(crate) struct Prefetch<S> {
+    hours: Pin<Box<S>>,
+    hours_done: bool,
+    ready: VecDeque<Result<Vec<Tick>, crate::error::Error>>,
+    current: std::vec::IntoIter<Tick>,
+    lookahead: usize,
+}
+
+impl<S> Prefetch<S>
+where
+    S: Stream<Item = Result<Vec<Tick>, crate::error::Error>>,
+{
+    pub(crate) fn new(hours: S, lookahead: usize) -> Prefetch<S> {
+        Prefetch {
+            hours: Box::pin(hours),
+            hours_done: false,
+            ready: VecDeque::new(),
+            current: Vec::new().into_iter(),
+            lookahead: lookahead.max(1),
+        }
+    }
+
+    /// Moves hours that finished downloading to the queue
+    fn poll_hours(&mut self, cx: &mut Context<'_>) {
+        while !self.hours_done && self.ready.len() < self.lookahead {
+            match self.hours.as_mut().poll_next(cx) {
+                Poll::Ready(Some(hour)) => self.ready.push_back(hour),
+                Poll::Ready(None) => self.hours_done = true,
+                Poll::Pending => break,
+            }
+        }
+    }
+}
+
+impl<S> Stream for Prefetch<S>
+where
+    S: Stream<Item = Result<Vec<Tick>, crate::error::Error>>,
+{
+    type Item = Result<Tick, crate::error::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            this.poll_hours(cx);
+
+            if let Some(tick) = this.current.next() {
+                return Poll::Ready(Some(Ok(tick)));
+            }
+
+            match this.ready.pop_front() {
+                Some(Ok(ticks)) => this.current = ticks.into_iter(),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None if this.hours_done => return Poll::Ready(None),
+                // the queue is empty and the next hour isn't downloaded yet, polling
+                // the hours above registered the waker
+                None => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use time::PrimitiveDateTime;
+
+/// Progress of a single download, reported after each hour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The hour that was just completed
+    pub hour: PrimitiveDateTime,
+
+    /// Number of hours completed so far, including the failed ones
+    pub hours_completed: u64,
+
+    /// Number of hours that failed so far
+    pub hours_failed: u64,
+
+    /// Number of hours the download consists of, hours in which the instrument
+    /// is not traded are not counted
+    pub hours_total: u64,
+
+    /// Number of (compressed) bytes downloaded so far
+    pub bytes: u64,
+
+    /// Number of ticks decoded so far
+    pub ticks: u64,
+}
+
+/// Receives the [Progress](Progress) of downloads, e.g. to render a progress bar or emit metrics
+///
+/// Any `Fn(&Progress)` closure can be used as a reporter.
+pub trait ProgressReporter: Send + Sync {
+    /// Called after each hour of a download is completed, in chronological order
+    fn on_hour(&self, progress: &Progress);
+}
+
+impl ProgressReporter for Box<dyn ProgressReporter> {
+    fn on_hour(&self, progress: &Progress) {
+        self.as_ref().on_hour(progress)
+    }
+}
+
+impl<F> ProgressReporter for F
+where
+    F: Fn(&Progress) + Send + Sync,
+{
+    fn on_hour(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+impl Progress {
+    pub(crate) fn new(start: PrimitiveDateTime, hours_total: u64) -> Progress {
+        Progress {
+            hour: start,
+            hours_completed: 0,
+            hours_failed: 0,
+            hours_total,
+            bytes: 0,
+            ticks: 0,
+        }
+    }
+
+    /// Records a completed hour
+    pub(crate) fn complete<T>(
+        &mut self,
+        hour: PrimitiveDateTime,
+        bytes: u64,
+        result: &Result<Vec<T>, crate::error::Error>,
+    ) {
+        self.hour = hour;
+        self.hours_completed += 1;
+        self.bytes += bytes;
+        match result {
+            Ok(items) => self.ticks += items.len() as u64,
+            Err(_) => self.hours_failed += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use super::Progress;
+    use crate::{data_supplier::tests::TestResourceDataSupplier, DukascopyService};
+
+    #[tokio::test]
+    async fn reports_progress_after_each_hour() {
+        let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let recorded = reports.clone();
+        let service = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(TestResourceDataSupplier {}),
+        )
+        .with_progress(move |e: &Progress| recorded.lock().unwrap().push(*e));
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 10:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 4);
+        assert_eq!(
+            reports.iter().map(|e| e.hour).collect::<Vec<_>>(),
+            vec![
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+                datetime!(2020-03-12 08:00),
+                datetime!(2020-03-12 09:00),
+            ]
+        );
+
+        let last = reports.last().unwrap();
+        assert_eq!((last.hours_completed, last.hours_total), (4, 4));
+        assert_eq!(last.hours_failed, 0);
+        assert_eq!(last.ticks, ticks.len() as u64);
+        assert!(last.bytes > 0);
+    }
+}
@@ -0,0 +1,183 @@
+use time::PrimitiveDateTime;
+
+use crate::dukascopy_service::validate_range;
+use crate::{DukascopyService, Tick};
+
+/// Outcome of [check_quality](check_quality)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QualityReport {
+    /// Number of hours downloaded and checked
+    pub hours_checked: usize,
+
+    /// Number of ticks checked
+    pub ticks_checked: usize,
+
+    /// Hours without any ticks although the instrument should be traded in them
+    pub empty_hours: Vec<PrimitiveDateTime>,
+
+    /// Ticks whose time is earlier than the time of the tick preceding them
+    pub out_of_order: Vec<Tick>,
+
+    /// Ticks with a zero or negative ask or bid
+    pub invalid_prices: Vec<Tick>,
+
+    /// Ticks whose ask is below their bid or whose spread exceeds the given limit
+    pub abnormal_spreads: Vec<Tick>,
+}
+
+impl QualityReport {
+    /// Returns whether no problems were found
+    pub fn is_clean(&self) -> bool {
+        self.empty_hours.is_empty()
+            && self.out_of_order.is_empty()
+            && self.invalid_prices.is_empty()
+            && self.abnormal_spreads.is_empty()
+    }
+}
+
+/// Downloads a range and reports problems with the quality of its data, such as gaps
+/// during market hours, ticks out of chronological order, non-positive prices or abnormal spreads.
+///
+/// Hours are expected to be traded according to the instrument's
+/// [trading hours](crate::Instrument::trading_hours). A tick with an invalid price isn't checked for its spread.
+///
+/// # Arguments
+///
+/// * `service` - service the range is downloaded with
+/// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+/// * `max_spread_pips` - spread in [pips](crate::Instrument::pip_size) above which it's considered abnormal
+///
+/// # Returned value
+///
+/// * Ok - when the whole range was downloaded and checked
+/// * Err - when the range or instrument is invalid or fetching or parsing any hour failed
+pub async fn check_quality(
+    service: &DukascopyService,
+    instrument: impl AsRef<str>,
+    start: PrimitiveDateTime,
+    end: PrimitiveDateTime,
+    max_spread_pips: f64,
+) -> Result<QualityReport, crate::error::Error> {
+    validate_range(start, end)?;
+    let instrument = service.resolve_instrument(instrument.as_ref())?;
+    let schedule = instrument.trading_hours;
+
+    let mut report = QualityReport::default();
+    let mut previous = None::<Tick>;
+
+    for hour in service.compute_tick_download_times(&instrument, start, end) {
+        let ticks = service.fetch_hour(&instrument, hour).await?;
+
+        report.hours_checked += 1;
+        report.ticks_checked += ticks.len();
+        if ticks.is_empty() && schedule.is_open(hour.assume_utc()) {
+            report.empty_hours.push(hour);
+        }
+
+        for tick in ticks {
+            if previous.is_some_and(|e| tick.time < e.time) {
+                report.out_of_order.push(tick);
+            }
+
+            if tick.ask <= 0.0 || tick.bid <= 0.0 {
+                report.invalid_prices.push(tick);
+            } else if tick.spread() < 0.0 || tick.spread_pips(instrument.pip_size) > max_spread_pips
+            {
+                report.abnormal_spreads.push(tick);
+            }
+
+            previous = Some(tick);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use time::macros::datetime;
+
+    use super::check_quality;
+    use crate::{
+        bi5, data_supplier::tests::TestResourceDataSupplier, DataSupplier, DukascopyService, Tick,
+    };
+
+    /// Serves a single hour with a few broken ticks
+    struct BrokenDataSupplier;
+
+    #[async_trait]
+    impl DataSupplier for BrokenDataSupplier {
+        async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            if !url.ends_with("06h_ticks.bi5") {
+                return Ok(None);
+            }
+
+            let hour = datetime!(2020-03-12 06:00 UTC).unix_timestamp() * 1000;
+            let tick = |offset: i64, ask: f64, bid: f64| Tick {
+                time: hour + offset,
+                ask,
+                bid,
+                ask_volume: 1.0,
+                bid_volume: 1.0,
+            };
+            let ticks = [
+                tick(1_000, 0.86, 0.8599),
+                tick(500, 0.86, 0.8599),
+                tick(2_000, 0.86, 0.0),
+                tick(3_000, 0.85, 0.86),
+                tick(4_000, 0.90, 0.80),
+            ];
+
+            bi5::encode_hour(&ticks, datetime!(2020-03-12 06:00), 100_000.0).map(|e| Some(e.into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn published_data_is_clean() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let report = check_quality(
+            &service,
+            "EURGBP",
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 09:00),
+            50.0,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.hours_checked, 3);
+        assert!(report.ticks_checked > 0);
+        assert!(report.is_clean());
+    }
+
+    #[tokio::test]
+    async fn reports_gaps_and_broken_ticks() {
+        let service = DukascopyService::new(String::from(""), Box::new(BrokenDataSupplier));
+
+        let report = check_quality(
+            &service,
+            "EURGBP",
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 08:00),
+            50.0,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.empty_hours, vec![datetime!(2020-03-12 07:00)]);
+        assert_eq!(
+            report
+                .out_of_order
+                .iter()
+                .map(|e| e.time % 10_000)
+                .collect::<Vec<_>>(),
+            vec![500]
+        );
+        assert_eq!(report.invalid_prices.len(), 1);
+        assert_eq!(report.abnormal_spreads.len(), 2);
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// Request budget that can be shared between multiple [DukascopyService](crate::DukascopyService)
+/// instances, so that their aggregate traffic stays within limits
+///
+/// Cloning a budget is cheap and every clone refers to the same limits.
+#[derive(Clone)]
+pub struct RateBudget {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    permits: Semaphore,
+    interval: Duration,
+    burst_tolerance: Duration,
+    next_request: Mutex<Instant>,
+}
+
+impl RateBudget {
+    /// Creates a budget allowing at most `max_concurrent_requests` requests in flight
+    /// and starting consecutive requests no sooner than `min_interval` apart
+    pub fn new(max_concurrent_requests: usize, min_interval: Duration) -> RateBudget {
+        RateBudget::token_bucket(max_concurrent_requests, min_interval, 1)
+    }
+
+    /// Creates a budget that only spaces requests at least `min_interval` apart,
+    /// without limiting how many of them are in flight
+    pub fn interval(min_interval: Duration) -> RateBudget {
+        RateBudget::new(Semaphore::MAX_PERMITS, min_interval)
+    }
+
+    /// Creates a token bucket budget allowing at most `max_concurrent_requests` requests in flight.
+    ///
+    /// The bucket holds up to `capacity` tokens and is refilled with one token every
+    /// `refill_interval`, each request takes one token. This allows short bursts
+    /// of up to `capacity` requests while keeping the average rate limited.
+    /// Both `max_concurrent_requests` and `capacity` are at least 1.
+    pub fn token_bucket(
+        max_concurrent_requests: usize,
+        refill_interval: Duration,
+        capacity: u32,
+    ) -> RateBudget {
+        RateBudget {
+            inner: Arc::new(Inner {
+                permits: Semaphore::new(max_concurrent_requests.max(1)),
+                interval: refill_interval,
+                burst_tolerance: refill_interval * (capacity.max(1) - 1),
+                next_request: Mutex::new(Instant::now()),
+            }),
+        }
+    }
+
+    /// Waits until a request is allowed to start, the returned permit
+    /// has to be held for as long as the request is in flight
+    pub(crate) async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self.inner.permits.acquire().await.unwrap();
+
+        // `next_request` is the time at which the bucket would be empty again,
+        // a request can start as long as it's no further away than the burst allows
+        let start_at = {
+            let mut next_request = self.inner.next_request.lock().unwrap();
+            let now = Instant::now();
+            let start_at = next_request
+                .checked_sub(self.inner.burst_tolerance)
+                .map_or(now, |e| e.max(now));
+            *next_request = (*next_request).max(start_at) + self.inner.interval;
+            start_at
+        };
+        tokio::time::sleep_until(start_at).await;
+
+        permit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use time::macros::datetime;
+    use tokio::time::Instant;
+
+    use crate::{data_supplier::tests::TestResourceDataSupplier, DukascopyService, RateBudget};
+
+    #[tokio::test]
+    async fn spaces_requests_of_services_sharing_a_budget() {
+        let budget = RateBudget::new(1, Duration::from_millis(20));
+        let first = DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}))
+            .with_rate_budget(budget.clone());
+        let second = DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}))
+            .with_rate_budget(budget);
+
+        let started = Instant::now();
+        let (first_count, second_count) = futures::join!(
+            first
+                .download_ticks(
+                    String::from("EURGBP"),
+                    datetime!(2020-03-12 06:00),
+                    datetime!(2020-03-12 08:00),
+                )
+                .count(),
+            second
+                .download_ticks(
+                    String::from("EURGBP"),
+                    datetime!(2020-03-12 06:00),
+                    datetime!(2020-03-12 08:00),
+                )
+                .count(),
+        );
+
+        assert_eq!(first_count, second_count);
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn allows_bursts_up_to_bucket_capacity() {
+        let budget = RateBudget::token_bucket(10, Duration::from_millis(50), 3);
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            let _permit = budget.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        let _permit = budget.acquire().await;
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
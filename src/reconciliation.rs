@@ -0,0 +1,279 @@
+use std::cmp::Ordering;
+
+use time::PrimitiveDateTime;
+
+use crate::dukascopy_service::validate_range;
+use crate::error::Kind;
+use crate::{DukascopyService, Tick};
+
+/// Difference between two sources found within a single hour
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HourDifference {
+    pub hour: PrimitiveDateTime,
+
+    /// Number of ticks the left source has for this hour
+    pub left_ticks: usize,
+
+    /// Number of ticks the right source has for this hour
+    pub right_ticks: usize,
+
+    /// Number of ticks of the right source without a tick at the same time in the left source
+    pub missing_in_left: usize,
+
+    /// Number of ticks of the left source without a tick at the same time in the right source
+    pub missing_in_right: usize,
+
+    /// Number of ticks present at the same time in both sources whose values differ
+    pub mismatched_ticks: usize,
+
+    /// Kind of error fetching or parsing the hour from the left source failed with, if any,
+    /// in which case ticks aren't compared
+    pub left_error: Option<Kind>,
+
+    /// Kind of error fetching or parsing the hour from the right source failed with, if any,
+    /// in which case ticks aren't compared
+    pub right_error: Option<Kind>,
+}
+
+impl HourDifference {
+    /// Number of ticks present in one source but missing in the other
+    pub fn missing_ticks(&self) -> usize {
+        self.missing_in_left + self.missing_in_right
+    }
+
+    /// Returns whether the hour couldn't be fetched or parsed from either source
+    pub fn is_failed(&self) -> bool {
+        self.left_error.is_some() || self.right_error.is_some()
+    }
+}
+
+/// Outcome of [compare_sources](compare_sources)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceComparison {
+    /// Number of hours fetched from both sources
+    pub hours_compared: usize,
+
+    /// Hours in which the sources differ, including the ones that failed in either source
+    pub differences: Vec<HourDifference>,
+}
+
+impl SourceComparison {
+    /// Returns whether both sources returned exactly the same data
+    pub fn is_identical(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// Downloads the same range from two sources (e.g. the live datafeed and a local mirror)
+/// and reports hours in which their data differs.
+///
+/// Ticks are aligned by their time, ticks sharing the same time are paired in order of appearance.
+/// An hour that can't be fetched or parsed from either source is reported as a difference
+/// with the kind of the error, and the comparison continues with the next hour.
+///
+/// # Arguments
+///
+/// * `left` and `right` - services configured with the sources to compare
+/// * `instrument`, `start` and `end` - see [download_ticks](DukascopyService::download_ticks)
+///
+/// # Returned value
+///
+/// * Ok - when the range was compared, even if some hours failed
+/// * Err - when the range or instrument is invalid
+pub async fn compare_sources(
+    left: &DukascopyService,
+    right: &DukascopyService,
+    instrument: impl AsRef<str>,
+    start: PrimitiveDateTime,
+    end: PrimitiveDateTime,
+) -> Result<SourceComparison, crate::error::Error> {
+    validate_range(start, end)?;
+    let instrument = left.resolve_instrument(instrument.as_ref())?;
+
+    let mut comparison = SourceComparison::default();
+
+    for hour in left.compute_tick_download_times(&instrument, start, end) {
+        let (left_ticks, right_ticks) = futures::join!(
+            left.fetch_hour(&instrument, hour),
+            right.fetch_hour(&instrument, hour)
+        );
+
+        let difference = match (left_ticks, right_ticks) {
+            (Ok(left_ticks), Ok(right_ticks)) => {
+                comparison.hours_compared += 1;
+                compare_hour(hour, &left_ticks, &right_ticks)
+            }
+            (left_ticks, right_ticks) => HourDifference {
+                left_ticks: left_ticks.as_ref().map_or(0, |e| e.len()),
+                right_ticks: right_ticks.as_ref().map_or(0, |e| e.len()),
+                left_error: left_ticks.err().map(|e| e.kind),
+                right_error: right_ticks.err().map(|e| e.kind),
+                ..compare_hour(hour, &[], &[])
+            },
+        };
+        if difference.is_failed()
+            || difference.missing_ticks() > 0
+            || difference.mismatched_ticks > 0
+        {
+            comparison.differences.push(difference);
+        }
+    }
+
+    Ok(comparison)
+}
+
+fn compare_hour(hour: PrimitiveDateTime, left: &[Tick], right: &[Tick]) -> HourDifference {
+    let mut difference = HourDifference {
+        hour,
+        left_ticks: left.len(),
+        right_ticks: right.len(),
+        missing_in_left: 0,
+        missing_in_right: 0,
+        mismatched_ticks: 0,
+        left_error: None,
+        right_error: None,
+    };
+
+    let (mut l, mut r) = (0, 0);
+    while l < left.len() && r < right.len() {
+        match left[l].time.cmp(&right[r].time) {
+            Ordering::Less => {
+                difference.missing_in_right += 1;
+                l += 1;
+            }
+            Ordering::Greater => {
+                difference.missing_in_left += 1;
+                r += 1;
+            }
+            Ordering::Equal => {
+                difference.mismatched_ticks += (left[l] != right[r]) as usize;
+                l += 1;
+                r += 1;
+            }
+        }
+    }
+    difference.missing_in_right += left.len() - l;
+    difference.missing_in_left += right.len() - r;
+
+    difference
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use time::macros::datetime;
+
+    use super::{compare_hour, compare_sources};
+    use crate::{
+        data_supplier::tests::{InMemoryDataSupplier, TestResourceDataSupplier},
+        DataSupplier, DukascopyService, Kind, Tick,
+    };
+
+    /// Serves test resources, except for the 7th hour which is corrupt
+    struct CorruptHourDataSupplier {}
+
+    #[async_trait]
+    impl DataSupplier for CorruptHourDataSupplier {
+        async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            match url.ends_with("07h_ticks.bi5") {
+                true => Ok(Some(Bytes::from_static(b"not lzma"))),
+                false => TestResourceDataSupplier {}.fetch(url).await,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_sources_have_no_differences() {
+        let left = DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let right = DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+
+        let comparison = compare_sources(
+            &left,
+            &right,
+            String::from("EURGBP"),
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 09:00),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(comparison.hours_compared, 3);
+        assert!(comparison.is_identical());
+    }
+
+    #[tokio::test]
+    async fn reports_hours_missing_in_one_source() {
+        let left = DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let right = DukascopyService::new(
+            String::from(""),
+            Box::new(InMemoryDataSupplier { data: None }),
+        );
+
+        let comparison = compare_sources(
+            &left,
+            &right,
+            String::from("EURGBP"),
+            datetime!(2020-03-12 05:00),
+            datetime!(2020-03-12 07:00),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(comparison.hours_compared, 2);
+        assert_eq!(comparison.differences.len(), 1);
+
+        let difference = &comparison.differences[0];
+        assert_eq!(difference.hour, datetime!(2020-03-12 06:00));
+        assert_eq!(difference.right_ticks, 0);
+        assert_eq!(difference.missing_ticks(), difference.left_ticks);
+        assert_eq!(difference.missing_in_right, difference.left_ticks);
+        assert_eq!(difference.mismatched_ticks, 0);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_hours_and_compares_the_rest() {
+        let left = DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let right = DukascopyService::new(String::from(""), Box::new(CorruptHourDataSupplier {}));
+
+        let comparison = compare_sources(
+            &left,
+            &right,
+            String::from("EURGBP"),
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 09:00),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(comparison.hours_compared, 2);
+        assert_eq!(comparison.differences.len(), 1);
+
+        let difference = &comparison.differences[0];
+        assert_eq!(difference.hour, datetime!(2020-03-12 07:00));
+        assert!(difference.is_failed());
+        assert_eq!(difference.left_error, None);
+        assert_eq!(difference.right_error, Some(Kind::Decode));
+        assert!(difference.left_ticks > 0);
+        assert_eq!(difference.missing_ticks(), 0);
+    }
+
+    #[test]
+    fn aligns_ticks_by_time() {
+        let tick = |time: i64, ask: f64| Tick {
+            time,
+            ask,
+            bid: ask - 0.0001,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+        let left = [tick(1, 1.1), tick(2, 1.2), tick(3, 1.3), tick(5, 1.5)];
+        let right = [tick(1, 1.1), tick(3, 1.3), tick(4, 1.4), tick(5, 1.6)];
+
+        let difference = compare_hour(datetime!(2020-03-12 06:00), &left, &right);
+
+        assert_eq!(difference.missing_in_left, 1);
+        assert_eq!(difference.missing_in_right, 1);
+        assert_eq!(difference.mismatched_ticks, 1);
+    }
+}
@@ -0,0 +1,108 @@
+//! Conversion of ticks to Arrow record batches
+
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float64Type, TimestampMillisecondType};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, TimestampMillisecondArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::error::Kind;
+use crate::Tick;
+
+/// Returns the schema of record batches made of ticks
+///
+/// It consists of a non-nullable `time` column of UTC timestamps with millisecond precision, followed by
+/// `ask`, `bid`, `ask_volume` and `bid_volume` columns of 64-bit floats.
+pub fn tick_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("ask", DataType::Float64, false),
+        Field::new("bid", DataType::Float64, false),
+        Field::new("ask_volume", DataType::Float64, false),
+        Field::new("bid_volume", DataType::Float64, false),
+    ]))
+}
+
+/// Converts ticks to a record batch with the [tick schema](tick_schema)
+pub fn ticks_to_record_batch(ticks: &[Tick]) -> Result<RecordBatch, crate::error::Error> {
+    let column = |f: fn(&Tick) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(ticks.iter().map(f)))
+    };
+
+    RecordBatch::try_new(
+        tick_schema(),
+        vec![
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(ticks.iter().map(|e| e.time))
+                    .with_timezone("UTC"),
+            ),
+            column(|e| e.ask),
+            column(|e| e.bid),
+            column(|e| e.ask_volume),
+            column(|e| e.bid_volume),
+        ],
+    )
+    .map_err(|e| crate::error::Error::new(Kind::Decode, e))
+}
+
+/// Converts a record batch with the [tick schema](tick_schema) back to ticks,
+/// e.g. one read from a Parquet file written by `ParquetSink`
+pub fn record_batch_to_ticks(batch: &RecordBatch) -> Result<Vec<Tick>, crate::error::Error> {
+    if batch.schema().fields() != tick_schema().fields() {
+        return Err(crate::error::Error::new(
+            Kind::Decode,
+            "record batch doesn't have the tick schema",
+        ));
+    }
+
+    let time = batch.column(0).as_primitive::<TimestampMillisecondType>();
+    let column = |index: usize| batch.column(index).as_primitive::<Float64Type>();
+    let (ask, bid, ask_volume, bid_volume) = (column(1), column(2), column(3), column(4));
+
+    Ok((0..batch.num_rows())
+        .map(|i| Tick {
+            time: time.value(i),
+            ask: ask.value(i),
+            bid: bid.value(i),
+            ask_volume: ask_volume.value(i),
+            bid_volume: bid_volume.value(i),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{cast::AsArray, types::Float64Type};
+
+    use super::{record_batch_to_ticks, ticks_to_record_batch};
+    use crate::Tick;
+
+    #[test]
+    fn converts_ticks_to_columns() {
+        let ticks = [0, 1].map(|time| Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25 + time as f64,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        });
+
+        let batch = ticks_to_record_batch(&ticks).unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch
+                .column_by_name("bid")
+                .unwrap()
+                .as_primitive::<Float64Type>()
+                .values(),
+            &[1.25, 2.25]
+        );
+        assert_eq!(record_batch_to_ticks(&batch).unwrap(), ticks);
+    }
+}
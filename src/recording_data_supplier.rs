@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::archive_manifest::ManifestEntry;
+use crate::dukascopy_service::DATAFEED_URL;
+use crate::file_system_data_supplier::relative_path;
+use crate::{ArchiveManifest, DataSupplier, DiskCache, StorageLayout};
+
+/// A [DataSupplier](DataSupplier) passing the responses of another one through
+/// while writing them to a directory, building an archive of everything downloaded
+///
+/// Files are written using the same layout as the datafeed unless [another one](RecordingDataSupplier::with_layout)
+/// is set, so the directory can be replayed later with a [FileSystemDataSupplier](crate::FileSystemDataSupplier)
+/// using the same layout.
+/// Only responses with data are recorded, and URLs outside of the recorded base URL
+/// (e.g. the instrument list) are passed through without being recorded.
+pub struct RecordingDataSupplier {
+    inner: Box<dyn DataSupplier + Send + Sync>,
+    archive: DiskCache,
+    base_url: String,
+    manifest: Option<ArchiveManifest>,
+}
+
+impl RecordingDataSupplier {
+    /// Records the responses of a given supplier of the public datafeed into a given directory
+    pub fn new(
+        inner: impl DataSupplier + Send + Sync + 'static,
+        dir: impl Into<PathBuf>,
+    ) -> RecordingDataSupplier {
+        RecordingDataSupplier {
+            inner: Box::new(inner),
+            archive: DiskCache::new(dir),
+            base_url: DATAFEED_URL.to_string(),
+            manifest: None,
+        }
+    }
+
+    /// Sets the base URL file paths are relative to, it has to match the base URL of the
+    /// [DukascopyService](crate::DukascopyService) the supplier is used with
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> RecordingDataSupplier {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the [layout](StorageLayout) files are written in, the datafeed's one by default
+    pub fn with_layout(mut self, layout: impl StorageLayout + 'static) -> RecordingDataSupplier {
+        self.archive = self.archive.with_layout(layout);
+        self
+    }
+
+    /// Records the provenance of each written file in a given [manifest](ArchiveManifest),
+    /// which is appended to once the file is written
+    pub fn with_manifest(mut self, manifest: ArchiveManifest) -> RecordingDataSupplier {
+        self.manifest = Some(manifest);
+        self
+    }
+}
+
+#[async_trait]
+impl DataSupplier for RecordingDataSupplier {
+    async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let bytes = self.inner.fetch(url).await?;
+
+        if let (Some(bytes), Some(path)) = (&bytes, relative_path(&self.base_url, url)) {
+            self.archive.put(path, bytes).await?;
+
+            if let Some(manifest) = &self.manifest {
+                manifest
+                    .record(&ManifestEntry::new(
+                        &self.archive.storage_path(path),
+                        url,
+                        bytes,
+                    ))
+                    .await?;
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use super::RecordingDataSupplier;
+    use crate::{
+        data_supplier::tests::TestResourceDataSupplier, ArchiveManifest, CalendarLayout,
+        DukascopyService, FileSystemDataSupplier, Tick,
+    };
+
+    #[tokio::test]
+    async fn records_files_for_replaying() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let recording = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(
+                RecordingDataSupplier::new(TestResourceDataSupplier {}, dir.path())
+                    .with_base_url("https://example.com"),
+            ),
+        );
+        let replaying = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(FileSystemDataSupplier::new(dir.path()).with_base_url("https://example.com")),
+        );
+
+        let recorded = recording
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let replayed = replaying
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert!(!recorded.is_empty());
+        assert_eq!(replayed, recorded);
+        assert!(dir.path().join("EURGBP/2020/02/12/08h_ticks.bi5").exists());
+    }
+
+    #[tokio::test]
+    async fn replays_files_recorded_with_another_layout() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let recording = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(
+                RecordingDataSupplier::new(TestResourceDataSupplier {}, dir.path())
+                    .with_base_url("https://example.com")
+                    .with_layout(CalendarLayout),
+            ),
+        );
+        let replaying = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(
+                FileSystemDataSupplier::new(dir.path())
+                    .with_base_url("https://example.com")
+                    .with_layout(CalendarLayout),
+            ),
+        );
+
+        let recorded = recording
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+        let replayed = replaying
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        assert!(!recorded.is_empty());
+        assert_eq!(replayed, recorded);
+        assert!(dir.path().join("EURGBP/2020/03/12/06h_ticks.bi5").exists());
+    }
+
+    #[tokio::test]
+    async fn writes_a_manifest_of_recorded_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = ArchiveManifest::new(dir.path().join("manifest.tsv"));
+
+        let recording = DukascopyService::new(
+            String::from("https://example.com"),
+            Box::new(
+                RecordingDataSupplier::new(TestResourceDataSupplier {}, dir.path())
+                    .with_base_url("https://example.com")
+                    .with_manifest(manifest.clone()),
+            ),
+        );
+        let ticks = recording
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 09:00),
+            )
+            .map(|e| e.unwrap())
+            .collect::<Vec<Tick>>()
+            .await;
+
+        let entries = manifest.entries().await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].url,
+            "https://example.com/EURGBP/2020/02/12/06h_ticks.bi5"
+        );
+        assert_eq!(
+            entries.iter().map(|e| e.ticks.unwrap()).sum::<u64>(),
+            ticks.len() as u64
+        );
+        assert!(manifest
+            .verify(dir.path())
+            .await
+            .unwrap()
+            .iter()
+            .all(|(_, status)| *status == crate::FileStatus::Valid));
+    }
+}
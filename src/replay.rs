@@ -0,0 +1,118 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{ready, Stream};
+use tokio::time::{Instant, Sleep};
+
+use crate::Tick;
+
+/// Adds [replay](ReplayExt::replay) to tick streams
+pub trait ReplayExt: Stream<Item = Result<Tick, crate::error::Error>> + Sized {
+    /// Re-emits the ticks of the stream paced by the time between them, as if they were
+    /// arriving live, e.g. to test a trading system against historical data.
+    ///
+    /// The first tick is emitted right away and each following one once the time between
+    /// it and the first tick, divided by `speed`, has elapsed. A `speed` of 1.0 replays
+    /// in real time, 60.0 replays an hour in a minute. Ticks that are already late, e.g.
+    /// because the download couldn't keep up, are emitted right away and errors are
+    /// passed through as they come. A `speed` that isn't positive replays in real time.
+    fn replay(self, speed: f64) -> Replay<Self> {
+        Replay {
+            stream: Box::pin(self),
+            speed: if speed > 0.0 { speed } else { 1.0 },
+            anchor: None,
+            delayed: None,
+        }
+    }
+}
+
+impl<S> ReplayExt for S where S: Stream<Item = Result<Tick, crate::error::Error>> {}
+
+/// Stream returned by [replay](ReplayExt::replay)
+pub struct Replay<S> {
+    stream: Pin<Box<S>>,
+    speed: f64,
+    anchor: Option<(Instant, i64)>,
+    delayed: Option<(Pin<Box<Sleep>>, Tick)>,
+}
+
+impl<S> Stream for Replay<S>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some((sleep, tick)) = &mut this.delayed {
+            ready!(sleep.as_mut().poll(cx));
+            let tick = *tick;
+            this.delayed = None;
+            return Poll::Ready(Some(Ok(tick)));
+        }
+
+        match ready!(this.stream.as_mut().poll_next(cx)) {
+            Some(Ok(tick)) => {
+                let (start, first) = *this.anchor.get_or_insert((Instant::now(), tick.time));
+                let offset = (tick.time - first).max(0) as f64 / 1000.0 / this.speed;
+                let deadline = start + Duration::from_secs_f64(offset);
+                if deadline <= Instant::now() {
+                    return Poll::Ready(Some(Ok(tick)));
+                }
+
+                let mut sleep = Box::pin(tokio::time::sleep_until(deadline));
+                match sleep.as_mut().poll(cx) {
+                    Poll::Ready(()) => Poll::Ready(Some(Ok(tick))),
+                    Poll::Pending => {
+                        this.delayed = Some((sleep, tick));
+                        Poll::Pending
+                    }
+                }
+            }
+            other => Poll::Ready(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::{stream, StreamExt};
+
+    use super::ReplayExt;
+    use crate::Tick;
+
+    fn tick(time: i64) -> Tick {
+        Tick {
+            time,
+            ask: 1.5,
+            bid: 1.25,
+            ask_volume: 2.0,
+            bid_volume: 0.75,
+        }
+    }
+
+    #[tokio::test]
+    async fn paces_ticks_by_their_time_deltas() {
+        let ticks = [1_000, 1_100, 1_300].map(|e| Ok(tick(e)));
+        let started = Instant::now();
+
+        let replayed = stream::iter(ticks)
+            .replay(2.0)
+            .map(|e| (e.unwrap().time, started.elapsed()))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            replayed.iter().map(|(time, _)| *time).collect::<Vec<_>>(),
+            vec![1_000, 1_100, 1_300]
+        );
+        assert!(replayed[0].1 < Duration::from_millis(50));
+        assert!(replayed[1].1 >= Duration::from_millis(50));
+        assert!(replayed[2].1 >= Duration::from_millis(150));
+    }
+}
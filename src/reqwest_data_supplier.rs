@@ -0,0 +1,482 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER},
+    Client, ClientBuilder, Response, StatusCode,
+};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+use crate::error::Kind;
+use crate::DataSupplier;
+
+/// Provides credentials for endpoints that require authentication,
+/// e.g. partner endpoints or internal mirrors
+#[async_trait]
+pub trait Credentials: Send + Sync {
+    /// Returns headers (e.g. `Authorization` or `Cookie`) attached to every request
+    async fn headers(&self) -> Result<HeaderMap, crate::error::Error>;
+
+    /// Called when the server rejected the credentials with `401` or `403`.
+    ///
+    /// Returns whether the credentials were refreshed, in which case the request is retried once.
+    async fn refresh(&self) -> Result<bool, crate::error::Error> {
+        Ok(false)
+    }
+}
+
+/// Static headers that never need refreshing
+#[async_trait]
+impl Credentials for HeaderMap {
+    async fn headers(&self) -> Result<HeaderMap, crate::error::Error> {
+        Ok(self.clone())
+    }
+}
+
+/// Inner error of [RateLimited](crate::Kind::RateLimited) errors, returned when the server
+/// responds with `429 Too Many Requests` or `503 Service Unavailable`
+///
+/// [ExponentialBackoff](crate::ExponentialBackoff) waits at least `retry_after` before retrying.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    pub status: u16,
+
+    /// How long the server asked to wait with its `Retry-After` header, if it did
+    pub retry_after: Option<Duration>,
+}
+
+impl std::fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "server responded with {}", self.status)?;
+        if let Some(retry_after) = self.retry_after {
+            write!(f, ", retry after {}s", retry_after.as_secs())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimit {}
+
+/// How long establishing a connection may take by default
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a whole request, including reading the response, may take by default
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often idle connections are probed to be kept alive
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// The default [DataSupplier](DataSupplier), fetching the data over HTTP
+///
+/// A single client is shared by all requests, so connections are reused.
+pub struct ReqwestDataSupplier {
+    client: Client,
+    headers: HeaderMap,
+    credentials: Option<Box<dyn Credentials>>,
+}
+
+impl Default for ReqwestDataSupplier {
+    fn default() -> Self {
+        ReqwestDataSupplier::new()
+    }
+}
+
+impl ReqwestDataSupplier {
+    /// Creates a supplier with a client configured by [client_builder](ReqwestDataSupplier::client_builder)
+    pub fn new() -> Self {
+        let client = ReqwestDataSupplier::client_builder()
+            .build()
+            .expect("failed to initialize the HTTP client");
+
+        ReqwestDataSupplier::from_client(client)
+    }
+
+    /// Creates a supplier sending requests with a given client, e.g. one configured with
+    /// custom timeouts. Start with [client_builder](ReqwestDataSupplier::client_builder)
+    /// to keep the defaults that aren't changed.
+    pub fn from_client(client: Client) -> Self {
+        ReqwestDataSupplier {
+            client,
+            headers: HeaderMap::new(),
+            credentials: None,
+        }
+    }
+
+    /// Returns a builder of the client used by [new](ReqwestDataSupplier::new), which gives up
+    /// on connecting after 10 seconds and on a whole request after 60 seconds, and keeps
+    /// idle connections alive
+    pub fn client_builder() -> ClientBuilder {
+        Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .tcp_keepalive(KEEP_ALIVE)
+    }
+
+    /// Sends given headers with every request, e.g. a custom `User-Agent`, a `Referer`
+    /// or a static token of an internal mirror. Headers of [Credentials](Credentials) take precedence.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Authenticates every request using given [Credentials](Credentials)
+    pub fn with_credentials(mut self, credentials: impl Credentials + 'static) -> Self {
+        self.credentials = Some(Box::new(credentials));
+        self
+    }
+
+    async fn send(&self, url: &str) -> Result<Response, crate::error::Error> {
+        let mut request = self.client.get(url).headers(self.headers.clone());
+        if let Some(credentials) = &self.credentials {
+            request = request.headers(credentials.headers().await?);
+        }
+
+        request.send().await.map_err(network_error)
+    }
+}
+
+#[async_trait]
+impl DataSupplier for ReqwestDataSupplier {
+    async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let mut response = self.send(url).await?;
+
+        if let Some(credentials) = &self.credentials {
+            let rejected = matches!(
+                response.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            );
+            if rejected && credentials.refresh().await? {
+                response = self.send(url).await?;
+            }
+        }
+
+        // it is a valid case for the server to return a 404 - it means there were no events
+        // during the requested time interval
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if let Some(error) = rate_limit_error(response.status(), response.headers()) {
+            return Err(error);
+        }
+
+        let response = response.error_for_status().map_err(network_error)?;
+        let is_file = is_file(response.url().path());
+        let is_html = is_html(response.headers());
+        let bytes = response.bytes().await.map_err(network_error)?;
+
+        check_body(is_file, is_html, bytes)
+    }
+}
+
+/// Returns a [RateLimited](crate::Kind::RateLimited) error if the server throttles requests,
+/// which the datafeed does with both `429` and `503`
+pub(crate) fn rate_limit_error(
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Option<crate::error::Error> {
+    if !matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        return None;
+    }
+
+    let rate_limit = RateLimit {
+        status: status.as_u16(),
+        retry_after: retry_after(headers),
+    };
+    Some(crate::error::Error::new(Kind::RateLimited, rate_limit))
+}
+
+/// Returns whether a URL path points to a bi5 file, as opposed to e.g. the instrument list
+pub(crate) fn is_file(path: &str) -> bool {
+    path.ends_with(".bi5")
+}
+
+pub(crate) fn is_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|e| e.to_str().ok())
+        .is_some_and(|e| e.contains("html"))
+}
+
+/// Rejects pages returned instead of bi5 files, e.g. maintenance pages, and treats
+/// an empty body as no data. Bodies of other URLs aren't sniffed.
+pub(crate) fn check_body(
+    is_file: bool,
+    is_html: bool,
+    bytes: Bytes,
+) -> Result<Option<Bytes>, crate::error::Error> {
+    if is_file && (is_html || !looks_like_lzma(&bytes)) {
+        return Err(crate::error::Error::new(
+            Kind::ServerError,
+            "server responded with a page instead of a file",
+        ));
+    }
+
+    Ok(if bytes.is_empty() { None } else { Some(bytes) })
+}
+
+/// Parses a `Retry-After` header, either in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = OffsetDateTime::parse(value, &Rfc2822).ok()?;
+    Some(
+        (date - OffsetDateTime::now_utc())
+            .try_into()
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns whether a response body could be an LZMA file rather than e.g. an HTML error page.
+/// A file starts with a properties byte, which is below 225, an empty body is an empty file.
+fn looks_like_lzma(bytes: &[u8]) -> bool {
+    match bytes.iter().find(|e| !e.is_ascii_whitespace()) {
+        Some(b'<') => false,
+        _ => bytes.first().is_none_or(|&e| e < 225),
+    }
+}
+
+pub(crate) fn network_error(error: reqwest::Error) -> crate::error::Error {
+    let kind = match error.status() {
+        _ if error.is_timeout() => Kind::Timeout,
+        Some(StatusCode::TOO_MANY_REQUESTS) => Kind::RateLimited,
+        Some(status) if status.is_server_error() => Kind::ServerError,
+        _ => Kind::Network,
+    };
+
+    crate::error::Error::new(kind, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, REFERER, USER_AGENT};
+    use warp::{http::StatusCode, Filter};
+
+    use super::{Credentials, RateLimit, ReqwestDataSupplier};
+    use crate::instrument_list::parse_instrument_list;
+    use crate::DataSupplier;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        headers
+    }
+
+    async fn serve_authenticated() -> String {
+        let route = warp::header::optional::<String>("authorization").map(
+            |authorization: Option<String>| match authorization.as_deref() {
+                Some("Bearer valid") => warp::reply::with_status("data", StatusCode::OK),
+                _ => warp::reply::with_status("", StatusCode::UNAUTHORIZED),
+            },
+        );
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        format!("http://{address}/EURUSD/2020/02/12/10h_ticks.bi5")
+    }
+
+    struct RefreshingCredentials {
+        refreshed: AtomicBool,
+    }
+
+    #[async_trait]
+    impl Credentials for RefreshingCredentials {
+        async fn headers(&self) -> Result<HeaderMap, crate::error::Error> {
+            Ok(match self.refreshed.load(Ordering::SeqCst) {
+                true => bearer("valid"),
+                false => bearer("expired"),
+            })
+        }
+
+        async fn refresh(&self) -> Result<bool, crate::error::Error> {
+            self.refreshed.store(true, Ordering::SeqCst);
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_credentials_headers() {
+        let url = serve_authenticated().await;
+        let supplier = ReqwestDataSupplier::new().with_credentials(bearer("valid"));
+
+        let bytes = supplier.fetch(&url).await.unwrap();
+
+        assert_eq!(bytes.unwrap().as_ref(), b"data");
+    }
+
+    #[tokio::test]
+    async fn fails_without_credentials() {
+        let url = serve_authenticated().await;
+        let supplier = ReqwestDataSupplier::new();
+
+        let error = supplier.fetch(&url).await.unwrap_err();
+
+        assert!(matches!(error.kind, crate::Kind::Network));
+    }
+
+    #[tokio::test]
+    async fn sends_custom_headers() {
+        let route = warp::header::<String>("referer")
+            .and(warp::header::<String>("user-agent"))
+            .map(|referer: String, user_agent: String| format!("{referer} {user_agent}"));
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            REFERER,
+            HeaderValue::from_static("https://mirror.example.com"),
+        );
+        headers.insert(USER_AGENT, HeaderValue::from_static("archiver/1.0"));
+        let supplier = ReqwestDataSupplier::new().with_headers(headers);
+
+        let bytes = supplier
+            .fetch(&format!("http://{address}/EURUSD/2020/02/12/10h_ticks.bi5"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            bytes.unwrap().as_ref(),
+            b"https://mirror.example.com archiver/1.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_with_refreshed_credentials() {
+        let url = serve_authenticated().await;
+        let supplier = ReqwestDataSupplier::new().with_credentials(RefreshingCredentials {
+            refreshed: AtomicBool::new(false),
+        });
+
+        let bytes = supplier.fetch(&url).await.unwrap();
+
+        assert_eq!(bytes.unwrap().as_ref(), b"data");
+    }
+
+    #[tokio::test]
+    async fn times_out_hung_requests() {
+        let route = warp::any().then(|| async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            "data"
+        });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let client = ReqwestDataSupplier::client_builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let supplier = ReqwestDataSupplier::from_client(client);
+
+        let error = supplier
+            .fetch(&format!("http://{address}/EURUSD/2020/02/12/10h_ticks.bi5"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error.kind, crate::Kind::Timeout));
+    }
+
+    #[tokio::test]
+    async fn classifies_error_statuses() {
+        let route = warp::path!(u16)
+            .map(|status: u16| warp::reply::with_status("", StatusCode::from_u16(status).unwrap()));
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let supplier = ReqwestDataSupplier::new();
+
+        let mut kinds = Vec::new();
+        for status in [429, 503, 500, 400] {
+            let url = format!("http://{address}/{status}");
+            kinds.push(supplier.fetch(&url).await.unwrap_err().kind);
+        }
+
+        assert!(matches!(
+            kinds[..],
+            [
+                crate::Kind::RateLimited,
+                crate::Kind::RateLimited,
+                crate::Kind::ServerError,
+                crate::Kind::Network
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn reads_retry_after_headers() {
+        let route = warp::any().map(|| {
+            warp::reply::with_header(
+                warp::reply::with_status("", StatusCode::TOO_MANY_REQUESTS),
+                "retry-after",
+                "7",
+            )
+        });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let error = ReqwestDataSupplier::new()
+            .fetch(&format!("http://{address}/EURUSD/2020/02/12/10h_ticks.bi5"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error.kind, crate::Kind::RateLimited));
+        assert_eq!(
+            error.inner.downcast_ref::<RateLimit>(),
+            Some(&RateLimit {
+                status: 429,
+                retry_after: Some(Duration::from_secs(7)),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_pages_that_are_not_files() {
+        let route = warp::path!("2.0" / "index.php").map(|| {
+            warp::reply::html(
+                r#"jsonp({"instruments": {"EUR/USD": {"historical_filename": "EURUSD",
+                    "description": "EUR vs USD", "pipValue": 0.0001}}})"#,
+            )
+        });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let bytes = ReqwestDataSupplier::new()
+            .fetch(&format!(
+                "http://{address}/2.0/index.php?path=common%2Finstruments&json"
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let instruments = parse_instrument_list(&bytes).unwrap();
+        assert_eq!(instruments[0].name, "EURUSD");
+    }
+
+    #[tokio::test]
+    async fn rejects_html_pages() {
+        let route = warp::any().map(|| warp::reply::html("<html>maintenance</html>"));
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let error = ReqwestDataSupplier::new()
+            .fetch(&format!("http://{address}/EURUSD/2020/02/12/10h_ticks.bi5"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error.kind, crate::Kind::ServerError));
+    }
+}
@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::error::Kind;
+use crate::RateLimit;
+
+/// Decides whether and when a failed request is retried, see [DukascopyService::with_retry](crate::DukascopyService::with_retry)
+///
+/// Implement it for custom strategies, e.g. to stop retrying altogether after too many failures
+/// or to handle some errors differently. Any `Fn(usize, &Error) -> Option<Duration>` closure
+/// can be used as a policy.
+pub trait RetryPolicy: Send + Sync {
+    /// Returns how long to wait before retrying after a given failed attempt (starting at 1),
+    /// or None if the error should be returned
+    fn decide(&self, attempt: usize, error: &crate::error::Error) -> Option<Duration>;
+}
+
+impl<F> RetryPolicy for F
+where
+    F: Fn(usize, &crate::error::Error) -> Option<Duration> + Send + Sync,
+{
+    fn decide(&self, attempt: usize, error: &crate::error::Error) -> Option<Duration> {
+        self(attempt, error)
+    }
+}
+
+/// Retries failed requests with exponentially growing delays between attempts
+///
+/// Only transient failures are retried, i.e. errors of [Network](crate::Kind::Network),
+/// [RateLimited](crate::Kind::RateLimited), [Timeout](crate::Kind::Timeout),
+/// [ServerError](crate::Kind::ServerError) and [Stalled](crate::Kind::Stalled) kind.
+/// When the server asked to wait with a [RateLimit](crate::RateLimit), the delay is at least as long,
+/// up to the [maximum delay](ExponentialBackoff::with_max_delay).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExponentialBackoff {
+    max_attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        ExponentialBackoff::new(3, Duration::from_millis(500))
+    }
+}
+
+impl ExponentialBackoff {
+    /// Makes at most `max_attempts` attempts (including the first one), waiting `base_delay`
+    /// before the first retry and twice as long before each subsequent one.
+    /// A `max_attempts` of 0 is the same as 1, i.e. requests aren't retried.
+    pub fn new(max_attempts: usize, base_delay: Duration) -> ExponentialBackoff {
+        ExponentialBackoff {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+
+    /// Caps the delay between attempts, 30 seconds by default
+    pub fn with_max_delay(mut self, max_delay: Duration) -> ExponentialBackoff {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Whether delays are randomly shortened by up to a half, so that concurrent requests
+    /// that failed at the same time don't retry at the same time, enabled by default
+    pub fn with_jitter(mut self, jitter: bool) -> ExponentialBackoff {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns how long to wait before retrying after a given failed attempt (starting at 1),
+    /// or None if the request shouldn't be retried
+    pub(crate) fn retry_delay(
+        &self,
+        attempt: usize,
+        error: &crate::error::Error,
+    ) -> Option<Duration> {
+        let transient = matches!(
+            error.kind,
+            Kind::Network | Kind::RateLimited | Kind::Timeout | Kind::ServerError | Kind::Stalled
+        );
+        if !transient || attempt >= self.max_attempts {
+            return None;
+        }
+
+        let exponent = (attempt - 1).min(31) as u32;
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_delay);
+
+        let delay = if self.jitter {
+            rand::thread_rng().gen_range(delay / 2..=delay)
+        } else {
+            delay
+        };
+
+        let retry_after = error
+            .inner
+            .downcast_ref::<RateLimit>()
+            .and_then(|e| e.retry_after);
+        Some(retry_after.map_or(delay, |e| e.max(delay).min(self.max_delay)))
+    }
+}
+
+impl RetryPolicy for Box<dyn RetryPolicy> {
+    fn decide(&self, attempt: usize, error: &crate::error::Error) -> Option<Duration> {
+        self.as_ref().decide(attempt, error)
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn decide(&self, attempt: usize, error: &crate::error::Error) -> Option<Duration> {
+        self.retry_delay(attempt, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use super::ExponentialBackoff;
+    use crate::{
+        data_supplier::tests::TestResourceDataSupplier, DataSupplier, DukascopyService, Kind,
+        RateLimit,
+    };
+
+    struct FlakyDataSupplier {
+        failures: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DataSupplier for FlakyDataSupplier {
+        async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(crate::error::Error::new(Kind::Network, "connection reset"));
+            }
+
+            TestResourceDataSupplier {}.fetch(url).await
+        }
+    }
+
+    fn flaky_service(failures: usize) -> DukascopyService {
+        DukascopyService::new(
+            String::from(""),
+            Box::new(FlakyDataSupplier {
+                failures: AtomicUsize::new(failures),
+            }),
+        )
+        .with_retry(ExponentialBackoff::new(3, Duration::from_millis(1)))
+    }
+
+    #[test]
+    fn delays_grow_exponentially_up_to_max_delay() {
+        let policy = ExponentialBackoff::new(10, Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(350))
+            .with_jitter(false);
+        let error = crate::error::Error::new(Kind::Network, "");
+
+        let delays = (1..=4)
+            .map(|e| policy.retry_delay(e, &error))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(350)),
+                Some(Duration::from_millis(350)),
+            ]
+        );
+        assert_eq!(policy.retry_delay(10, &error), None);
+    }
+
+    #[test]
+    fn zero_attempts_never_retry() {
+        let policy = ExponentialBackoff::new(0, Duration::from_millis(100));
+        let error = crate::error::Error::new(Kind::Network, "");
+
+        assert_eq!(policy.retry_delay(1, &error), None);
+    }
+
+    #[test]
+    fn waits_as_long_as_the_server_asks() {
+        let policy = ExponentialBackoff::new(3, Duration::from_millis(100)).with_jitter(false);
+        let error = |retry_after| {
+            crate::error::Error::new(
+                Kind::RateLimited,
+                RateLimit {
+                    status: 429,
+                    retry_after,
+                },
+            )
+        };
+
+        assert_eq!(
+            policy.retry_delay(1, &error(Some(Duration::from_secs(5)))),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            policy.retry_delay(1, &error(None)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry_delay(1, &error(Some(Duration::from_secs(3600)))),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let service = flaky_service(2);
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(!ticks.is_empty());
+        assert!(ticks.iter().all(|e| e.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let service = flaky_service(3);
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(ticks.len(), 1);
+        assert!(matches!(ticks[0].as_ref().unwrap_err().kind, Kind::Network));
+    }
+
+    #[tokio::test]
+    async fn uses_custom_policies() {
+        let service = DukascopyService::new(
+            String::from(""),
+            Box::new(FlakyDataSupplier {
+                failures: AtomicUsize::new(5),
+            }),
+        )
+        .with_retry(|attempt, error: &crate::error::Error| {
+            matches!(error.kind, Kind::Network)
+                .then_some(Duration::from_millis(1))
+                .filter(|_| attempt <= 5)
+        });
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(!ticks.is_empty());
+        assert!(ticks.iter().all(|e| e.is_ok()));
+    }
+}
@@ -0,0 +1,116 @@
+use futures::{stream, Stream, StreamExt};
+
+use crate::{Tick, Timeframe};
+
+/// Which tick [downsample_ticks](downsample_ticks) keeps of each period
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SampleStrategy {
+    /// The first tick of each period, which is emitted as soon as it arrives
+    First,
+
+    /// The last tick of each period
+    Last,
+
+    /// The tick closest to the start of each period, either before or after it
+    Closest,
+}
+
+/// Reduces a stream of ticks to at most one tick per period of a given timeframe,
+/// e.g. to get second-level data without the full tick volume
+///
+/// Ticks are expected to be in chronological order. Periods without any ticks
+/// produce no ticks and errors are passed through as they come.
+pub fn downsample_ticks<S>(
+    ticks: S,
+    timeframe: Timeframe,
+    strategy: SampleStrategy,
+) -> impl Stream<Item = Result<Tick, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    let ticks = Box::pin(ticks.fuse());
+    let half_period = timeframe.millis() / 2;
+
+    // with the Closest strategy, a period contains the ticks closer to its start than to
+    // the start of any other period, i.e. it begins half a period earlier
+    let sample_point = move |tick: &Tick| match strategy {
+        SampleStrategy::First | SampleStrategy::Last => timeframe.period_start(tick.time),
+        SampleStrategy::Closest => timeframe.period_start(tick.time + half_period),
+    };
+
+    stream::unfold(
+        (ticks, None::<(i64, Tick)>),
+        move |(mut ticks, mut sample)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let point = sample_point(&tick);
+
+                        match sample.as_mut() {
+                            Some((current, kept)) if *current == point => {
+                                let replace = match strategy {
+                                    SampleStrategy::First => false,
+                                    SampleStrategy::Last => true,
+                                    SampleStrategy::Closest => {
+                                        (tick.time - point).abs() < (kept.time - point).abs()
+                                    }
+                                };
+                                if replace {
+                                    *kept = tick;
+                                }
+                            }
+                            _ if strategy == SampleStrategy::First => {
+                                return Some((Ok(tick), (ticks, Some((point, tick)))));
+                            }
+                            _ => {
+                                let finished = sample.replace((point, tick));
+                                if let Some((_, finished)) = finished {
+                                    return Some((Ok(finished), (ticks, sample)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, sample))),
+                    None if strategy == SampleStrategy::First => return None,
+                    None => return sample.take().map(|(_, e)| (Ok(e), (ticks, None))),
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{downsample_ticks, SampleStrategy};
+    use crate::{Tick, Timeframe};
+
+    fn sample(strategy: SampleStrategy) -> Vec<i64> {
+        let ticks = [100, 400, 900, 1_200, 1_700, 3_050].map(|time| {
+            Ok(Tick {
+                time,
+                ask: 1.1001,
+                bid: 1.1,
+                ask_volume: 1.0,
+                bid_volume: 1.0,
+            })
+        });
+
+        block_on(
+            downsample_ticks(stream::iter(ticks), Timeframe::seconds(1), strategy)
+                .map(|e| e.unwrap().time)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn keeps_a_tick_per_period() {
+        assert_eq!(sample(SampleStrategy::First), vec![100, 1_200, 3_050]);
+        assert_eq!(sample(SampleStrategy::Last), vec![900, 1_700, 3_050]);
+        assert_eq!(
+            sample(SampleStrategy::Closest),
+            vec![100, 900, 1_700, 3_050]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{stream, Stream, StreamExt};
+use time::Date;
+
+use crate::trading_hours::Session;
+use crate::Tick;
+
+fn in_session(session: Session, tick: &Tick) -> bool {
+    tick.datetime().is_some_and(|e| session.is_open(e))
+}
+
+/// Adds [filter_session](SessionExt::filter_session) to tick streams
+pub trait SessionExt: Stream<Item = Result<Tick, crate::error::Error>> + Sized {
+    /// Keeps only the ticks in a given [session](Session), e.g. London hours only.
+    /// Errors are passed through as they come.
+    fn filter_session(self, session: Session) -> FilterSession<Self> {
+        FilterSession {
+            stream: Box::pin(self),
+            session,
+        }
+    }
+}
+
+impl<S> SessionExt for S where S: Stream<Item = Result<Tick, crate::error::Error>> {}
+
+/// Stream returned by [filter_session](SessionExt::filter_session)
+pub struct FilterSession<S> {
+    stream: Pin<Box<S>>,
+    session: Session,
+}
+
+impl<S> Stream for FilterSession<S>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            return match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(tick))) if !in_session(this.session, &tick) => continue,
+                result => result,
+            };
+        }
+    }
+}
+
+/// Ticks of a single day's [session](Session), see [split_sessions](split_sessions)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionTicks {
+    pub session: Session,
+
+    /// Day of the session in its local time
+    pub date: Date,
+
+    pub ticks: Vec<Tick>,
+}
+
+/// Splits a stream of ticks into the ticks of each day's [session](Session),
+/// dropping the ticks outside of it
+///
+/// Ticks are expected to be in chronological order. Days without any ticks in the session
+/// produce nothing and errors are passed through as they come.
+pub fn split_sessions<S>(
+    ticks: S,
+    session: Session,
+) -> impl Stream<Item = Result<SessionTicks, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    let ticks = Box::pin(ticks.filter_session(session).fuse());
+
+    stream::unfold(
+        (ticks, None::<SessionTicks>),
+        move |(mut ticks, mut current)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        // ticks in the session always have a valid date time
+                        let date = session.local_time(tick.datetime().unwrap()).date();
+
+                        match current.as_mut() {
+                            Some(current) if current.date == date => current.ticks.push(tick),
+                            _ => {
+                                let finished = current.replace(SessionTicks {
+                                    session,
+                                    date,
+                                    ticks: vec![tick],
+                                });
+                                if let Some(finished) = finished {
+                                    return Some((Ok(finished), (ticks, current)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, current))),
+                    None => return current.take().map(|e| (Ok(e), (ticks, None))),
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+    use time::macros::{date, datetime};
+    use time::OffsetDateTime;
+
+    use super::{split_sessions, SessionExt};
+    use crate::trading_hours::Session;
+    use crate::Tick;
+
+    fn tick(time: OffsetDateTime) -> Result<Tick, crate::error::Error> {
+        Ok(Tick {
+            time: time.unix_timestamp() * 1000,
+            ask: 1.1001,
+            bid: 1.1,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        })
+    }
+
+    fn ticks() -> Vec<Result<Tick, crate::error::Error>> {
+        vec![
+            tick(datetime!(2020-07-16 06:00 UTC)),
+            tick(datetime!(2020-07-16 07:00 UTC)),
+            tick(datetime!(2020-07-16 15:59 UTC)),
+            tick(datetime!(2020-07-16 16:00 UTC)),
+            tick(datetime!(2020-07-17 10:00 UTC)),
+        ]
+    }
+
+    #[test]
+    fn keeps_ticks_in_session() {
+        let count = block_on(
+            stream::iter(ticks())
+                .filter_session(Session::London)
+                .count(),
+        );
+
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn splits_ticks_by_day() {
+        let days = block_on(
+            split_sessions(stream::iter(ticks()), Session::London)
+                .map(|e| e.unwrap())
+                .map(|e| (e.date, e.ticks.len()))
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            days,
+            vec![(date!(2020 - 07 - 16), 2), (date!(2020 - 07 - 17), 1)]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+use crate::error::Kind;
+use crate::Tick;
+
+/// What [filter_spikes](SpikeFilterExt::filter_spikes) does with the ticks it considers bad
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SpikeAction {
+    /// Bad ticks are silently removed from the stream
+    #[default]
+    Drop,
+
+    /// Bad ticks are replaced by errors of [Outlier](crate::Kind::Outlier) kind,
+    /// whose inner error is the [Spike](Spike) describing them
+    Flag,
+}
+
+/// Describes which ticks [filter_spikes](SpikeFilterExt::filter_spikes) considers bad
+///
+/// By default no tick is, enable the checks with [with_max_spread](SpikeFilter::with_max_spread)
+/// and [with_max_deviation](SpikeFilter::with_max_deviation).
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct SpikeFilter {
+    max_spread: Option<f64>,
+    max_deviation: Option<(usize, f64)>,
+    action: SpikeAction,
+}
+
+impl SpikeFilter {
+    pub fn new() -> SpikeFilter {
+        SpikeFilter::default()
+    }
+
+    /// Considers ticks with a spread above `max_spread` bad, as well as ticks whose ask is
+    /// below their bid. The spread is in price units, e.g. `0.005` is 50 pips of EURUSD.
+    pub fn with_max_spread(mut self, max_spread: f64) -> SpikeFilter {
+        self.max_spread = Some(max_spread);
+        self
+    }
+
+    /// Considers ticks whose mid price deviates from the median of the preceding `window`
+    /// good ticks by more than a fraction of it bad, e.g. `0.01` is 1%. Until `window`
+    /// good ticks are seen, no tick is considered bad by this check. The window is at least one tick.
+    pub fn with_max_deviation(mut self, window: usize, max_deviation: f64) -> SpikeFilter {
+        self.max_deviation = Some((window.max(1), max_deviation));
+        self
+    }
+
+    /// What happens with bad ticks, by default they're dropped
+    pub fn with_action(mut self, action: SpikeAction) -> SpikeFilter {
+        self.action = action;
+        self
+    }
+}
+
+/// Bad tick found by [filter_spikes](SpikeFilterExt::filter_spikes)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Spike {
+    pub tick: Tick,
+    pub reason: SpikeReason,
+}
+
+/// Why a tick is considered bad
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpikeReason {
+    /// The spread is above the limit or negative
+    Spread(f64),
+
+    /// The mid price deviates from the rolling median by a given fraction
+    Deviation(f64),
+}
+
+impl Display for Spike {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reason {
+            SpikeReason::Spread(spread) => {
+                write!(f, "tick at {} has a spread of {spread}", self.tick.time)
+            }
+            SpikeReason::Deviation(deviation) => write!(
+                f,
+                "tick at {} deviates from the median by {:.2}%",
+                self.tick.time,
+                deviation * 100.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Spike {}
+
+/// Adds [filter_spikes](SpikeFilterExt::filter_spikes) to tick streams
+pub trait SpikeFilterExt: Stream<Item = Result<Tick, crate::error::Error>> + Sized {
+    /// Drops or flags the ticks a given [SpikeFilter](SpikeFilter) considers bad,
+    /// e.g. obvious misprints. Errors are passed through as they come.
+    fn filter_spikes(self, filter: SpikeFilter) -> FilterSpikes<Self> {
+        FilterSpikes {
+            stream: Box::pin(self),
+            filter,
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+impl<S> SpikeFilterExt for S where S: Stream<Item = Result<Tick, crate::error::Error>> {}
+
+/// Stream returned by [filter_spikes](SpikeFilterExt::filter_spikes)
+pub struct FilterSpikes<S> {
+    stream: Pin<Box<S>>,
+    filter: SpikeFilter,
+    recent: VecDeque<f64>,
+}
+
+impl<S> FilterSpikes<S> {
+    fn check(&mut self, tick: &Tick) -> Option<SpikeReason> {
+        if let Some(max_spread) = self.filter.max_spread {
+            let spread = tick.spread();
+            if spread < 0.0 || spread > max_spread {
+                return Some(SpikeReason::Spread(spread));
+            }
+        }
+
+        if let Some((window, max_deviation)) = self.filter.max_deviation {
+            if self.recent.len() == window {
+                let mut sorted = self.recent.iter().copied().collect::<Vec<_>>();
+                sorted.sort_by(f64::total_cmp);
+                let median = sorted[sorted.len() / 2];
+
+                let deviation = ((tick.mid() - median) / median).abs();
+                if deviation > max_deviation {
+                    return Some(SpikeReason::Deviation(deviation));
+                }
+
+                self.recent.pop_front();
+            }
+            self.recent.push_back(tick.mid());
+        }
+
+        None
+    }
+}
+
+impl<S> Stream for FilterSpikes<S>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            return match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(tick))) => match this.check(&tick) {
+                    None => Poll::Ready(Some(Ok(tick))),
+                    Some(_) if this.filter.action == SpikeAction::Drop => continue,
+                    Some(reason) => Poll::Ready(Some(Err(crate::error::Error::new(
+                        Kind::Outlier,
+                        Spike { tick, reason },
+                    )))),
+                },
+                result => result,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{Spike, SpikeAction, SpikeFilter, SpikeFilterExt, SpikeReason};
+    use crate::{Kind, Tick};
+
+    fn tick(time: i64, bid: f64, spread: f64) -> Tick {
+        Tick {
+            time,
+            ask: bid + spread,
+            bid,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        }
+    }
+
+    fn ticks() -> Vec<Result<Tick, crate::error::Error>> {
+        vec![
+            Ok(tick(0, 1.1000, 0.0001)),
+            Ok(tick(1, 1.1001, 0.0001)),
+            Ok(tick(2, 1.1002, 0.0001)),
+            Ok(tick(3, 1.1003, 0.0100)),
+            Ok(tick(4, 11.003, 0.0001)),
+            Ok(tick(5, 1.1004, 0.0001)),
+        ]
+    }
+
+    #[test]
+    fn drops_wide_spreads_and_deviating_prices() {
+        let filter = SpikeFilter::new()
+            .with_max_spread(0.005)
+            .with_max_deviation(3, 0.05);
+
+        let times = block_on(
+            stream::iter(ticks())
+                .filter_spikes(filter)
+                .map(|e| e.unwrap().time)
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(times, vec![0, 1, 2, 5]);
+    }
+
+    #[test]
+    fn flags_spikes_as_errors() {
+        let filter = SpikeFilter::new()
+            .with_max_spread(0.005)
+            .with_action(SpikeAction::Flag);
+
+        let results = block_on(
+            stream::iter(ticks())
+                .filter_spikes(filter)
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(results.len(), 6);
+        let error = results[3].as_ref().unwrap_err();
+        assert_eq!(error.kind, Kind::Outlier);
+
+        let spike = error.inner.downcast_ref::<Spike>().unwrap();
+        assert_eq!(spike.tick.time, 3);
+        assert!(matches!(spike.reason, SpikeReason::Spread(_)));
+    }
+}
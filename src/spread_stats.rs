@@ -0,0 +1,152 @@
+use futures::{stream, Stream, StreamExt};
+
+use crate::{Tick, Timeframe};
+
+/// Statistics of spreads and activity over a period of time, see [spread_stats](spread_stats)
+///
+/// Spreads are in price units, divide them by [pip_size](crate::Instrument::pip_size)
+/// to get them in pips.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpreadStats {
+    /// Start of the period, in the same unit as [Tick::time](Tick::time)
+    pub time: i64,
+
+    /// Number of ticks in the period
+    pub ticks: u64,
+
+    /// Ask and bid volumes summed up
+    pub volume: f64,
+
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+
+    /// 50th percentile of the spreads
+    pub median: f64,
+
+    /// 95th percentile of the spreads
+    pub p95: f64,
+
+    /// 99th percentile of the spreads
+    pub p99: f64,
+}
+
+impl SpreadStats {
+    fn from_spreads(time: i64, mut spreads: Vec<f64>, volume: f64) -> SpreadStats {
+        spreads.sort_by(f64::total_cmp);
+
+        // nearest-rank percentile, spreads are never empty
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * spreads.len() as f64).ceil() as usize;
+            spreads[rank.clamp(1, spreads.len()) - 1]
+        };
+
+        SpreadStats {
+            time,
+            ticks: spreads.len() as u64,
+            volume,
+            min: spreads[0],
+            mean: spreads.iter().sum::<f64>() / spreads.len() as f64,
+            max: spreads[spreads.len() - 1],
+            median: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        }
+    }
+}
+
+/// Summarizes a stream of ticks into [spread statistics](SpreadStats) over periods
+/// of a given timeframe, e.g. [hourly](Timeframe::H1) or [daily](Timeframe::D1),
+/// to evaluate the liquidity of an instrument without storing its ticks
+///
+/// Ticks are expected to be in chronological order. Periods without any ticks
+/// produce no statistics and errors are passed through as they come.
+pub fn spread_stats<S>(
+    ticks: S,
+    timeframe: Timeframe,
+) -> impl Stream<Item = Result<SpreadStats, crate::error::Error>>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>>,
+{
+    let ticks = Box::pin(ticks.fuse());
+
+    stream::unfold(
+        (ticks, None::<(i64, Vec<f64>, f64)>),
+        move |(mut ticks, mut period)| async move {
+            loop {
+                match ticks.next().await {
+                    Some(Ok(tick)) => {
+                        let start = timeframe.period_start(tick.time);
+                        let volume = tick.ask_volume + tick.bid_volume;
+
+                        match period.as_mut() {
+                            Some((current, spreads, total)) if *current == start => {
+                                spreads.push(tick.spread());
+                                *total += volume;
+                            }
+                            _ => {
+                                let finished = period.replace((start, vec![tick.spread()], volume));
+                                if let Some((time, spreads, volume)) = finished {
+                                    let stats = SpreadStats::from_spreads(time, spreads, volume);
+                                    return Some((Ok(stats), (ticks, period)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), (ticks, period))),
+                    None => {
+                        return period.take().map(|(time, spreads, volume)| {
+                            let stats = SpreadStats::from_spreads(time, spreads, volume);
+                            (Ok(stats), (ticks, None))
+                        })
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::{spread_stats, SpreadStats};
+    use crate::{Tick, Timeframe};
+
+    #[test]
+    fn summarizes_spreads_per_period() {
+        let ticks = (0..20).map(|e| {
+            Ok(Tick {
+                time: e * 200,
+                ask: 100.0 + (e % 10 + 1) as f64,
+                bid: 100.0,
+                ask_volume: 1.0,
+                bid_volume: 0.5,
+            })
+        });
+
+        let stats = block_on(
+            spread_stats(stream::iter(ticks), Timeframe::seconds(2))
+                .map(|e| e.unwrap())
+                .collect::<Vec<SpreadStats>>(),
+        );
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].time, 2_000);
+        assert_eq!(
+            stats[0],
+            SpreadStats {
+                time: 0,
+                ticks: 10,
+                volume: 15.0,
+                min: 1.0,
+                mean: 5.5,
+                max: 10.0,
+                median: 5.0,
+                p95: 10.0,
+                p99: 10.0,
+            }
+        );
+    }
+}
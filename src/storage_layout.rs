@@ -0,0 +1,100 @@
+use std::fmt::Debug;
+
+/// Maps the files of the datafeed to the paths they're stored under on disk, used consistently by
+/// [DiskCache](crate::DiskCache), [FileSystemDataSupplier](crate::FileSystemDataSupplier) and
+/// [RecordingDataSupplier](crate::RecordingDataSupplier), so a directory written with a layout
+/// can be read with the same one
+pub trait StorageLayout: Debug + Send + Sync {
+    /// Returns the path a file is stored under, relative to the storage's directory
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the file relative to the datafeed's base URL, e.g. `EURUSD/2020/02/12/10h_ticks.bi5`
+    fn storage_path(&self, path: &str) -> String;
+}
+
+/// Stores files using the same paths as the datafeed, e.g. `EURUSD/2020/02/12/10h_ticks.bi5`
+/// for an hour of March 12th, as months are counted from zero. This is the default layout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DatafeedLayout;
+
+/// Stores files under `{instrument}/{yyyy}/{mm}/{dd}/` with months counted from one,
+/// e.g. `EURUSD/2020/03/12/10h_ticks.bi5` for an hour of March 12th
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CalendarLayout;
+
+/// Stores files under hashes of their paths, spread over 256 directories,
+/// e.g. `3f/3f2a11c07d5e9b04.bi5`, so no directory ends up with too many entries
+///
+/// The hash is 64-bit FNV-1a, which stays the same across versions and platforms.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashedLayout;
+
+impl StorageLayout for DatafeedLayout {
+    fn storage_path(&self, path: &str) -> String {
+        path.to_string()
+    }
+}
+
+impl StorageLayout for CalendarLayout {
+    fn storage_path(&self, path: &str) -> String {
+        let mut parts = path.split('/').map(String::from).collect::<Vec<_>>();
+
+        // the month is only a part of paths of daily files and hours, e.g. the yearly
+        // candles are stored as EURUSD/2020/BID_candles_day_1.bi5
+        if parts.len() >= 4 {
+            if let Ok(month) = parts[2].parse::<u8>() {
+                parts[2] = format!("{:02}", month + 1);
+            }
+        }
+
+        parts.join("/")
+    }
+}
+
+impl StorageLayout for HashedLayout {
+    fn storage_path(&self, path: &str) -> String {
+        let hash = path.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        let extension = path
+            .rsplit_once('.')
+            .filter(|(_, e)| !e.contains('/'))
+            .map_or(String::new(), |(_, e)| format!(".{e}"));
+
+        format!("{:02x}/{hash:016x}{extension}", hash >> 56)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CalendarLayout, DatafeedLayout, HashedLayout, StorageLayout};
+
+    #[test]
+    fn maps_datafeed_paths() {
+        let path = "EURUSD/2020/02/12/10h_ticks.bi5";
+
+        assert_eq!(DatafeedLayout.storage_path(path), path);
+        assert_eq!(
+            CalendarLayout.storage_path(path),
+            "EURUSD/2020/03/12/10h_ticks.bi5"
+        );
+        assert_eq!(
+            CalendarLayout.storage_path("EURUSD/2020/11/BID_candles_hour_1.bi5"),
+            "EURUSD/2020/12/BID_candles_hour_1.bi5"
+        );
+        assert_eq!(
+            CalendarLayout.storage_path("EURUSD/2020/BID_candles_day_1.bi5"),
+            "EURUSD/2020/BID_candles_day_1.bi5"
+        );
+
+        let hashed = HashedLayout.storage_path(path);
+        assert_eq!(hashed, HashedLayout.storage_path(path));
+        assert_ne!(
+            hashed,
+            HashedLayout.storage_path("EURUSD/2020/02/12/11h_ticks.bi5")
+        );
+        assert!(hashed.ends_with(".bi5"));
+        assert_eq!(hashed[..2], hashed[3..5]);
+    }
+}
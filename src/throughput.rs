@@ -0,0 +1,161 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::Stream;
+
+use crate::Tick;
+
+/// Throughput of a tick stream measured over a single reporting interval
+///
+/// Only ticks are counted, since that's all the stream sees. Compressed bytes downloaded
+/// are reported by [Progress](crate::Progress) instead.
+#[derive(Debug, Copy, Clone)]
+pub struct ThroughputReport {
+    /// Length of the interval the report covers
+    pub elapsed: Duration,
+
+    /// Number of ticks that passed through the stream during the interval
+    pub ticks: u64,
+
+    /// Ticks that passed through the stream per second
+    pub ticks_per_second: f64,
+
+    /// Fraction of the interval (between 0 and 1) spent waiting for the stream to produce
+    /// ticks, i.e. on the network and decompression. The rest was spent by the consumer.
+    ///
+    /// A value close to 1 means the download is the bottleneck,
+    /// a value close to 0 means the consumer (e.g. a sink) is.
+    pub upstream_share: f64,
+}
+
+/// Adds [measure_throughput](ThroughputExt::measure_throughput) to tick streams
+pub trait ThroughputExt: Stream<Item = Result<Tick, crate::error::Error>> + Sized {
+    /// Measures throughput of the stream and periodically passes it to `report`.
+    ///
+    /// Reports are emitted as ticks pass through, once at least `interval` elapsed since
+    /// the previous report, and once more when the stream ends.
+    fn measure_throughput<F>(self, interval: Duration, report: F) -> MeasureThroughput<Self, F>
+    where
+        F: FnMut(ThroughputReport),
+    {
+        MeasureThroughput {
+            stream: self,
+            interval,
+            report,
+            interval_start: Instant::now(),
+            ticks: 0,
+            upstream_wait: Duration::ZERO,
+            waiting_since: None,
+            finished: false,
+        }
+    }
+}
+
+impl<S> ThroughputExt for S where S: Stream<Item = Result<Tick, crate::error::Error>> {}
+
+/// Stream returned by [measure_throughput](ThroughputExt::measure_throughput)
+pub struct MeasureThroughput<S, F> {
+    stream: S,
+    interval: Duration,
+    report: F,
+    interval_start: Instant,
+    ticks: u64,
+    upstream_wait: Duration,
+    waiting_since: Option<Instant>,
+    finished: bool,
+}
+
+impl<S, F> MeasureThroughput<S, F>
+where
+    F: FnMut(ThroughputReport),
+{
+    fn emit_report(&mut self, now: Instant) {
+        let elapsed = now - self.interval_start;
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+
+        (self.report)(ThroughputReport {
+            elapsed,
+            ticks: self.ticks,
+            ticks_per_second: self.ticks as f64 / seconds,
+            upstream_share: (self.upstream_wait.as_secs_f64() / seconds).min(1.0),
+        });
+
+        self.interval_start = now;
+        self.ticks = 0;
+        self.upstream_wait = Duration::ZERO;
+    }
+}
+
+impl<S, F> Stream for MeasureThroughput<S, F>
+where
+    S: Stream<Item = Result<Tick, crate::error::Error>> + Unpin,
+    F: FnMut(ThroughputReport) + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        let poll_start = Instant::now();
+        let result = Pin::new(&mut this.stream).poll_next(cx);
+        let now = Instant::now();
+
+        let waiting_since = *this.waiting_since.get_or_insert(poll_start);
+        if result.is_ready() {
+            this.upstream_wait += now - waiting_since;
+            this.waiting_since = None;
+        }
+
+        match result {
+            Poll::Ready(Some(item)) => {
+                if item.is_ok() {
+                    this.ticks += 1;
+                }
+                if now - this.interval_start >= this.interval {
+                    this.emit_report(now);
+                }
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => {
+                this.finished = true;
+                this.emit_report(now);
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use time::macros::datetime;
+
+    use crate::{data_supplier::tests::TestResourceDataSupplier, DukascopyService, ThroughputExt};
+
+    #[tokio::test]
+    async fn reports_all_ticks_that_passed_through() {
+        let service =
+            DukascopyService::new(String::from(""), Box::new(TestResourceDataSupplier {}));
+        let mut reports = Vec::new();
+
+        let count = Box::pin(service.download_ticks(
+            String::from("EURGBP"),
+            datetime!(2020-03-12 06:00),
+            datetime!(2020-03-12 09:00),
+        ))
+        .measure_throughput(Duration::from_secs(3600), |e| reports.push(e))
+        .count()
+        .await;
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].ticks, count as u64);
+        assert!(reports[0].upstream_share <= 1.0);
+    }
+}
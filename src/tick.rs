@@ -1,11 +1,18 @@
 use std::fmt::Display;
+use std::ops::Deref;
+use std::sync::Arc;
 
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime};
 
 /// Instrument's price change event
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tick {
+    /// Unix timestamp in milliseconds
+    ///
+    /// Up to version 0.1.2 this was (incorrectly) a timestamp in seconds with the millisecond
+    /// offset within the hour added to it, use [unix_timestamp](Tick::unix_timestamp)
+    /// where whole seconds are expected.
     pub time: i64,
 
     pub ask: f64,
@@ -14,16 +21,299 @@ pub struct Tick {
     pub bid_volume: f64,
 }
 
+impl Tick {
+    /// Returns the time of the tick as a unix timestamp in whole seconds
+    pub fn unix_timestamp(&self) -> i64 {
+        self.time.div_euclid(1000)
+    }
+
+    /// Returns the time of the tick in UTC, or None if it's out of the range supported by `time`
+    pub fn datetime(&self) -> Option<OffsetDateTime> {
+        millis_to_date_time(self.time)
+    }
+
+    /// Returns the time of the tick as a chrono date time, or None if it's out of the range supported by `chrono`
+    #[cfg(feature = "chrono")]
+    pub fn chrono_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::from_timestamp_millis(self.time)
+    }
+
+    /// Returns the time that passed between an earlier tick and this one
+    pub fn elapsed_since(&self, earlier: &Tick) -> Duration {
+        Duration::milliseconds(self.time - earlier.time)
+    }
+
+    /// Returns the price in the middle of ask and bid
+    pub fn mid(&self) -> f64 {
+        (self.ask + self.bid) / 2.0
+    }
+
+    /// Returns the difference between ask and bid
+    pub fn spread(&self) -> f64 {
+        self.ask - self.bid
+    }
+
+    /// Returns the spread in pips of a given size, see [Instrument::pip_size](crate::Instrument::pip_size)
+    pub fn spread_pips(&self, pip_size: f64) -> f64 {
+        self.spread() / pip_size
+    }
+}
+
+/// A [Tick](Tick) with prices exactly as stored in the datafeed's files, i.e. integers
+/// scaled by the instrument's [decimal factor](crate::Instrument::decimal_factor),
+/// for consumers that can't tolerate the rounding of floating point prices
+///
+/// E.g. an ask of `111815` with a decimal factor of `100000` is `1.11815`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawTick {
+    /// Unix timestamp in milliseconds
+    pub time: i64,
+
+    pub ask: u32,
+    pub bid: u32,
+    pub ask_volume: f32,
+    pub bid_volume: f32,
+}
+
+impl RawTick {
+    /// Converts the tick to a [Tick](Tick) with prices divided by a given decimal factor
+    pub fn to_tick(&self, decimal_factor: f64) -> Tick {
+        Tick {
+            time: self.time,
+            ask: self.ask as f64 / decimal_factor,
+            bid: self.bid as f64 / decimal_factor,
+            ask_volume: self.ask_volume as f64,
+            bid_volume: self.bid_volume as f64,
+        }
+    }
+}
+
+/// A [Tick](Tick) tagged with the instrument it belongs to, e.g. in a stream merging
+/// several instruments
+///
+/// The name is shared by all ticks of an instrument, so tagging doesn't allocate.
+/// Fields and methods of the tick are accessible directly through [Deref].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstrumentTick {
+    /// Name of the instrument, e.g. `EURUSD`
+    pub instrument: Arc<str>,
+
+    pub tick: Tick,
+}
+
+impl Deref for InstrumentTick {
+    type Target = Tick;
+
+    fn deref(&self) -> &Tick {
+        &self.tick
+    }
+}
+
+impl Display for InstrumentTick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<16} {}", self.instrument, self.tick)
+    }
+}
+
+/// Ticks of a single hour, see [download_hours](crate::DukascopyService::download_hours)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourBatch {
+    /// Start of the hour, in UTC
+    pub hour: PrimitiveDateTime,
+
+    /// Ticks of the hour in chronological order
+    pub ticks: Vec<Tick>,
+}
+
+/// Ticks of a single hour stored column by column, see
+/// [download_tick_batches](crate::DukascopyService::download_tick_batches)
+///
+/// All columns have the same length, the values at a given index make up a single tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickBatch {
+    /// Start of the hour, in UTC
+    pub hour: PrimitiveDateTime,
+
+    pub times: Vec<i64>,
+    pub asks: Vec<f64>,
+    pub bids: Vec<f64>,
+    pub ask_volumes: Vec<f64>,
+    pub bid_volumes: Vec<f64>,
+}
+
+impl TickBatch {
+    /// Returns the number of ticks in the batch
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Returns the tick at a given index, or None if it's out of bounds
+    pub fn get(&self, index: usize) -> Option<Tick> {
+        Some(Tick {
+            time: *self.times.get(index)?,
+            ask: self.asks[index],
+            bid: self.bids[index],
+            ask_volume: self.ask_volumes[index],
+            bid_volume: self.bid_volumes[index],
+        })
+    }
+
+    /// Returns an iterator over the ticks of the batch
+    pub fn iter(&self) -> impl Iterator<Item = Tick> + '_ {
+        (0..self.len()).filter_map(|e| self.get(e))
+    }
+}
+
+impl From<HourBatch> for TickBatch {
+    fn from(batch: HourBatch) -> TickBatch {
+        let ticks = &batch.ticks;
+
+        TickBatch {
+            hour: batch.hour,
+            times: ticks.iter().map(|e| e.time).collect(),
+            asks: ticks.iter().map(|e| e.ask).collect(),
+            bids: ticks.iter().map(|e| e.bid).collect(),
+            ask_volumes: ticks.iter().map(|e| e.ask_volume).collect(),
+            bid_volumes: ticks.iter().map(|e| e.bid_volume).collect(),
+        }
+    }
+}
+
+/// Converts a unix timestamp in milliseconds, as stored in [Tick::time](Tick::time)
+pub(crate) fn millis_to_date_time(millis: i64) -> Option<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000).ok()
+}
+
+/// Converts a chrono date time, or returns None if it's out of the range supported by `time`
+#[cfg(feature = "chrono")]
+pub(crate) fn chrono_to_date_time(time: chrono::DateTime<chrono::Utc>) -> Option<OffsetDateTime> {
+    let nanos = time.timestamp() as i128 * 1_000_000_000 + time.timestamp_subsec_nanos() as i128;
+
+    OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+}
+
 impl Display for Tick {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Ok(date_time) = OffsetDateTime::from_unix_timestamp(self.time) {
+        if let Some(date_time) = self.datetime() {
             write!(
                 f,
                 "{} {}\t\t{:<16} {:<16} {:<26} {:<26}",
-                date_time.date(), date_time.time(), self.ask, self.bid, self.ask_volume, self.bid_volume
+                date_time.date(),
+                date_time.time(),
+                self.ask,
+                self.bid,
+                self.ask_volume,
+                self.bid_volume
             )
         } else {
-            Err(std::fmt::Error::default())
+            Err(std::fmt::Error)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use approx::assert_abs_diff_eq;
+    use time::{macros::datetime, Duration};
+
+    use super::{HourBatch, InstrumentTick, Tick, TickBatch};
+
+    #[test]
+    fn computes_prices_and_time() {
+        let tick = Tick {
+            time: datetime!(2020-03-12 06:00:00.250 UTC).unix_timestamp() * 1000 + 250,
+            ask: 1.11815,
+            bid: 1.11812,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+
+        assert_abs_diff_eq!(tick.mid(), 1.118135, epsilon = 0.000_000_1);
+        assert_abs_diff_eq!(tick.spread_pips(0.0001), 0.3, epsilon = 0.000_000_1);
+        assert_eq!(
+            tick.datetime(),
+            Some(datetime!(2020-03-12 06:00:00.250 UTC))
+        );
+        assert_eq!(
+            tick.unix_timestamp(),
+            datetime!(2020-03-12 06:00 UTC).unix_timestamp()
+        );
+
+        let later = Tick {
+            time: tick.time + 1500,
+            ..tick
+        };
+        assert_eq!(later.elapsed_since(&tick), Duration::milliseconds(1500));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn converts_chrono_date_times() {
+        use chrono::TimeZone;
+
+        let time = chrono::Utc.with_ymd_and_hms(2020, 3, 12, 6, 0, 1).unwrap();
+        let tick = Tick {
+            time: time.timestamp_millis(),
+            ask: 1.11815,
+            bid: 1.11812,
+            ask_volume: 1.0,
+            bid_volume: 1.0,
+        };
+
+        assert_eq!(tick.chrono_datetime(), Some(time));
+        assert_eq!(
+            super::chrono_to_date_time(time),
+            Some(datetime!(2020-03-12 06:00:01 UTC))
+        );
+    }
+
+    #[test]
+    fn stores_batches_in_columns() {
+        let ticks = (0..3)
+            .map(|e| Tick {
+                time: e,
+                ask: 1.1 + e as f64,
+                bid: 1.0 + e as f64,
+                ask_volume: 2.0,
+                bid_volume: 3.0,
+            })
+            .collect::<Vec<_>>();
+
+        let batch = TickBatch::from(HourBatch {
+            hour: datetime!(1970-01-01 00:00),
+            ticks: ticks.clone(),
+        });
+
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.bids, vec![1.0, 2.0, 3.0]);
+        assert_eq!(batch.iter().collect::<Vec<_>>(), ticks);
+        assert_eq!(batch.get(3), None);
+    }
+
+    #[test]
+    fn tags_ticks_with_their_instrument() {
+        let tick = InstrumentTick {
+            instrument: "EURUSD".into(),
+            tick: Tick {
+                time: 1_584_000_000_123,
+                ask: 1.1002,
+                bid: 1.1,
+                ask_volume: 1.0,
+                bid_volume: 2.0,
+            },
+        };
+
+        assert!(Arc::ptr_eq(&tick.clone().instrument, &tick.instrument));
+        assert_eq!(tick.time, 1_584_000_000_123);
+        assert_abs_diff_eq!(tick.mid(), 1.1001, epsilon = 0.000_001);
+        assert!(tick.to_string().starts_with("EURUSD"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{Request, Response, StatusCode};
+use tower_service::Service;
+
+use crate::error::{BoxError, Kind};
+use crate::reqwest_data_supplier::{check_body, is_file, is_html, rate_limit_error};
+use crate::DataSupplier;
+
+/// A [DataSupplier](DataSupplier) sending requests through a [tower](https://docs.rs/tower) service,
+/// so that its retry, rate limiting, metrics and tracing middleware can be layered on top
+/// of any HTTP client
+///
+/// The service receives `GET` requests of the files and responds with their whole body,
+/// e.g. a hyper client wrapped in a layer collecting the body. Responses are handled
+/// the same way as by [ReqwestDataSupplier](crate::ReqwestDataSupplier): `404` means
+/// there's no data, throttling results in a [RateLimited](crate::Kind::RateLimited) error
+/// and pages returned instead of files are rejected. Errors of the service itself
+/// are [Network](crate::Kind::Network) errors.
+pub struct TowerDataSupplier<S> {
+    // services are cloned for each request as usual in tower, the mutex only makes
+    // the supplier shareable between threads without requiring the service to be Sync
+    service: Mutex<S>,
+}
+
+impl<S> TowerDataSupplier<S> {
+    pub fn new(service: S) -> TowerDataSupplier<S> {
+        TowerDataSupplier {
+            service: Mutex::new(service),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> DataSupplier for TowerDataSupplier<S>
+where
+    S: Service<Request<()>, Response = Response<Bytes>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+{
+    async fn fetch(&self, url: &str) -> Result<Option<Bytes>, crate::error::Error> {
+        let is_file = is_file(url.split(['?', '#']).next().unwrap_or_default());
+        let request = Request::get(url)
+            .body(())
+            .map_err(|e| crate::error::Error::new(Kind::InvalidArgument, e))?;
+
+        let mut service = self.service.lock().unwrap().clone();
+        futures::future::poll_fn(|cx| service.poll_ready(cx))
+            .await
+            .map_err(|e| crate::error::Error::new(Kind::Network, e))?;
+        let response = service
+            .call(request)
+            .await
+            .map_err(|e| crate::error::Error::new(Kind::Network, e))?;
+
+        let status = response.status();
+        // it is a valid case for the server to return a 404 - it means there were no events
+        // during the requested time interval
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if let Some(error) = rate_limit_error(status, response.headers()) {
+            return Err(error);
+        }
+        if !status.is_success() {
+            let kind = match status.is_server_error() {
+                true => Kind::ServerError,
+                false => Kind::Network,
+            };
+            return Err(crate::error::Error::new(
+                kind,
+                format!("server responded with {status}"),
+            ));
+        }
+
+        let is_html = is_html(response.headers());
+        check_body(is_file, is_html, response.into_body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::future::{ready, Ready};
+    use std::task::{Context, Poll};
+
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use http::{Request, Response, StatusCode};
+    use time::macros::datetime;
+    use tower_service::Service;
+
+    use super::TowerDataSupplier;
+    use crate::{DataSupplier, DukascopyService, Kind};
+
+    /// Serves the test resources, or responds with the status in the path if there's one
+    #[derive(Clone)]
+    struct ResourceService;
+
+    impl Service<Request<()>> for ResourceService {
+        type Response = Response<Bytes>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<Bytes>, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            let name = request.uri().path().rsplit('/').next().unwrap().to_string();
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("test-resources")
+                .join(&name);
+
+            let response = match (name.parse::<u16>(), std::fs::read(path)) {
+                (Ok(status), _) => Response::builder()
+                    .status(StatusCode::from_u16(status).unwrap())
+                    .body(Bytes::new()),
+                (_, Ok(bytes)) => Response::builder().body(Bytes::from(bytes)),
+                (_, Err(_)) => Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Bytes::new()),
+            };
+            ready(Ok(response.unwrap()))
+        }
+    }
+
+    #[tokio::test]
+    async fn downloads_through_tower_services() {
+        let service = DukascopyService::new(
+            String::from("http://localhost"),
+            Box::new(TowerDataSupplier::new(ResourceService)),
+        );
+
+        let ticks = service
+            .download_ticks(
+                String::from("EURGBP"),
+                datetime!(2020-03-12 06:00),
+                datetime!(2020-03-12 07:00),
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(!ticks.is_empty());
+        assert!(ticks.iter().all(|e| e.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn classifies_error_statuses() {
+        let supplier = TowerDataSupplier::new(ResourceService);
+
+        let mut kinds = Vec::new();
+        for status in [429, 503, 500, 400] {
+            let url = format!("http://localhost/{status}");
+            kinds.push(supplier.fetch(&url).await.unwrap_err().kind);
+        }
+
+        assert!(matches!(
+            kinds[..],
+            [
+                Kind::RateLimited,
+                Kind::RateLimited,
+                Kind::ServerError,
+                Kind::Network
+            ]
+        ));
+        assert!(supplier
+            .fetch("http://localhost/missing")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,42 @@
+//! Instrumentation of the download pipeline, which compiles to nothing
+//! unless the `tracing` feature is enabled
+
+use std::future::Future;
+
+use time::PrimitiveDateTime;
+
+/// Emits a `tracing` event at the debug level
+macro_rules! debug_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+/// Emits a `tracing` event at the warn level
+macro_rules! warn_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+    };
+}
+
+pub(crate) use {debug_event, warn_event};
+
+/// Runs a future within a span identifying the hour it downloads
+#[cfg(feature = "tracing")]
+pub(crate) fn in_hour_span<F: Future>(
+    instrument: &str,
+    hour: PrimitiveDateTime,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    use tracing::Instrument;
+
+    future.instrument(tracing::debug_span!("hour", instrument, %hour))
+}
+
+/// Runs a future within a span identifying the hour it downloads
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn in_hour_span<F: Future>(_instrument: &str, _hour: PrimitiveDateTime, future: F) -> F {
+    future
+}
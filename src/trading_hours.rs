@@ -0,0 +1,407 @@
+//! Weekly trading schedules of Dukascopy instruments
+//!
+//! Schedules are hour-granular, which matches the granularity of the files served by
+//! the datafeed. When in doubt a schedule errs on the side of being open, so an hour
+//! that may contain data is never considered closed.
+//!
+//! [Sessions](Session) describe the business hours of the major financial centres instead,
+//! e.g. to analyze or filter the ticks of a single session.
+
+use time::{Date, Duration, Month, OffsetDateTime, UtcOffset, Weekday};
+
+use crate::{AssetClass, Instrument, Kind};
+
+/// Local time of a financial centre, with daylight saving time taken into account
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeZone {
+    Utc,
+    NewYork,
+    London,
+
+    /// Central European time, e.g. Frankfurt or Paris
+    CentralEurope,
+    Tokyo,
+}
+
+impl TimeZone {
+    /// Returns the zone's UTC offset at a given time
+    pub fn offset_at(&self, time: OffsetDateTime) -> UtcOffset {
+        match self {
+            TimeZone::Utc => UtcOffset::UTC,
+            TimeZone::NewYork => new_york_offset(time),
+            TimeZone::London => london_offset(time),
+            TimeZone::CentralEurope => {
+                UtcOffset::from_whole_seconds(london_offset(time).whole_seconds() + 3600).unwrap()
+            }
+            TimeZone::Tokyo => UtcOffset::from_hms(9, 0, 0).unwrap(),
+        }
+    }
+}
+
+/// Describes when an instrument is traded, see [Instrument::trading_hours](Instrument::trading_hours)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TradingHours {
+    /// Continuous trading from Sunday 17:00 to Friday 17:00 New York time, e.g. currency pairs
+    Forex,
+
+    /// Same as [Forex](TradingHours::Forex) but with a daily one hour break at 17:00
+    /// New York time, e.g. spot metals and CFDs following CME futures such as the S&P 500
+    DailyBreak,
+
+    /// Trading on weekdays between `open` (inclusive) and `close` (exclusive) hours of the local
+    /// time of a given zone, e.g. index CFDs that follow their exchange's session
+    ///
+    /// Sessions spanning midnight aren't supported, a schedule with `open` not before `close`
+    /// is never open. Use [weekdays](TradingHours::weekdays) to have the hours validated.
+    Weekdays { zone: TimeZone, open: u8, close: u8 },
+
+    /// Trading around the clock, e.g. cryptocurrencies
+    Continuous,
+}
+
+impl TradingHours {
+    /// Returns the schedule most instruments of a given [AssetClass](AssetClass) follow
+    ///
+    /// Index and commodity CFDs follow the session of their exchange, which can't be told from
+    /// the asset class alone, so they are assumed to follow the [Forex](TradingHours::Forex) week.
+    /// Built-in instruments come with their exchange's schedule.
+    pub fn for_asset_class(asset_class: AssetClass) -> TradingHours {
+        match asset_class {
+            AssetClass::Metal => TradingHours::DailyBreak,
+            AssetClass::Crypto => TradingHours::Continuous,
+            AssetClass::Forex | AssetClass::Index | AssetClass::Commodity | AssetClass::Other => {
+                TradingHours::Forex
+            }
+        }
+    }
+
+    /// Creates a [Weekdays](TradingHours::Weekdays) schedule
+    ///
+    /// # Returned value
+    ///
+    /// * Ok - the schedule
+    /// * Err - of [InvalidArgument](crate::Kind::InvalidArgument) kind, when `open` isn't before
+    ///   `close` or `close` is after 24
+    pub fn weekdays(
+        zone: TimeZone,
+        open: u8,
+        close: u8,
+    ) -> Result<TradingHours, crate::error::Error> {
+        if open >= close || close > 24 {
+            return Err(crate::error::Error::new(
+                Kind::InvalidArgument,
+                format!("invalid trading hours {open}-{close}, open has to be before close"),
+            ));
+        }
+
+        Ok(TradingHours::Weekdays { zone, open, close })
+    }
+
+    /// Returns whether the instrument is traded at a given time
+    pub fn is_open(&self, time: OffsetDateTime) -> bool {
+        match *self {
+            TradingHours::Forex => is_forex_week(time),
+            TradingHours::DailyBreak => {
+                is_forex_week(time) && time.to_offset(new_york_offset(time)).hour() != 17
+            }
+            TradingHours::Weekdays { zone, open, close } => {
+                let time = time.to_offset(zone.offset_at(time));
+                !is_weekend(time.weekday()) && (open..close).contains(&time.hour())
+            }
+            TradingHours::Continuous => true,
+        }
+    }
+}
+
+/// Returns whether a given instrument is traded at a given time,
+/// according to its [trading hours](Instrument::trading_hours)
+pub fn is_open(instrument: &Instrument, time: OffsetDateTime) -> bool {
+    instrument.trading_hours.is_open(time)
+}
+
+/// Business hours of a major financial centre, in its local time with daylight saving
+/// time taken into account
+///
+/// Sessions overlap, e.g. London and New York are both open in the early afternoon UTC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Session {
+    /// 9:00 to 18:00 Tokyo time on weekdays
+    Tokyo,
+
+    /// 8:00 to 17:00 London time on weekdays
+    London,
+
+    /// 8:00 to 17:00 New York time on weekdays
+    NewYork,
+}
+
+impl Session {
+    pub const ALL: [Session; 3] = [Session::Tokyo, Session::London, Session::NewYork];
+
+    /// Returns whether the session is open at a given time
+    pub fn is_open(&self, time: OffsetDateTime) -> bool {
+        let local = self.local_time(time);
+        let (open, close) = match self {
+            Session::Tokyo => (9, 18),
+            Session::London | Session::NewYork => (8, 17),
+        };
+
+        !is_weekend(local.weekday()) && (open..close).contains(&local.hour())
+    }
+
+    /// Returns a given time in the session's local time
+    pub fn local_time(&self, time: OffsetDateTime) -> OffsetDateTime {
+        let offset = match self {
+            Session::Tokyo => TimeZone::Tokyo,
+            Session::London => TimeZone::London,
+            Session::NewYork => TimeZone::NewYork,
+        }
+        .offset_at(time);
+
+        time.to_offset(offset)
+    }
+}
+
+/// Returns the sessions open at a given time
+pub fn sessions_at(time: OffsetDateTime) -> Vec<Session> {
+    Session::ALL
+        .into_iter()
+        .filter(|e| e.is_open(time))
+        .collect()
+}
+
+fn is_forex_week(time: OffsetDateTime) -> bool {
+    let local = time.to_offset(new_york_offset(time));
+
+    match local.weekday() {
+        Weekday::Saturday => false,
+        Weekday::Sunday => local.hour() >= 17,
+        Weekday::Friday => local.hour() < 17,
+        _ => true,
+    }
+}
+
+fn is_weekend(weekday: Weekday) -> bool {
+    matches!(weekday, Weekday::Saturday | Weekday::Sunday)
+}
+
+/// Returns New York's UTC offset at a given time, taking daylight saving time into account
+fn new_york_offset(time: OffsetDateTime) -> UtcOffset {
+    let year = time.to_offset(UtcOffset::UTC).year();
+    // DST starts on the second Sunday of March at 2:00 EST
+    // and ends on the first Sunday of November at 2:00 EDT
+    let dst_start = nth_sunday(year, Month::March, 2).midnight().assume_utc() + Duration::hours(7);
+    let dst_end = nth_sunday(year, Month::November, 1).midnight().assume_utc() + Duration::hours(6);
+
+    if time >= dst_start && time < dst_end {
+        UtcOffset::from_hms(-4, 0, 0).unwrap()
+    } else {
+        UtcOffset::from_hms(-5, 0, 0).unwrap()
+    }
+}
+
+/// Returns London's UTC offset at a given time, taking daylight saving time into account
+fn london_offset(time: OffsetDateTime) -> UtcOffset {
+    let year = time.to_offset(UtcOffset::UTC).year();
+    // DST starts on the last Sunday of March and ends on the last Sunday of October, at 1:00 UTC
+    let dst_start = last_sunday(year, Month::March).midnight().assume_utc() + Duration::hours(1);
+    let dst_end = last_sunday(year, Month::October).midnight().assume_utc() + Duration::hours(1);
+
+    if time >= dst_start && time < dst_end {
+        UtcOffset::from_hms(1, 0, 0).unwrap()
+    } else {
+        UtcOffset::UTC
+    }
+}
+
+fn last_sunday(year: i32, month: Month) -> Date {
+    let last = Date::from_calendar_date(year, month.next(), 1).unwrap() - Duration::days(1);
+
+    last - Duration::days(last.weekday().number_days_from_sunday() as i64)
+}
+
+fn nth_sunday(year: i32, month: Month, n: i64) -> Date {
+    let first = Date::from_calendar_date(year, month, 1).unwrap();
+    let days_until_sunday = (7 - first.weekday().number_days_from_sunday() as i64) % 7;
+
+    first + Duration::days(days_until_sunday + 7 * (n - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{is_open, sessions_at, Session, TimeZone, TradingHours};
+    use crate::{AssetClass, Instrument, Kind};
+
+    fn instrument(name: &str) -> Instrument {
+        Instrument::lookup(name).unwrap()
+    }
+
+    #[test]
+    fn forex_week_follows_new_york_close() {
+        // summer time, New York is UTC-4
+        assert!(is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-07-10 20:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-07-10 21:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-07-11 12:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-07-12 20:00 UTC)
+        ));
+        assert!(is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-07-12 21:00 UTC)
+        ));
+
+        // winter time, New York is UTC-5
+        assert!(is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-02-14 21:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-02-14 22:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-02-16 21:00 UTC)
+        ));
+        assert!(is_open(
+            &instrument("EURUSD"),
+            datetime!(2020-02-16 22:00 UTC)
+        ));
+    }
+
+    #[test]
+    fn metals_have_a_daily_break() {
+        assert!(is_open(
+            &instrument("XAUUSD"),
+            datetime!(2020-02-12 21:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("XAUUSD"),
+            datetime!(2020-02-12 22:00 UTC)
+        ));
+        assert!(is_open(
+            &instrument("XAUUSD"),
+            datetime!(2020-02-12 23:00 UTC)
+        ));
+    }
+
+    #[test]
+    fn weekday_sessions_follow_local_time() {
+        let hours = TradingHours::Weekdays {
+            zone: TimeZone::CentralEurope,
+            open: 8,
+            close: 22,
+        };
+
+        // winter time, Frankfurt is UTC+1
+        assert!(!hours.is_open(datetime!(2020-03-12 06:00 UTC)));
+        assert!(hours.is_open(datetime!(2020-03-12 07:00 UTC)));
+        assert!(!hours.is_open(datetime!(2020-03-12 21:00 UTC)));
+        assert!(!hours.is_open(datetime!(2020-03-14 12:00 UTC)));
+
+        // summer time, Frankfurt is UTC+2
+        assert!(hours.is_open(datetime!(2020-07-16 06:00 UTC)));
+        assert!(!hours.is_open(datetime!(2020-07-16 20:00 UTC)));
+    }
+
+    #[test]
+    fn validates_weekday_hours() {
+        assert_eq!(
+            TradingHours::weekdays(TimeZone::London, 8, 17).unwrap(),
+            TradingHours::Weekdays {
+                zone: TimeZone::London,
+                open: 8,
+                close: 17,
+            }
+        );
+        assert!(TradingHours::weekdays(TimeZone::Utc, 0, 24).is_ok());
+
+        for (open, close) in [(8, 8), (22, 6), (0, 25)] {
+            let error = TradingHours::weekdays(TimeZone::London, open, close).unwrap_err();
+            assert_eq!(error.kind, Kind::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn sessions_follow_local_daylight_saving_time() {
+        // London switches to summer time a few weeks after New York
+        assert_eq!(
+            sessions_at(datetime!(2020-03-16 07:30 UTC)),
+            vec![Session::Tokyo]
+        );
+        assert_eq!(
+            sessions_at(datetime!(2020-03-16 09:30 UTC)),
+            vec![Session::London]
+        );
+        assert_eq!(
+            sessions_at(datetime!(2020-03-16 12:30 UTC)),
+            vec![Session::London, Session::NewYork]
+        );
+        assert_eq!(
+            sessions_at(datetime!(2020-07-16 07:30 UTC)),
+            vec![Session::Tokyo, Session::London]
+        );
+        assert!(!Session::NewYork.is_open(datetime!(2020-07-16 21:30 UTC)));
+        assert!(Session::NewYork.is_open(datetime!(2020-01-16 21:30 UTC)));
+        assert!(sessions_at(datetime!(2020-03-14 12:00 UTC)).is_empty());
+    }
+
+    #[test]
+    fn cryptocurrencies_are_always_open() {
+        assert!(is_open(
+            &instrument("BTCUSD"),
+            datetime!(2020-03-14 12:00 UTC)
+        ));
+    }
+
+    #[test]
+    fn asset_classes_have_their_own_schedules() {
+        assert_eq!(
+            TradingHours::for_asset_class(AssetClass::Crypto),
+            TradingHours::Continuous
+        );
+        assert_eq!(
+            TradingHours::for_asset_class(AssetClass::Metal),
+            instrument("XAGUSD").trading_hours
+        );
+        assert_eq!(
+            TradingHours::for_asset_class(AssetClass::Index),
+            TradingHours::Forex
+        );
+    }
+
+    #[test]
+    fn indices_follow_their_exchange() {
+        assert!(is_open(
+            &instrument("DEUIDXEUR"),
+            datetime!(2020-03-12 10:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("DEUIDXEUR"),
+            datetime!(2020-03-12 02:00 UTC)
+        ));
+        assert!(!is_open(
+            &instrument("USA500IDXUSD"),
+            datetime!(2020-03-12 21:00 UTC)
+        ));
+        assert!(is_open(
+            &instrument("USA500IDXUSD"),
+            datetime!(2020-03-12 02:00 UTC)
+        ));
+    }
+}